@@ -0,0 +1,135 @@
+use anyhow::{bail, Context, Result};
+use serde_json::Value;
+use tiny_keccak::{Hasher, Keccak};
+
+/// The first four bytes of the Keccak-256 hash of a signature like `deposit(uint256)`, which calls
+/// of that function start with.
+pub fn selector(signature: &str) -> [u8; 4] {
+    let mut hasher = Keccak::v256();
+    hasher.update(signature.as_bytes());
+    let mut hash = [0; 32];
+    hasher.finalize(&mut hash);
+    [hash[0], hash[1], hash[2], hash[3]]
+}
+
+/// The signatures of the functions in a JSON ABI, or in a compiler artifact with an `abi` key.
+pub fn signatures(abi: &Value) -> Vec<String> {
+    let entries = abi.get("abi").unwrap_or(abi).as_array();
+    entries
+        .into_iter()
+        .flatten()
+        .filter(|entry| entry.get("type").and_then(Value::as_str) == Some("function"))
+        .filter_map(|entry| {
+            let name = entry.get("name")?.as_str()?;
+            Some(format!("{}({})", name, parameters(entry.get("inputs")?)?))
+        })
+        .collect()
+}
+
+/// The canonical types of ABI parameters, with tuples spelled out as `(type,…)`.
+fn parameters(inputs: &Value) -> Option<String> {
+    let types = inputs.as_array()?.iter().map(|input| {
+        let ty = input.get("type")?.as_str()?;
+        match ty.strip_prefix("tuple") {
+            Some(array) => Some(format!(
+                "({}){}",
+                parameters(input.get("components")?)?,
+                array
+            )),
+            None => Some(ty.to_owned()),
+        }
+    });
+    Some(types.collect::<Option<Vec<_>>>()?.join(","))
+}
+
+/// The calldata of a call: the selector of the signature followed by the encoded arguments. Only
+/// static types are supported.
+pub fn calldata(signature: &str, args: &[String]) -> Result<Vec<u8>> {
+    let types = signature
+        .split_once('(')
+        .and_then(|(_, rest)| rest.strip_suffix(')'))
+        .with_context(|| format!("invalid signature {}", signature))?;
+    let types: Vec<&str> = types.split(',').filter(|ty| !ty.is_empty()).collect();
+    if types.len() != args.len() {
+        bail!(
+            "{} takes {} arguments, got {}",
+            signature,
+            types.len(),
+            args.len()
+        );
+    }
+    let mut data = selector(signature).to_vec();
+    for (ty, arg) in types.iter().zip(args) {
+        data.extend(word(ty.trim(), arg).with_context(|| format!("invalid {} {}", ty, arg))?);
+    }
+    Ok(data)
+}
+
+/// ABI-encodes a single argument of a static type.
+fn word(ty: &str, arg: &str) -> Result<[u8; 32]> {
+    let mut word = [0; 32];
+    if ty == "bool" {
+        word[31] = match arg {
+            "true" => 1,
+            "false" => 0,
+            _ => bail!("expected true or false"),
+        };
+    } else if ty == "address" || ty.starts_with("uint") {
+        let bytes = number(arg)?;
+        word[32 - bytes.len()..].copy_from_slice(&bytes);
+    } else if ty.starts_with("int") {
+        match arg.strip_prefix('-') {
+            Some(digits) => {
+                let value: i128 = -digits.parse::<i128>()?;
+                word = [0xff; 32];
+                word[16..].copy_from_slice(&value.to_be_bytes());
+            }
+            None => {
+                let bytes = number(arg)?;
+                word[32 - bytes.len()..].copy_from_slice(&bytes);
+            }
+        }
+    } else if ty.starts_with("bytes") && ty.len() > "bytes".len() {
+        let bytes = unhex(arg)?;
+        if bytes.len() > 32 {
+            bail!("more than 32 bytes");
+        }
+        word[..bytes.len()].copy_from_slice(&bytes);
+    } else {
+        bail!("only static types are supported, pass the calldata as data instead");
+    }
+    Ok(word)
+}
+
+/// The big-endian bytes of a decimal or `0x` hex number.
+fn number(arg: &str) -> Result<Vec<u8>> {
+    if arg.starts_with("0x") {
+        let bytes = unhex(arg)?;
+        if bytes.len() > 32 {
+            bail!("more than 32 bytes");
+        }
+        Ok(bytes)
+    } else {
+        Ok(arg.parse::<u128>()?.to_be_bytes().to_vec())
+    }
+}
+
+pub fn unhex(text: &str) -> Result<Vec<u8>> {
+    let digits = text.trim_start_matches("0x");
+    if !digits.is_ascii() {
+        bail!("invalid hex");
+    }
+    let digits = if digits.len() % 2 == 1 {
+        format!("0{}", digits)
+    } else {
+        digits.to_owned()
+    };
+    (0..digits.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&digits[i..i + 2], 16).context("invalid hex"))
+        .collect()
+}
+
+pub fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
@@ -0,0 +1,188 @@
+use std::{
+    collections::HashSet,
+    env,
+    io::{Cursor, Read},
+};
+
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+
+use crate::cli;
+
+/// A report stored as a CI artifact.
+///
+/// * `gh:<owner>/<repo>/<artifact>[/<file>][@<branch>]` is the latest GitHub Actions artifact of
+///   that name, authenticated with `GITHUB_TOKEN` or `GH_TOKEN`. The workflow, if given, is the
+///   workflow file name, e.g. `gas.yml`.
+/// * `gl:<project>:<file>[@<branch>]` is a file in the artifacts of the latest successful GitLab
+///   job on that branch, authenticated with `GITLAB_TOKEN` or `CI_JOB_TOKEN`. The workflow is the
+///   job name and is required.
+#[derive(Debug, Clone)]
+pub struct Artifact {
+    pub spec: String,
+    pub workflow: Option<String>,
+}
+
+impl Artifact {
+    pub fn fetch(&self) -> Result<Vec<u8>> {
+        let (spec, branch) = match self.spec.rsplit_once('@') {
+            Some((spec, branch)) => (spec, Some(branch)),
+            None => (self.spec.as_str(), None),
+        };
+        if let Some(spec) = spec.strip_prefix("gh:") {
+            github(spec, branch, self.workflow.as_deref())
+        } else if let Some(spec) = spec.strip_prefix("gl:") {
+            let job = self
+                .workflow
+                .as_deref()
+                .context("GitLab artifacts need the job name as --artifact-workflow")?;
+            gitlab(spec, branch.unwrap_or("main"), job)
+        } else {
+            bail!(
+                "unknown artifact store in {}, expected gh: or gl:",
+                self.spec
+            )
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct Artifacts {
+    artifacts: Vec<GithubArtifact>,
+}
+
+#[derive(Deserialize)]
+struct GithubArtifact {
+    expired: bool,
+    archive_download_url: String,
+    workflow_run: Option<WorkflowRun>,
+}
+
+#[derive(Deserialize)]
+struct WorkflowRuns {
+    workflow_runs: Vec<WorkflowRun>,
+}
+
+#[derive(Deserialize)]
+struct WorkflowRun {
+    id: u64,
+    head_branch: Option<String>,
+}
+
+fn github(spec: &str, branch: Option<&str>, workflow: Option<&str>) -> Result<Vec<u8>> {
+    let mut parts = spec.splitn(4, '/');
+    let (Some(owner), Some(repo), Some(name)) = (parts.next(), parts.next(), parts.next()) else {
+        bail!("expected gh:<owner>/<repo>/<artifact>, got gh:{}", spec);
+    };
+    let file = parts.next().unwrap_or(name);
+    let api = env::var("GITHUB_API_URL").unwrap_or_else(|_| "https://api.github.com".to_owned());
+    let token = cli::token("GITHUB_TOKEN")
+        .or_else(|_| env::var("GH_TOKEN"))
+        .context("GITHUB_TOKEN or GH_TOKEN must be set to download GitHub artifacts")?;
+    let get = |url: &str| {
+        ureq::get(url)
+            .set("Authorization", &format!("Bearer {}", token))
+            .set("Accept", "application/vnd.github+json")
+            .set("User-Agent", env!("CARGO_PKG_NAME"))
+            .call()
+            .with_context(|| format!("GitHub request to {} failed", url))
+    };
+
+    let runs: Option<HashSet<u64>> = match workflow {
+        Some(workflow) => {
+            let mut url = format!(
+                "{}/repos/{}/{}/actions/workflows/{}/runs?status=success&per_page=100",
+                api, owner, repo, workflow
+            );
+            if let Some(branch) = branch {
+                url.push_str(&format!("&branch={}", branch));
+            }
+            let runs: WorkflowRuns = serde_json::from_reader(get(&url)?.into_reader())
+                .context("unexpected response listing workflow runs")?;
+            Some(runs.workflow_runs.iter().map(|run| run.id).collect())
+        }
+        None => None,
+    };
+
+    // Artifacts are listed newest first.
+    let url = format!(
+        "{}/repos/{}/{}/actions/artifacts?name={}&per_page=100",
+        api, owner, repo, name
+    );
+    let artifacts: Artifacts = serde_json::from_reader(get(&url)?.into_reader())
+        .context("unexpected response listing artifacts")?;
+    let artifact = artifacts
+        .artifacts
+        .into_iter()
+        .filter(|artifact| !artifact.expired)
+        .find(|artifact| {
+            let run = artifact.workflow_run.as_ref();
+            let branch_matches = branch.is_none_or(|branch| {
+                run.and_then(|run| run.head_branch.as_deref()) == Some(branch)
+            });
+            let run_matches = runs
+                .as_ref()
+                .is_none_or(|runs| run.is_some_and(|run| runs.contains(&run.id)));
+            branch_matches && run_matches
+        })
+        .with_context(|| format!("no artifact found for gh:{}", spec))?;
+
+    let mut archive = vec![];
+    get(&artifact.archive_download_url)?
+        .into_reader()
+        .read_to_end(&mut archive)
+        .context("cannot download the artifact")?;
+    unzip(&archive, file)
+}
+
+/// Extracts `file` from a zip archive, or its only JSON file if there is no file of that name.
+fn unzip(archive: &[u8], file: &str) -> Result<Vec<u8>> {
+    let mut archive =
+        zip::ZipArchive::new(Cursor::new(archive)).context("the artifact is no zip archive")?;
+    let names = archive
+        .file_names()
+        .map(|name| name.map(|name| name.into_owned()))
+        .collect::<Result<Vec<String>, _>>()?;
+    let json: Vec<&String> = names
+        .iter()
+        .filter(|name| name.ends_with(".json"))
+        .collect();
+    let name = match names.iter().find(|name| *name == file) {
+        Some(name) => name,
+        None if json.len() == 1 => json[0],
+        None => bail!("artifact contains no file {}, only {:?}", file, names),
+    };
+
+    let mut bytes = vec![];
+    archive.by_name(name)?.read_to_end(&mut bytes)?;
+    Ok(bytes)
+}
+
+fn gitlab(spec: &str, branch: &str, job: &str) -> Result<Vec<u8>> {
+    let (project, file) = spec
+        .split_once(':')
+        .with_context(|| format!("expected gl:<project>:<file>, got gl:{}", spec))?;
+    let server = env::var("CI_SERVER_URL").unwrap_or_else(|_| "https://gitlab.com".to_owned());
+    let url = format!(
+        "{}/api/v4/projects/{}/jobs/artifacts/{}/raw/{}?job={}",
+        server,
+        project.replace('/', "%2F"),
+        branch,
+        file,
+        job
+    );
+    let request = match (cli::token("GITLAB_TOKEN"), env::var("CI_JOB_TOKEN")) {
+        (Ok(token), _) => ureq::get(&url).set("PRIVATE-TOKEN", &token),
+        (_, Ok(token)) => ureq::get(&url).set("JOB-TOKEN", &token),
+        _ => bail!("GITLAB_TOKEN or CI_JOB_TOKEN must be set to download GitLab artifacts"),
+    };
+
+    let mut bytes = vec![];
+    request
+        .call()
+        .with_context(|| format!("GitLab request to {} failed", url))?
+        .into_reader()
+        .read_to_end(&mut bytes)
+        .context("cannot download the artifact")?;
+    Ok(bytes)
+}
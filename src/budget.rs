@@ -0,0 +1,161 @@
+use std::fmt;
+
+use crate::comparison::{Comparison, Kind};
+
+/// An upper limit for the average gas of a single method or deployment.
+///
+/// Budgets are declared one per line, e.g. `MyContract.swap <= 180000` or
+/// `deployment MyVault <= 3_000_000`. Empty lines and lines starting with `#` are ignored.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Budget {
+    pub kind: Kind,
+    pub name: String,
+    pub limit: u64,
+}
+
+impl fmt::Display for Budget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            Kind::Deployment => write!(f, "deployment {} <= {}", self.name, self.limit),
+            Kind::Method => write!(f, "{} <= {}", self.name, self.limit),
+        }
+    }
+}
+
+pub fn parse(text: &str) -> Result<Vec<Budget>, String> {
+    let mut budgets = vec![];
+    for (index, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let budget = parse_line(line)
+            .ok_or_else(|| format!("line {}: invalid budget: {}", index + 1, line))?;
+        budgets.push(budget);
+    }
+    Ok(budgets)
+}
+
+fn parse_line(line: &str) -> Option<Budget> {
+    let (name, limit) = line.split_once("<=")?;
+    let limit = limit.trim().replace('_', "").parse().ok()?;
+    let (kind, name) = match name.trim().strip_prefix("deployment ") {
+        Some(name) => (Kind::Deployment, name.trim()),
+        None => (Kind::Method, name.trim()),
+    };
+    if name.is_empty() {
+        return None;
+    }
+    Some(Budget {
+        kind,
+        name: name.to_owned(),
+        limit,
+    })
+}
+
+#[derive(Debug)]
+pub enum Violation<'a> {
+    Exceeded { budget: &'a Budget, avg_gas: u64 },
+    Missing { budget: &'a Budget },
+}
+
+impl fmt::Display for Violation<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Violation::Exceeded { budget, avg_gas } => write!(
+                f,
+                "{}: {} gas exceeds the budget by {}",
+                budget,
+                avg_gas,
+                avg_gas - budget.limit
+            ),
+            Violation::Missing { budget } => write!(f, "{}: not found in the report", budget),
+        }
+    }
+}
+
+/// Checks the first file of the comparison against every budget.
+pub fn check<'a>(budgets: &'a [Budget], comparison: &Comparison) -> Vec<Violation<'a>> {
+    budgets
+        .iter()
+        .filter_map(|budget| {
+            let cell = comparison
+                .entries
+                .iter()
+                .find(|row| row.kind == budget.kind && row.name == budget.name)
+                .and_then(|row| row.cells.first()?.as_ref());
+            match cell {
+                Some(cell) if cell.avg_gas > budget.limit => Some(Violation::Exceeded {
+                    budget,
+                    avg_gas: cell.avg_gas,
+                }),
+                Some(_) => None,
+                None => Some(Violation::Missing { budget }),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{comparison::tests::compare, Config};
+
+    #[test]
+    fn parses_budgets() {
+        let budgets =
+            parse("# limits\n\nVault.deposit <= 120_000\n  deployment Vault <= 3000000  \n")
+                .unwrap();
+        assert_eq!(
+            budgets,
+            [
+                Budget {
+                    kind: Kind::Method,
+                    name: "Vault.deposit".to_owned(),
+                    limit: 120_000,
+                },
+                Budget {
+                    kind: Kind::Deployment,
+                    name: "Vault".to_owned(),
+                    limit: 3_000_000,
+                },
+            ]
+        );
+        assert_eq!(budgets[1].to_string(), "deployment Vault <= 3000000");
+        assert_eq!(
+            parse("Vault.deposit <= 1\nVault.withdraw < 2").unwrap_err(),
+            "line 2: invalid budget: Vault.withdraw < 2"
+        );
+        assert!(parse("<= 5").is_err());
+        assert!(parse("Vault.deposit <= 5%").is_err());
+    }
+
+    #[test]
+    fn checks_the_first_file() {
+        let comparison = compare(
+            &[
+                &[("Vault.deposit", &[100_000]), ("Vault.withdraw", &[60_000])],
+                &[("Vault.deposit", &[200_000])],
+            ],
+            &Config::default(),
+        );
+        let budgets = parse(
+            "Vault.deposit <= 100000\nVault.withdraw <= 50000\nVault.redeem <= 1\n\
+             deployment Vault.deposit <= 1",
+        )
+        .unwrap();
+        let violations: Vec<String> = check(&budgets, &comparison)
+            .iter()
+            .map(Violation::to_string)
+            .collect();
+        assert_eq!(
+            violations,
+            [
+                "Vault.withdraw <= 50000: 60000 gas exceeds the budget by 10000",
+                "Vault.redeem <= 1: not found in the report",
+                "deployment Vault.deposit <= 1: not found in the report",
+            ]
+        );
+        assert!(check(&budgets[..1], &comparison).is_empty());
+    }
+}
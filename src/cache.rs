@@ -0,0 +1,155 @@
+use std::{
+    collections::hash_map::DefaultHasher,
+    env, fs,
+    hash::{Hash, Hasher},
+    path::PathBuf,
+    sync::OnceLock,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::prices;
+
+static SETTINGS: OnceLock<Settings> = OnceLock::new();
+
+/// How the cache is used, from `--cache-ttl` and `--offline`.
+#[derive(Debug, Clone, Copy)]
+pub struct Settings {
+    /// Values that can change are fetched again after this long.
+    pub ttl: Duration,
+    /// Never fetch, use cached values of any age.
+    pub offline: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            ttl: Duration::from_secs(5 * 60),
+            offline: false,
+        }
+    }
+}
+
+/// Whether a value can change after it was fetched.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Expiry {
+    /// Fetched again after [`Settings::ttl`], e.g. prices.
+    Ttl,
+    /// Kept forever, e.g. mined transactions.
+    Never,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Stored {
+    key: String,
+    /// Seconds since the Unix epoch.
+    fetched: u64,
+    value: Value,
+}
+
+pub fn configure(settings: Settings) {
+    let _ = SETTINGS.set(settings);
+}
+
+fn settings() -> Settings {
+    SETTINGS.get().copied().unwrap_or_default()
+}
+
+/// Returns a fresh cached value or fetches it, falling back to an outdated one when the fetch
+/// fails. `what` names the value in messages. `null`, e.g. for a transaction that isn't mined yet,
+/// is not kept.
+pub fn get(
+    key: &str,
+    what: &str,
+    expiry: Expiry,
+    fetch: impl FnOnce() -> Result<Value>,
+) -> Result<Value> {
+    let settings = settings();
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let previous = load(key);
+    let age = |stored: &Stored| Duration::from_secs(now.saturating_sub(stored.fetched));
+    let fresh = |stored: &&Stored| expiry == Expiry::Never || age(stored) < settings.ttl;
+    if let Some(stored) = previous
+        .as_ref()
+        .filter(|stored| settings.offline || fresh(stored))
+    {
+        log::debug!("using {} from the cache", what);
+        return Ok(stored.value.clone());
+    }
+    if settings.offline {
+        bail!("{} is not cached, and --offline doesn't fetch it", what);
+    }
+    match fetch() {
+        Ok(value) => {
+            log::debug!("fetched {}", what);
+            if !value.is_null() {
+                save(&Stored {
+                    key: key.to_owned(),
+                    fetched: now,
+                    value: value.clone(),
+                });
+            }
+            Ok(value)
+        }
+        Err(e) => match previous {
+            Some(stored) => {
+                log::warn!(
+                    "cannot fetch {}, using the one from {} minutes ago: {:#}",
+                    what,
+                    age(&stored).as_secs() / 60,
+                    e
+                );
+                Ok(stored.value)
+            }
+            None => Err(e),
+        },
+    }
+}
+
+/// Sends a JSON-RPC request through the cache.
+pub fn call(rpc_url: &str, request: Value, expiry: Expiry) -> Result<Value> {
+    let key = format!("{} {} {}", rpc_url, request["method"], request["params"]);
+    let what = format!(
+        "the {} response",
+        request["method"].as_str().unwrap_or("JSON-RPC")
+    );
+    get(&key, &what, expiry, || prices::call(rpc_url, request))
+}
+
+fn path(key: &str) -> Option<PathBuf> {
+    let dir = match env::var_os("XDG_CACHE_HOME") {
+        Some(dir) => PathBuf::from(dir),
+        None => PathBuf::from(env::var_os("HOME")?).join(".cache"),
+    };
+    let mut hasher = DefaultHasher::new();
+    key.hash(&mut hasher);
+    let name = format!("{:016x}.json", hasher.finish());
+    Some(dir.join("eth-gas-delta").join(name))
+}
+
+fn load(key: &str) -> Option<Stored> {
+    let bytes = fs::read(path(key)?).ok()?;
+    let stored: Stored = serde_json::from_slice(&bytes).ok()?;
+    // The file may belong to another key with the same hash.
+    (stored.key == key).then_some(stored)
+}
+
+/// Writes a value, which is only an optimization, so failures are just logged.
+fn save(stored: &Stored) {
+    let Some(path) = path(&stored.key) else {
+        return;
+    };
+    let written = path
+        .parent()
+        .map_or(Ok(()), fs::create_dir_all)
+        .and_then(|()| fs::write(&path, serde_json::to_vec(stored).unwrap_or_default()));
+    if let Err(e) = written {
+        log::debug!("cannot write the cache {}: {}", path.display(), e);
+    }
+}
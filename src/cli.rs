@@ -0,0 +1,804 @@
+use std::env;
+
+use clap::{
+    builder::BoolishValueParser, ArgAction, CommandFactory, FromArgMatches, Parser, Subcommand,
+};
+
+use eth_gas_delta::{
+    comparison::{CompareTo, Network, SortKey},
+    gate::Limit,
+    output::{self, Align, AnnotationLevel, Format, ThemePreset},
+    stats::Outliers,
+    Aggregate, Parsers,
+};
+
+use crate::{
+    input::{Order, ReadOptions},
+    pager::Paging,
+};
+
+/// When to emit ANSI color codes.
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorChoice {
+    Auto,
+    Always,
+    Never,
+}
+
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+pub struct Args {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    // Running without a subcommand is the same as `diff`.
+    #[command(flatten)]
+    pub diff: DiffArgs,
+
+    #[command(flatten)]
+    pub global: GlobalArgs,
+}
+
+/// Prefix of the environment variables standing in for flags and integration tokens, e.g.
+/// `ETH_GAS_DELTA_FORMAT=json` for `--format json`.
+pub const ENV_PREFIX: &str = "ETH_GAS_DELTA_";
+
+impl Args {
+    /// Parses the command line, taking flags that are not given from their environment variables.
+    pub fn parse_with_env() -> Self {
+        let matches = Args::command_with_env().get_matches();
+        Args::from_arg_matches(&matches).unwrap_or_else(|e| e.exit())
+    }
+
+    /// The command with an `ETH_GAS_DELTA_<FLAG>` variable for every flag of every subcommand.
+    pub fn command_with_env() -> clap::Command {
+        fn with_env(command: clap::Command) -> clap::Command {
+            command
+                .mut_args(|arg| {
+                    if arg.is_positional() {
+                        return arg;
+                    }
+                    let name = format!("{}{}", ENV_PREFIX, arg.get_id().as_str().to_uppercase());
+                    let arg = arg.env(name).hide_env_values(true);
+                    // Lets switches be set like `ETH_GAS_DELTA_REVERSE=1`, not only `=true`.
+                    match arg.get_action() {
+                        ArgAction::SetTrue => arg.value_parser(BoolishValueParser::new()),
+                        _ => arg,
+                    }
+                })
+                .mut_subcommands(with_env)
+        }
+        with_env(Args::command())
+    }
+
+    pub fn into_command(self) -> (Command, GlobalArgs) {
+        (
+            self.command.unwrap_or(Command::Diff(Box::new(self.diff))),
+            self.global,
+        )
+    }
+}
+
+impl GlobalArgs {
+    pub fn read_options(&self) -> ReadOptions<'_> {
+        ReadOptions {
+            lenient: self.lenient,
+            max_samples: self.max_samples,
+            format: self.input_format,
+            network: self.network_key.as_deref(),
+            across_networks: self.across_networks,
+            exclude_intrinsic: self.exclude_intrinsic,
+            exclude_calldata: self.exclude_calldata,
+        }
+    }
+}
+
+// Options shared by all subcommands.
+#[derive(clap::Args, Debug)]
+pub struct GlobalArgs {
+    /// Config file [default: eth-gas-delta.toml if present]
+    #[arg(long, global = true)]
+    pub config: Option<String>,
+
+    /// Log more details to stderr: files read, entries skipped and timings; repeatable
+    #[arg(short, long, action = clap::ArgAction::Count, global = true)]
+    pub verbose: u8,
+
+    /// Log less, only errors or with `-qq` nothing
+    #[arg(short, long, action = clap::ArgAction::Count, global = true, conflicts_with = "verbose")]
+    pub quiet: u8,
+
+    /// Output format [default: table]
+    #[arg(long, value_enum, global = true)]
+    pub format: Option<Format>,
+
+    /// Render the comparison through this Handlebars template instead of a format
+    #[arg(long, value_name = "FILE", global = true, conflicts_with = "format")]
+    pub template: Option<String>,
+
+    /// When to color the output; `auto` colors a terminal unless `NO_COLOR` is set [default: auto]
+    #[arg(long, value_enum, value_name = "WHEN", global = true)]
+    pub color: Option<ColorChoice>,
+
+    /// Color theme, overriding the config file [default: default]
+    #[arg(long, value_enum, global = true)]
+    pub theme: Option<ThemePreset>,
+
+    /// File that each file's deltas are relative to [default: first]
+    #[arg(long, value_enum, global = true)]
+    pub compare_to: Option<CompareTo>,
+
+    /// Changes within this percentage count as unchanged [default: 0.1]
+    #[arg(long, value_name = "PERCENT", global = true)]
+    pub threshold_percent: Option<f64>,
+
+    /// Changes of at most this much gas count as unchanged [default: 0]
+    ///
+    /// Both thresholds have to be exceeded; they apply to the colors as well as to
+    /// `--fail-on-increase` without a value. `thresholds.contracts` in the config file overrides them
+    /// per contract or method.
+    #[arg(long, value_name = "GAS", global = true)]
+    pub threshold_gas: Option<u64>,
+
+    /// Base fee in gwei that the priority fee is added to, instead of a flat `--gas-price`
+    #[arg(long, value_name = "GWEI", global = true, conflicts_with_all = ["gas_price", "rpc_url"])]
+    pub base_fee: Option<f64>,
+
+    /// Priority fee in gwei paid on top of the base fee [default: 0, or from `--fee-percentile`]
+    #[arg(long, value_name = "GWEI", global = true, conflicts_with = "gas_price")]
+    pub priority_fee: Option<f64>,
+
+    /// Most that is paid per gas in gwei, capping the base fee plus the priority fee
+    #[arg(long, value_name = "GWEI", global = true, conflicts_with = "gas_price")]
+    pub max_fee: Option<f64>,
+
+    /// Use the base fee of the latest block from this JSON-RPC endpoint
+    ///
+    /// Prices are cached for `--cache-ttl`; when the endpoint can't be reached an older cached one
+    /// is used, or the cost columns are left out.
+    #[arg(long, value_name = "URL", global = true, conflicts_with = "gas_price")]
+    pub rpc_url: Option<String>,
+
+    /// Use the median of this percentile of the priority fees paid in the last 20 blocks, e.g. 50
+    #[arg(long, value_name = "PERCENTILE", global = true, requires = "rpc_url")]
+    pub fee_percentile: Option<f64>,
+
+    /// Use the current ETH price in USD from CoinGecko as `--eth-price`
+    #[arg(long, global = true, conflicts_with = "eth_price")]
+    pub live_eth_price: bool,
+
+    /// Seconds that prices and other API responses that can change are cached
+    ///
+    /// Mined transactions and their traces are cached for good. The cache is in
+    /// `$XDG_CACHE_HOME/eth-gas-delta`.
+    #[arg(long, value_name = "SECONDS", global = true, default_value_t = 300)]
+    pub cache_ttl: u64,
+
+    /// Only use cached prices and API responses, however old, failing where nothing is cached
+    #[arg(long, global = true)]
+    pub offline: bool,
+
+    /// L1 base fee in gwei, adding the L1 data fee of `--network` optimism, base or arbitrum to the
+    /// costs
+    ///
+    /// Calls are assumed to send 100 bytes of calldata unless `l1.calldata` or `l1.sizes` in the
+    /// config file say otherwise.
+    #[arg(long, value_name = "GWEI", global = true)]
+    pub l1_base_fee: Option<f64>,
+
+    /// L1 blob base fee in gwei, part of the data fee on OP Stack chains [default: 0]
+    #[arg(long, value_name = "GWEI", global = true, requires = "l1_base_fee")]
+    pub blob_base_fee: Option<f64>,
+
+    /// Block gas limit that the "% of block" values refer to [default: 30000000]
+    #[arg(long, value_name = "GAS", global = true)]
+    pub block_limit: Option<u64>,
+
+    /// Use the block gas limit of this chain
+    #[arg(long, value_enum, global = true, conflicts_with = "block_limit")]
+    pub network: Option<Network>,
+
+    /// Order of the entries [default: name]
+    #[arg(long, value_enum, global = true)]
+    pub sort: Option<SortKey>,
+
+    /// Reverse the sort order
+    #[arg(long, global = true)]
+    pub reverse: bool,
+
+    /// Only compare entries whose name (`Contract.method` or deployment) matches this regex
+    #[arg(long, value_name = "REGEX", global = true, value_parser = parse_regex)]
+    pub include: Vec<String>,
+
+    /// Leave out entries whose name matches this regex, e.g. `^Mock`
+    #[arg(long, value_name = "REGEX", global = true, value_parser = parse_regex)]
+    pub exclude: Vec<String>,
+
+    /// Combine overloaded methods into one entry per name
+    #[arg(long, global = true)]
+    pub collapse_overloads: bool,
+
+    /// Match contract and method names regardless of case
+    #[arg(long, global = true)]
+    pub ignore_case: bool,
+
+    /// Strip source paths from contract names, e.g. `contracts/v2/Vault.sol:Vault` to `Vault`
+    #[arg(long, global = true)]
+    pub strip_paths: bool,
+
+    /// Trim whitespace around names and remove it from signatures
+    #[arg(long, global = true)]
+    pub trim_names: bool,
+
+    /// Only show deployments
+    #[arg(long, global = true, conflicts_with = "methods_only")]
+    pub deployments_only: bool,
+
+    /// Only show methods
+    #[arg(long, global = true)]
+    pub methods_only: bool,
+
+    /// Hide entries that are within the margin in all files
+    #[arg(long, global = true)]
+    pub only_changed: bool,
+
+    /// Statistic compared for each entry [default: mean]
+    #[arg(long, value_enum, global = true)]
+    pub aggregate: Option<Aggregate>,
+
+    /// Leave out outliers before aggregating
+    ///
+    /// `iqr` drops samples more than 1.5 interquartile ranges outside the quartiles, a percentage
+    /// like `10%` drops that share of the samples from both the top and the bottom.
+    #[arg(long, value_name = "iqr|PERCENT", global = true)]
+    pub outliers: Option<Outliers>,
+
+    /// Treat deltas as unchanged unless a Mann-Whitney U test finds them significant at this level
+    ///
+    /// Only applies to entries with at least two samples in both files, e.g. 0.05.
+    #[arg(long, value_name = "ALPHA", global = true)]
+    pub significance: Option<f64>,
+
+    /// Show the 95% confidence interval of each delta, treating deltas whose interval spans zero
+    /// as unchanged
+    ///
+    /// Only applies to entries with at least two samples in both files.
+    #[arg(long, global = true)]
+    pub confidence: bool,
+
+    /// Count increases beyond this limit as warnings, e.g. `2%` or `5000` gas
+    ///
+    /// Warnings are colored apart from other increases and annotated as such, and make `diff`
+    /// exit with status 4 unless there are errors.
+    #[arg(long, value_name = "LIMIT", global = true)]
+    pub warn_at: Option<Limit>,
+
+    /// Count increases beyond this limit as errors, e.g. `10%`, making `diff` exit with status 3
+    #[arg(long, value_name = "LIMIT", global = true)]
+    pub error_at: Option<Limit>,
+
+    /// Warn about entries whose gas in the last file exceeds this percentage of the block gas
+    /// limit, e.g. 50
+    #[arg(long, value_name = "PERCENT", global = true)]
+    pub block_share: Option<f64>,
+
+    /// When to show the output in `$PAGER`, `less` by default
+    #[arg(long, value_enum, value_name = "WHEN", global = true, default_value_t)]
+    pub paging: Paging,
+
+    /// Leave out malformed entries and reports that can't be read with a warning instead of failing
+    #[arg(long, global = true)]
+    pub lenient: bool,
+
+    /// Keep at most N gas samples per entry, folding longer arrays into running statistics
+    ///
+    /// Saves memory with huge reports. Means, minimums, maximums and totals stay exact, percentiles
+    /// and medians are estimated from the kept samples. Has no effect with `--lenient`.
+    #[arg(long, value_name = "N", global = true)]
+    pub max_samples: Option<usize>,
+
+    /// Parse every report in this format instead of telling it from the content or the extension:
+    /// `hardhat`, `snapshot`, `tenderly`, `yaml` or `toml`
+    #[arg(long, value_name = "FORMAT", global = true, value_parser = parse_input_format)]
+    pub input_format: Option<&'static str>,
+
+    /// Network to read from reports that hold results for several, like `arbitrum`
+    #[arg(long, value_name = "NAME", global = true)]
+    pub network_key: Option<String>,
+
+    /// Compare the networks of reports holding results for several, with a column for each, e.g.
+    /// `gas.json#arbitrum`
+    #[arg(long, global = true, conflicts_with = "network_key")]
+    pub across_networks: bool,
+
+    /// Subtract the 21000 gas every transaction costs from each method call, to compare execution
+    /// gas only
+    #[arg(long, global = true)]
+    pub exclude_intrinsic: bool,
+
+    /// With --exclude-intrinsic, subtract the calldata gas estimated from the signature too
+    #[arg(long, global = true, requires = "exclude_intrinsic")]
+    pub exclude_calldata: bool,
+
+    /// Column label used instead of a file name, in the order of the files; repeatable
+    #[arg(long, global = true)]
+    pub label: Vec<String>,
+
+    /// Also write the output to this file, in the format its extension stands for; repeatable
+    ///
+    /// `.txt`, `.md`, `.json`, `.csv`, `.tsv`, `.html`, `.xml` (JUnit), `.sarif`, `.diff` and
+    /// `.xlsx` are recognized, other files get `--format` or else the table. `--template` applies to every
+    /// file. The table is still printed to the terminal.
+    #[arg(short, long, global = true)]
+    pub output: Vec<String>,
+
+    #[command(flatten)]
+    pub render: output::Options,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Compare gas usage across reports, relative to the first one
+    Diff(Box<DiffArgs>),
+    /// Compare many pairs of a baseline and a candidate, e.g. one per pull request, and summarize
+    /// them
+    Batch(BatchArgs),
+    /// Check a single report against per-entry gas budgets
+    Check(CheckArgs),
+    /// Show the gas usage of a single report
+    Report(ReportArgs),
+    /// Write a normalized baseline snapshot of a report
+    Snapshot(SnapshotArgs),
+    /// Combine the reports of parallel test shards into one report
+    Merge(MergeArgs),
+    /// Check report files for problems, e.g. when a comparison comes out empty
+    Doctor(DoctorArgs),
+    /// Run the gas reporting test suite on two git refs and compare the results
+    Run(RunArgs),
+    /// Store a report in a history database under a commit or other tag
+    Record(RecordArgs),
+    /// Show an entry's gas over the runs in a history database, e.g. `Vault.deposit`
+    Trend(TrendArgs),
+    /// Build a report from the receipts of mined transactions
+    ImportRpc(ImportRpcArgs),
+    /// Compare the changes of two branches, each against its own baseline
+    Versus(VersusArgs),
+    /// Break down the gas of a transaction by opcode and contract, or compare two transactions
+    Opcodes(OpcodesArgs),
+    /// Find entries whose gas varies between runs of the same code, which `--unstable` exempts
+    Flaky(FlakyArgs),
+    /// Serve the comparison as a web page on a local port, updated when the files change
+    Serve(ServeArgs),
+    /// Print a shell completion script, e.g. `source <(eth-gas-delta completions bash)`
+    Completions(CompletionsArgs),
+    /// Write man pages for the tool and each subcommand, e.g. when packaging a release
+    Man(ManArgs),
+}
+
+#[derive(clap::Args, Debug)]
+pub struct DiffArgs {
+    /// Report files, directories or glob patterns, the first one being the baseline
+    ///
+    /// Directories and patterns expand to their JSON files sorted by path. `-` reads from stdin,
+    /// `http://` and `https://` URLs are downloaded. Gzip and zstd compressed reports are
+    /// decompressed. `<label>=<file>` names the file's column, e.g. `main=base.json`. A file with a
+    /// JSON array of reports or one report per line (NDJSON) adds a column for each.
+    pub files: Vec<String>,
+
+    /// Read the files from a TOML manifest, placed before the other files
+    ///
+    /// Each `[[input]]` has a `path`, relative to the manifest, and optionally a `label`, a `role`
+    /// of `baseline` or `candidate`, a `format` and a `network` key. Baselines come first.
+    #[arg(long, value_name = "FILE")]
+    pub manifest: Option<String>,
+
+    /// Order of the files, after any `--base-ref`, `--baseline-artifact`, `--baseline-db` or
+    /// `--baseline`
+    #[arg(long, value_enum, default_value_t)]
+    pub order: Order,
+
+    /// Use a baseline stored in git as the first file, e.g. `main:gas-baseline.json`
+    #[arg(long, value_name = "REF:PATH")]
+    pub base_ref: Option<String>,
+
+    /// Use the latest CI artifact as the first file
+    ///
+    /// `gh:<owner>/<repo>/<artifact>[/<file>][@<branch>]` downloads from GitHub Actions using
+    /// `GITHUB_TOKEN`, `gl:<project>:<file>[@<branch>]` from GitLab using `GITLAB_TOKEN` or
+    /// `CI_JOB_TOKEN`.
+    #[arg(long, value_name = "SPEC", conflicts_with = "base_ref")]
+    pub baseline_artifact: Option<String>,
+
+    /// Use the latest run recorded with `record` as the first file, e.g. `gas.sqlite@main`
+    ///
+    /// The branch defaults to `main`.
+    #[arg(
+        long,
+        value_name = "DB[@BRANCH]",
+        conflicts_with_all = ["base_ref", "baseline_artifact"]
+    )]
+    pub baseline_db: Option<String>,
+
+    /// Pool several runs of the same commit into the first file
+    ///
+    /// The gas data of all runs is combined, so noisy entries are compared against their pooled
+    /// distribution. Takes all following values, so pass the other files before it.
+    #[arg(
+        long,
+        value_name = "FILE",
+        num_args = 1..,
+        conflicts_with_all = ["base_ref", "baseline_artifact", "baseline_db"]
+    )]
+    pub baseline: Vec<String>,
+
+    /// Workflow file (GitHub) or job name (GitLab) that produced the baseline artifact
+    #[arg(long, value_name = "NAME", requires = "baseline_artifact")]
+    pub artifact_workflow: Option<String>,
+
+    /// Add a column of `eth_estimateGas` results for the calls listed in this TOML file
+    ///
+    /// The file maps contract names to deployed addresses under `[contracts]` and lists
+    /// `[[calls]]` with a `method` like `Vault.deposit(uint256)` and its `args` or raw `data`. The
+    /// node is the file's `rpc_url` or `--rpc-url`.
+    #[arg(long, value_name = "FILE")]
+    pub estimate: Option<String>,
+
+    /// Add a column of the gas recently used on chain by a verified contract, e.g. `Vault=0x1234…`
+    ///
+    /// The transactions are fetched from Etherscan using `ETHERSCAN_API_KEY`, on the chain of
+    /// `--network`, and grouped by the functions of the contract's ABI.
+    #[arg(long, value_name = "NAME=ADDRESS")]
+    pub etherscan: Vec<String>,
+
+    /// Number of most recent transactions fetched per contract from Etherscan
+    #[arg(long, value_name = "N", default_value_t = 100)]
+    pub etherscan_transactions: usize,
+
+    /// File to compare the others to, by its position starting at 1 or by its path
+    ///
+    /// The file is moved to the first column. Defaults to the first file.
+    #[arg(long, value_name = "N|PATH")]
+    pub base: Option<String>,
+
+    /// Only show the columns of these files, by position starting at 1, counting from the end if
+    /// negative, or by name, e.g. `1,-2,-1`
+    ///
+    /// The other files are still compared, so any of them can be the baseline.
+    #[arg(
+        long,
+        value_name = "N|NAME",
+        value_delimiter = ',',
+        allow_hyphen_values = true,
+        conflicts_with = "update_baseline"
+    )]
+    pub columns: Vec<String>,
+
+    /// Show the overall change between every pair of files instead of the table
+    #[arg(long)]
+    pub matrix: bool,
+
+    /// Show a histogram of one entry's gas in every file, e.g. `Vault.deposit`
+    #[arg(long, value_name = "ENTRY")]
+    pub detail: Option<String>,
+
+    /// Show which tests' calls drive the change of a method, e.g. `Vault.deposit`
+    ///
+    /// Needs reports that attribute calls to tests, with a `tests` object mapping test names to
+    /// their gas samples on each method.
+    #[arg(long, value_name = "ENTRY", conflicts_with = "detail")]
+    pub by_test: Option<String>,
+
+    /// Show which calls of an entry changed between the first and the last file, e.g.
+    /// `Vault.deposit`
+    #[arg(long, value_name = "ENTRY", conflicts_with_all = ["detail", "by_test"])]
+    pub explain: Option<String>,
+
+    /// How `--explain` pairs up the calls of the two files
+    #[arg(long, value_enum, default_value_t, requires = "explain")]
+    pub align: Align,
+
+    /// Re-render whenever one of the files changes
+    #[arg(long)]
+    pub watch: bool,
+
+    /// Browse the comparison in an interactive terminal UI
+    #[arg(long, short, conflicts_with = "watch")]
+    pub interactive: bool,
+
+    /// Post a summary to this Slack/Discord webhook when any entry regressed
+    #[arg(long, value_name = "URL")]
+    pub notify_webhook: Option<String>,
+
+    /// Print GitHub Actions annotations for entries that regressed
+    #[arg(
+        long,
+        value_enum,
+        value_name = "LEVEL",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "warning"
+    )]
+    pub github_annotations: Option<AnnotationLevel>,
+
+    /// Build output of hardhat (`artifacts`) or foundry (`out`) to locate contracts and methods
+    /// in their Solidity sources, for GitHub annotations and SARIF
+    ///
+    /// Given twice, the first is the build of the first file and the second that of the last file.
+    /// Contracts whose bytecode changed while their gas stayed the same are then flagged, as the
+    /// tests may not run the new code, and so are contracts whose gas changed with identical
+    /// bytecode. Locations come from the second.
+    #[arg(long, value_name = "DIR")]
+    pub artifacts: Vec<String>,
+
+    /// Exit with status 3 if any entry increased by more than this (e.g. `2%` or `5000` gas)
+    ///
+    /// Without a value, `thresholds.fail_on_increase` from the config file is used, or else any
+    /// increase beyond the thresholds fails.
+    #[arg(long, value_name = "LIMIT", num_args = 0..=1, require_equals = true)]
+    pub fail_on_increase: Option<Option<Limit>>,
+
+    /// File listing entries whose gas varies between identical runs, one name per line
+    ///
+    /// Their changes are marked in the table and don't fail `--fail-on-increase` or the severity
+    /// thresholds. `flaky --save` writes such a file.
+    #[arg(long, value_name = "FILE")]
+    pub unstable: Option<String>,
+
+    /// Don't fail `--fail-on-increase` for methods with too few calls to be reliable
+    #[arg(long, requires = "fail_on_increase")]
+    pub skip_few_samples: bool,
+
+    /// Exit with status 3 if any entry exceeds the `--block-share` of the block gas limit, 50% by
+    /// default
+    #[arg(long)]
+    pub fail_near_block_limit: bool,
+
+    /// After showing the diff, rewrite the baseline file to match the last report
+    #[arg(long, conflicts_with_all = ["watch", "interactive"])]
+    pub update_baseline: bool,
+
+    /// Only update the entries whose increase stays within this limit (e.g. `2%` or `5000` gas),
+    /// keeping the baseline values of the others
+    #[arg(long, value_name = "LIMIT", requires = "update_baseline")]
+    pub approve: Option<Limit>,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct BatchArgs {
+    /// TOML file of the pairs
+    ///
+    /// Each `[[pair]]` has a `baseline` and a `candidate` path, relative to the file, and
+    /// optionally a `name`, by default the candidate's file name.
+    pub manifest: String,
+
+    /// Directory the comparison of each pair is written to, named after the pair, in `--format`
+    /// or else as a table; the summary goes to `summary.md`
+    #[arg(long, value_name = "DIR", default_value = "gas-batch")]
+    pub dir: String,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct CheckArgs {
+    pub report: String,
+
+    /// File declaring budgets like `MyContract.swap <= 180000`, one per line
+    #[arg(long, default_value = "gas-budgets.txt")]
+    pub budgets: String,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct ReportArgs {
+    pub report: String,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct SnapshotArgs {
+    pub report: String,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct RecordArgs {
+    pub report: String,
+
+    /// The history database, created if missing
+    #[arg(long, default_value = "gas.sqlite")]
+    pub db: String,
+
+    /// Commit or other name of the run; recording it again replaces the earlier run
+    #[arg(long)]
+    pub tag: String,
+
+    /// Branch of the run, by default the checked out git branch
+    #[arg(long)]
+    pub branch: Option<String>,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct TrendArgs {
+    pub entry: String,
+
+    /// The history database
+    #[arg(long, default_value = "gas.sqlite")]
+    pub db: String,
+
+    /// Number of most recent runs to show
+    #[arg(long, value_name = "N", default_value_t = 20)]
+    pub last: usize,
+
+    /// Only show runs of this branch
+    #[arg(long)]
+    pub branch: Option<String>,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct ImportRpcArgs {
+    /// JSON-RPC endpoint of the node
+    #[arg(value_name = "URL")]
+    pub node: String,
+
+    /// Hashes of the transactions
+    pub transactions: Vec<String>,
+
+    /// Also import all transactions to this contract in the blocks from `--from-block`
+    #[arg(long, requires = "from_block")]
+    pub address: Option<String>,
+
+    #[arg(long, value_name = "BLOCK", requires = "address")]
+    pub from_block: Option<u64>,
+
+    /// Last block to look at, by default the latest one
+    #[arg(long, value_name = "BLOCK", requires = "address")]
+    pub to_block: Option<u64>,
+
+    /// Name of a contract, e.g. `Vault=0x1234…`; others are named by their address
+    #[arg(long = "contract", value_name = "NAME=ADDRESS")]
+    pub contracts: Vec<String>,
+
+    /// JSON ABI or compiler artifact naming the functions; others are named by their selector
+    #[arg(long, value_name = "FILE")]
+    pub abi: Vec<String>,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct MergeArgs {
+    /// Report files, directories or glob patterns of the shards
+    #[arg(required = true)]
+    pub reports: Vec<String>,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct DoctorArgs {
+    /// Report files, directories or glob patterns to check
+    #[arg(required = true)]
+    pub reports: Vec<String>,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct RunArgs {
+    /// Git ref used as the baseline
+    #[arg(long)]
+    pub base: String,
+
+    /// Git ref to compare against the baseline [default: the current working tree]
+    #[arg(long)]
+    pub head: Option<String>,
+
+    /// Report file written by the command
+    #[arg(long, default_value = "gasReporterOutput.json")]
+    pub report_path: String,
+
+    /// Command producing the gas report, e.g. `npx hardhat test`
+    #[arg(last = true, required = true)]
+    pub command: Vec<String>,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct VersusArgs {
+    /// Baseline of the first branch
+    pub base_a: String,
+    /// Report of the first branch
+    pub head_a: String,
+    /// Baseline of the second branch, often the same as the first one's
+    pub base_b: String,
+    /// Report of the second branch
+    pub head_b: String,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct OpcodesArgs {
+    /// JSON-RPC endpoint of a node supporting `debug_traceTransaction`, e.g. anvil
+    #[arg(value_name = "URL")]
+    pub node: String,
+
+    /// Hash of the transaction
+    pub before: String,
+
+    /// Hash of a transaction to compare with the first one, e.g. the same call after a change
+    pub after: Option<String>,
+
+    /// Node the second transaction was sent to, if not the first one
+    #[arg(long, value_name = "URL", requires = "after")]
+    pub after_node: Option<String>,
+
+    /// Name of a contract, e.g. `Vault=0x1234…`; others are named by their address
+    #[arg(long = "contract", value_name = "NAME=ADDRESS")]
+    pub contracts: Vec<String>,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct FlakyArgs {
+    /// Reports of repeated runs of the same code, at least two
+    #[arg(required = true)]
+    pub files: Vec<String>,
+
+    /// Percentage by which an entry's average gas may vary between the runs
+    #[arg(long, value_name = "PERCENT", default_value_t = 0.5)]
+    pub threshold: f64,
+
+    /// Add the unstable entries to this file, keeping those listed before
+    #[arg(long, value_name = "FILE")]
+    pub save: Option<String>,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct ServeArgs {
+    /// Report files, directories or glob patterns, the first one being the baseline
+    #[arg(required = true)]
+    pub files: Vec<String>,
+
+    /// Address to listen on; port 0 picks a free one
+    #[arg(long, default_value = "127.0.0.1:8000")]
+    pub address: String,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct CompletionsArgs {
+    pub shell: clap_complete::Shell,
+}
+
+#[derive(clap::Args, Debug)]
+pub struct ManArgs {
+    /// Directory the pages are written to, created if missing
+    #[arg(long, default_value = "man")]
+    pub dir: String,
+}
+
+/// An integration token, preferring `ETH_GAS_DELTA_<NAME>` over the variable the service itself uses.
+pub fn token(name: &str) -> Result<String, env::VarError> {
+    env::var(format!("{}{}", ENV_PREFIX, name)).or_else(|_| env::var(name))
+}
+
+fn parse_input_format(name: &str) -> Result<&'static str, String> {
+    let parsers = Parsers::default();
+    parsers
+        .get(name)
+        .map(|parser| parser.name())
+        .ok_or_else(|| {
+            let names: Vec<_> = parsers.names().collect();
+            format!("expected one of {}", names.join(", "))
+        })
+}
+
+fn parse_regex(pattern: &str) -> Result<String, regex::Error> {
+    regex::Regex::new(pattern).map(|_| pattern.to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn switches_are_set_from_the_environment() {
+        let parse = || {
+            let matches = Args::command_with_env()
+                .try_get_matches_from(["eth-gas-delta", "a.json"])
+                .unwrap();
+            Args::from_arg_matches(&matches).unwrap()
+        };
+        env::set_var("ETH_GAS_DELTA_REVERSE", "1");
+        assert!(parse().global.reverse);
+        env::set_var("ETH_GAS_DELTA_REVERSE", "no");
+        assert!(!parse().global.reverse);
+        env::remove_var("ETH_GAS_DELTA_REVERSE");
+    }
+}
@@ -0,0 +1,1145 @@
+use std::{
+    cmp::Ordering,
+    collections::{HashMap, HashSet},
+    fmt,
+};
+
+use clap::ValueEnum;
+use itertools::Itertools;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::{self, Config},
+    gate::Limit,
+    report::{Entry, GasData},
+    stats::{self, Aggregate, Outliers},
+    GasReport,
+};
+
+pub const BLOCK_LIMIT: u64 = 30_000_000;
+/// Share of the block gas limit in percent above which `--fail-near-block-limit` fails by default.
+pub const BLOCK_SHARE: f64 = 50.0;
+/// Maximum size of deployed code in bytes since EIP-170.
+pub const CODE_SIZE_LIMIT: usize = 24_576;
+/// Share of the code size limit in percent from which a contract is flagged.
+pub const CODE_SIZE_WARNING: f64 = 90.0;
+pub const MARGIN: f64 = 0.1;
+/// Methods with fewer calls than this in any file are marked as unreliable.
+pub const MIN_SAMPLES: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Kind {
+    Deployment,
+    Method,
+}
+
+impl fmt::Display for Kind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Kind::Deployment => "deployment",
+            Kind::Method => "method",
+        })
+    }
+}
+
+/// Identifies an entry across reports: a deployment by its contract, a method by its contract and
+/// signature, or its name when overloads are collapsed.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct EntryId {
+    pub kind: Kind,
+    pub contract: String,
+    pub method: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Status {
+    Baseline,
+    Unchanged,
+    Decreased,
+    Increased,
+}
+
+/// How far an increase goes beyond `thresholds.warn` or `thresholds.error`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+/// How much an entry may change before it counts as changed. Changes have to exceed both limits.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Threshold {
+    pub percent: f64,
+    pub gas: u64,
+}
+
+impl Threshold {
+    pub fn status(&self, delta: i64, percent: f64) -> Status {
+        match percent {
+            _ if delta.unsigned_abs() <= self.gas => Status::Unchanged,
+            p if p > self.percent => Status::Increased,
+            p if p < -self.percent => Status::Decreased,
+            _ => Status::Unchanged,
+        }
+    }
+}
+
+/// Chain whose block gas limit the "% of block" values refer to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Network {
+    /// Ethereum mainnet, 60M
+    Mainnet,
+    /// OP Mainnet, 40M
+    Optimism,
+    /// Arbitrum One, 32M
+    Arbitrum,
+    /// Base, 150M
+    Base,
+    /// Polygon PoS, 45M
+    Polygon,
+    /// Gnosis Chain, 17M
+    Gnosis,
+}
+
+impl Network {
+    /// The block gas limit at the time of writing; `block_limit` overrides it once it changes.
+    pub fn block_limit(self) -> u64 {
+        match self {
+            Network::Mainnet => 60_000_000,
+            Network::Optimism => 40_000_000,
+            Network::Arbitrum => 32_000_000,
+            Network::Base => 150_000_000,
+            Network::Polygon => 45_000_000,
+            Network::Gnosis => 17_000_000,
+        }
+    }
+
+    pub fn chain_id(self) -> u64 {
+        match self {
+            Network::Mainnet => 1,
+            Network::Optimism => 10,
+            Network::Arbitrum => 42161,
+            Network::Base => 8453,
+            Network::Polygon => 137,
+            Network::Gnosis => 100,
+        }
+    }
+}
+
+/// Which file the deltas of a file are relative to.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CompareTo {
+    /// The first file
+    #[default]
+    First,
+    /// The closest preceding file containing the entry
+    Previous,
+}
+
+/// Order of the entries.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SortKey {
+    /// Alphabetically, methods before deployments
+    #[default]
+    Name,
+    /// Largest gas in the last file first
+    Gas,
+    /// Largest increase in gas first
+    Delta,
+    /// Largest increase in percent first
+    DeltaPercent,
+}
+
+impl SortKey {
+    pub fn compare(self, l: &Row, r: &Row) -> Ordering {
+        match self {
+            SortKey::Name => {
+                (l.kind == Kind::Deployment, &l.name).cmp(&(r.kind == Kind::Deployment, &r.name))
+            }
+            SortKey::Gas => r.gas().cmp(&l.gas()),
+            SortKey::Delta => r.max_delta().cmp(&l.max_delta()),
+            SortKey::DeltaPercent => r.max_delta_percent().total_cmp(&l.max_delta_percent()),
+        }
+    }
+}
+
+/// Aggregated gas of one entry in one file, relative to the first or the previous file.
+#[derive(Debug, Clone, Serialize)]
+pub struct Cell {
+    /// The mean of the samples, or the statistic chosen by [`Comparison::aggregate`].
+    pub avg_gas: u64,
+    pub min_gas: u64,
+    pub max_gas: u64,
+    pub stddev: f64,
+    pub p50: u64,
+    pub p90: u64,
+    pub p99: u64,
+    /// Sum of all samples.
+    pub total_gas: u64,
+    /// Number of calls of a method, or the number of deployments.
+    pub calls: usize,
+    /// Change in the number of calls relative to the first file.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub calls_delta: Option<i64>,
+    pub delta: Option<i64>,
+    pub delta_percent: Option<f64>,
+    /// Two-sided p-value of a Mann-Whitney U test against the first file's samples, if both have
+    /// at least two.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub p_value: Option<f64>,
+    /// Half the width of the delta's 95% confidence interval, with
+    /// [`Thresholds::confidence`](crate::config::Thresholds::confidence).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub confidence: Option<f64>,
+    /// The delta is not statistically significant and counts as unchanged.
+    pub noise: bool,
+    pub status: Status,
+    /// Severity of an increase, if it exceeds `thresholds.warn` or `thresholds.error`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub severity: Option<Severity>,
+    /// Size of a deployment's code in bytes, if the report has it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code_size: Option<usize>,
+    /// Change in code size relative to the first file.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code_size_delta: Option<i64>,
+    /// Hash of a deployment's bytecode, if the report keeps it.
+    #[serde(skip)]
+    pub code_hash: Option<u64>,
+    /// Gas used by every recorded call or deployment.
+    #[serde(skip)]
+    pub samples: Vec<u64>,
+    /// A method's gas per test, sorted by name, if the report attributes calls to tests.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tests: Vec<TestGas>,
+    /// Clearly separated gas levels, sorted by gas, if there are several. Their average matches
+    /// none of them.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub modes: Vec<Mode>,
+}
+
+/// A cluster of samples at about the same gas, e.g. the calls taking the cold storage path.
+#[derive(Debug, Clone, Serialize)]
+pub struct Mode {
+    /// Mean of the cluster's samples.
+    pub avg_gas: u64,
+    pub samples: usize,
+}
+
+/// The calls a single test made to a method.
+#[derive(Debug, Clone, Serialize)]
+pub struct TestGas {
+    pub name: String,
+    pub avg_gas: u64,
+    pub calls: usize,
+    /// Gas of every call, in the order they were made.
+    #[serde(skip)]
+    pub samples: Vec<u64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Row {
+    pub name: String,
+    pub kind: Kind,
+    pub contract: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub method: Option<String>,
+    /// Full signature of a method, e.g. `transfer(address,uint256)`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+    pub cells: Vec<Option<Cell>>,
+    pub status: Status,
+    /// A method with fewer than [`Comparison::min_samples`] calls in one of the files.
+    pub few_samples: bool,
+    /// An entry with several [`Cell::modes`] in one of the files.
+    pub multimodal: bool,
+    /// For a method that regressed, whether its contract's deployment changed from the first to
+    /// the last file. Unknown if the deployment isn't in both.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub contract_changed: Option<bool>,
+    /// Listed as varying between runs of the same code, so its changes don't fail gates.
+    pub unstable: bool,
+    /// Where the contract or method is defined, if build artifacts were given.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub location: Option<Location>,
+}
+
+/// A line in a Solidity source file, relative to the working directory.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Location {
+    pub file: String,
+    pub line: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Comparison {
+    pub files: Vec<String>,
+    pub block_limit: u64,
+    /// Changes within this percentage count as unchanged, unless overridden for the contract.
+    pub margin: f64,
+    /// Changes of at most this much gas count as unchanged, unless overridden for the contract.
+    pub margin_gas: u64,
+    /// Statistic that `avg_gas` holds, computed after leaving out outliers.
+    pub aggregate: Aggregate,
+    /// Deltas with a p-value at or above this level count as unchanged.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub significance: Option<f64>,
+    pub min_samples: usize,
+    /// Percentage of the block gas limit above which entries are flagged.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub block_share: Option<f64>,
+    pub entries: Vec<Row>,
+    /// Number of unchanged entries left out of `entries`.
+    pub hidden: usize,
+    /// Average gas of all methods in each file, weighted by their number of calls.
+    pub weighted_average: Vec<Option<u64>>,
+    /// Gas of all samples of all entries in each file.
+    pub total_gas: Vec<u64>,
+    /// Average gas of all deployments plus the total gas of all methods in each file.
+    pub totals: Vec<u64>,
+    /// Average gas of all deployments in each file, which their percentages are relative to.
+    pub deployment_gas: Vec<u64>,
+    /// Contracts whose bytecode and gas disagree about whether they changed, if the bytecode of
+    /// both builds is known.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub code_mismatches: Vec<CodeMismatch>,
+}
+
+/// A contract whose bytecode changed while none of its gas did, e.g. because no test runs the new
+/// code, or whose gas changed with identical bytecode.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CodeMismatch {
+    pub contract: String,
+    pub code_changed: bool,
+}
+
+/// How many entries changed in which way, for a one-line verdict.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct Summary {
+    pub regressions: usize,
+    pub improvements: usize,
+    pub unchanged: usize,
+    /// Entries missing from the first file.
+    pub new: usize,
+    /// Entries missing from the last file.
+    pub removed: usize,
+}
+
+impl fmt::Display for Summary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} regressions, {} improvements, {} unchanged, {} new, {} removed",
+            self.regressions, self.improvements, self.unchanged, self.new, self.removed
+        )
+    }
+}
+
+/// Settings of a comparison, resolved from the config.
+struct Settings {
+    compare_to: CompareTo,
+    threshold: Threshold,
+    /// Thresholds of contracts and methods, by name.
+    overrides: HashMap<String, config::Threshold>,
+    aggregate: Aggregate,
+    significance: Option<f64>,
+    confidence: bool,
+    warn: Option<Limit>,
+    error: Option<Limit>,
+    block_share: Option<f64>,
+    outliers: Option<Outliers>,
+    min_samples: usize,
+}
+
+/// Decides which entries take part in the comparison, by name.
+struct Filter {
+    only: Option<Kind>,
+    ignore: Vec<Regex>,
+    include: Vec<Regex>,
+    exclude: Vec<Regex>,
+}
+
+impl Filter {
+    fn new(config: &Config) -> Self {
+        let compile = |patterns: &[String]| -> Vec<Regex> {
+            patterns
+                .iter()
+                // Patterns are checked when the config is read and by the command line parser.
+                .filter_map(|pattern| match Regex::new(pattern) {
+                    Ok(regex) => Some(regex),
+                    Err(e) => {
+                        log::warn!("ignoring the pattern {}: {}", pattern, e);
+                        None
+                    }
+                })
+                .collect()
+        };
+        Filter {
+            only: config.only,
+            ignore: config
+                .ignore
+                .iter()
+                .map(|pattern| wildcard(pattern))
+                .collect(),
+            include: compile(&config.include),
+            exclude: compile(&config.exclude),
+        }
+    }
+
+    fn keeps(&self, kind: Kind, name: &str) -> bool {
+        self.only.is_none_or(|only| only == kind)
+            && !self.ignore.iter().any(|re| re.is_match(name))
+            && (self.include.is_empty() || self.include.iter().any(|re| re.is_match(name)))
+            && !self.exclude.iter().any(|re| re.is_match(name))
+    }
+}
+
+/// Matches a whole name where `*` stands for any text, e.g. `Mock*` or `*.initialize`.
+fn wildcard(pattern: &str) -> Regex {
+    let parts: Vec<String> = pattern.split('*').map(regex::escape).collect();
+    Regex::new(&format!("^{}$", parts.join(".*"))).unwrap()
+}
+
+impl Comparison {
+    /// The threshold of entries without one of their own.
+    pub fn threshold(&self) -> Threshold {
+        Threshold {
+            percent: self.margin,
+            gas: self.margin_gas,
+        }
+    }
+
+    /// Compares named reports, relative to the first one.
+    pub fn from_reports(reports: Vec<(String, GasReport)>, config: &Config) -> Self {
+        let mut data: HashMap<EntryId, Vec<Option<Entry>>> = HashMap::new();
+        let mut filenames: Vec<String> = Vec::new();
+
+        let filter = Filter::new(config);
+        let file_count = reports.len();
+        for (index, (name, report)) in reports.into_iter().enumerate() {
+            for mut depl in report.info.deployments {
+                depl.name = config.contract_name(&depl.name);
+                if depl.gas_data.is_empty() {
+                    log::debug!("{}: skipping {} without gas data", name, depl.name);
+                    continue;
+                }
+                if !filter.keeps(Kind::Deployment, &depl.name) {
+                    log::debug!("{}: filtered out {}", name, depl.name);
+                    continue;
+                }
+                let key = EntryId {
+                    kind: Kind::Deployment,
+                    contract: config.normalize.key(&depl.name),
+                    method: None,
+                };
+                data.entry(key).or_insert(vec![None; file_count])[index] =
+                    Some(Entry::Deployment(depl));
+            }
+            for (_, mut method) in report.info.methods {
+                config.rename_method(&mut method);
+                let full_name = format!("{}.{}", method.method.contract, method.method.method);
+                if method.gas_data.is_empty() {
+                    log::debug!("{}: skipping {} without gas data", name, full_name);
+                    continue;
+                }
+                if !filter.keeps(Kind::Method, &full_name) {
+                    log::debug!("{}: filtered out {}", name, full_name);
+                    continue;
+                }
+                let key = EntryId {
+                    kind: Kind::Method,
+                    contract: config.normalize.key(&method.method.contract),
+                    method: Some(config.normalize.key(if config.collapse_overloads {
+                        &method.method.method
+                    } else {
+                        &method.signature
+                    })),
+                };
+                let slot = &mut data.entry(key).or_insert(vec![None; file_count])[index];
+                match slot {
+                    // Overloads collapsed into one entry.
+                    Some(Entry::Method(existing)) => existing.absorb(method),
+                    _ => *slot = Some(Entry::Method(method)),
+                }
+            }
+
+            filenames.push(match config.labels.get(index) {
+                Some(label) => label.clone(),
+                None => name,
+            });
+        }
+
+        let settings = Settings {
+            compare_to: config.compare_to.unwrap_or_default(),
+            threshold: Threshold {
+                percent: config.thresholds.margin.unwrap_or(MARGIN),
+                gas: config.thresholds.gas.unwrap_or_default(),
+            },
+            overrides: config.thresholds.contracts.clone(),
+            aggregate: config.aggregate.unwrap_or_default(),
+            significance: config.thresholds.significance,
+            confidence: config.thresholds.confidence,
+            warn: config.thresholds.warn,
+            error: config.thresholds.error,
+            block_share: config.thresholds.block_share,
+            outliers: config.outliers,
+            min_samples: config.thresholds.min_samples.unwrap_or(MIN_SAMPLES),
+        };
+        let mut comparison = Comparison::new(
+            filenames,
+            &data,
+            config
+                .block_limit
+                .or(config.network.map(Network::block_limit))
+                .unwrap_or(BLOCK_LIMIT),
+            &settings,
+        );
+        if comparison.entries.is_empty() {
+            log::warn!("no entries to compare, all are empty or filtered out");
+        }
+        comparison.sort(config.sort.unwrap_or_default(), config.reverse);
+        if config.only_changed {
+            let count = comparison.entries.len();
+            let (limit, share) = (comparison.block_limit, comparison.block_share);
+            comparison.entries.retain(|row| {
+                row.changed() || share.is_some_and(|share| row.block_share(limit) > share)
+            });
+            comparison.hidden = count - comparison.entries.len();
+            log::info!("hiding {} unchanged entries", comparison.hidden);
+        }
+        comparison
+    }
+
+    /// Keeps only the columns of the files at these indices, in this order. Their deltas stay
+    /// relative to the files they were computed against, even if those are left out.
+    pub fn select_columns(&mut self, indices: &[usize]) {
+        fn select<T: Clone>(values: &[T], indices: &[usize]) -> Vec<T> {
+            indices.iter().map(|&index| values[index].clone()).collect()
+        }
+        self.files = select(&self.files, indices);
+        self.weighted_average = select(&self.weighted_average, indices);
+        self.total_gas = select(&self.total_gas, indices);
+        self.totals = select(&self.totals, indices);
+        self.deployment_gas = select(&self.deployment_gas, indices);
+        for row in &mut self.entries {
+            row.cells = select(&row.cells, indices);
+            let cells = row.cells.iter().flatten();
+            row.status = cells
+                .clone()
+                .map(|cell| cell.status)
+                .filter(|status| *status != Status::Baseline)
+                .max()
+                .unwrap_or(Status::Unchanged);
+            row.multimodal = cells.clone().any(|cell| !cell.modes.is_empty());
+            row.few_samples =
+                row.kind == Kind::Method && cells.clone().any(|cell| cell.calls < self.min_samples);
+        }
+        correlate(&mut self.entries);
+    }
+
+    /// Sets [`Comparison::code_mismatches`] from whether the bytecode of each contract changed
+    /// between the builds of the first and the last file. An entry that was added or removed
+    /// counts as a change, a contract whose entries are all left out as unchanged as none.
+    pub fn check_code(&mut self, code_changed: &HashMap<String, bool>) {
+        let mut mismatches: Vec<CodeMismatch> = code_changed
+            .iter()
+            .filter(|(contract, &code_changed)| {
+                let gas_changed = self
+                    .entries
+                    .iter()
+                    .any(|row| &row.contract == *contract && row.changed());
+                gas_changed != code_changed
+            })
+            .map(|(contract, &code_changed)| CodeMismatch {
+                contract: contract.clone(),
+                code_changed,
+            })
+            .collect();
+        mismatches.sort_by(|l, r| l.contract.cmp(&r.contract));
+        self.code_mismatches = mismatches;
+    }
+
+    /// Marks the entries of these names as [`Row::unstable`].
+    pub fn mark_unstable(&mut self, names: &HashSet<String>) {
+        for row in &mut self.entries {
+            row.unstable = names.contains(&row.name);
+        }
+    }
+
+    /// Whether several files are compared and no entry changed beyond its threshold, appeared or
+    /// disappeared.
+    pub fn unchanged(&self) -> bool {
+        self.files.len() > 1
+            && self.entries.len() + self.hidden > 0
+            && !self.entries.iter().any(Row::changed)
+    }
+
+    /// Entries whose gas in the last file exceeds [`Comparison::block_share`] of the block limit,
+    /// or [`BLOCK_SHARE`] without one, with their percentage.
+    pub fn near_block_limit(&self) -> Vec<(&Row, f64)> {
+        let threshold = self.block_share.unwrap_or(BLOCK_SHARE);
+        self.entries
+            .iter()
+            .map(|row| (row, row.block_share(self.block_limit)))
+            .filter(|(_, share)| *share > threshold)
+            .collect()
+    }
+
+    pub fn summary(&self) -> Summary {
+        let mut summary = Summary {
+            unchanged: self.hidden,
+            ..Summary::default()
+        };
+        for row in &self.entries {
+            if row.cells.first().is_some_and(Option::is_none) {
+                summary.new += 1;
+            } else if row.cells.last().is_some_and(Option::is_none) {
+                summary.removed += 1;
+            } else {
+                match row.status {
+                    Status::Increased => summary.regressions += 1,
+                    Status::Decreased => summary.improvements += 1,
+                    _ => summary.unchanged += 1,
+                }
+            }
+        }
+        summary
+    }
+
+    /// The entry and cell with the largest percentage change in any direction.
+    pub fn largest_change(&self) -> Option<(&Row, &Cell)> {
+        self.entries
+            .iter()
+            .flat_map(|row| row.cells.iter().flatten().map(move |cell| (row, cell)))
+            .filter(|(_, cell)| cell.delta_percent.is_some_and(|p| p != 0.0))
+            .max_by(|(_, l), (_, r)| {
+                let magnitude = |cell: &Cell| cell.delta_percent.unwrap_or_default().abs();
+                magnitude(l).total_cmp(&magnitude(r))
+            })
+    }
+
+    /// Entries that increased or decreased, each with its largest change in that direction, the
+    /// largest percentage first.
+    pub fn ranked(&self, status: Status) -> Vec<(&Row, &Cell)> {
+        let magnitude = |cell: &Cell| cell.delta_percent.unwrap_or_default().abs();
+        let mut ranked: Vec<_> = self
+            .entries
+            .iter()
+            .filter_map(|row| {
+                let cell = row
+                    .cells
+                    .iter()
+                    .flatten()
+                    .filter(|cell| cell.status == status)
+                    .max_by(|l, r| magnitude(l).total_cmp(&magnitude(r)))?;
+                Some((row, cell))
+            })
+            .collect();
+        ranked.sort_by(|(_, l), (_, r)| magnitude(r).total_cmp(&magnitude(l)));
+        ranked
+    }
+
+    pub fn sort(&mut self, key: SortKey, reverse: bool) {
+        self.entries.sort_by(|l, r| {
+            let order = key.compare(l, r);
+            if reverse {
+                order.reverse()
+            } else {
+                order
+            }
+        });
+    }
+
+    fn new(
+        files: Vec<String>,
+        data: &HashMap<EntryId, Vec<Option<Entry>>>,
+        block_limit: u64,
+        settings: &Settings,
+    ) -> Self {
+        let mut entries = data
+            .iter()
+            .sorted_by(|(l, _), (r, _)| l.cmp(r))
+            .filter_map(|(_, entries)| Row::new(entries, settings))
+            .collect::<Vec<_>>();
+        // Overloaded methods are named by their full signature.
+        let names = entries.iter().map(|row| row.name.clone()).counts();
+        for row in &mut entries {
+            if let (Some(signature), true) = (&row.signature, names[&row.name] > 1) {
+                row.name = format!("{}.{}", row.contract, signature);
+            }
+        }
+        correlate(&mut entries);
+        let weighted_average = (0..files.len())
+            .map(|index| weighted_average(&entries, index))
+            .collect();
+        let total_gas = (0..files.len())
+            .map(|index| {
+                let cells = entries.iter().filter_map(|row| row.cells[index].as_ref());
+                stats::sum(&cells.map(|cell| cell.total_gas).collect::<Vec<_>>())
+            })
+            .collect();
+        let totals = (0..files.len())
+            .map(|index| {
+                let gas = entries
+                    .iter()
+                    .filter_map(|row| Some((row.kind, row.cells[index].as_ref()?)))
+                    .map(|(kind, cell)| match kind {
+                        Kind::Deployment => cell.avg_gas,
+                        Kind::Method => cell.total_gas,
+                    });
+                stats::sum(&gas.collect::<Vec<_>>())
+            })
+            .collect();
+        let deployment_gas = (0..files.len())
+            .map(|index| {
+                let cells = entries
+                    .iter()
+                    .filter(|row| row.kind == Kind::Deployment)
+                    .filter_map(|row| row.cells[index].as_ref());
+                stats::sum(&cells.map(|cell| cell.avg_gas).collect::<Vec<_>>())
+            })
+            .collect();
+        Comparison {
+            files,
+            block_limit,
+            margin: settings.threshold.percent,
+            margin_gas: settings.threshold.gas,
+            aggregate: settings.aggregate,
+            significance: settings.significance,
+            min_samples: settings.min_samples,
+            block_share: settings.block_share,
+            entries,
+            hidden: 0,
+            weighted_average,
+            total_gas,
+            totals,
+            deployment_gas,
+            code_mismatches: vec![],
+        }
+    }
+}
+
+/// Sets [`Row::contract_changed`] of the methods that regressed.
+fn correlate(entries: &mut [Row]) {
+    let redeployed: HashMap<String, bool> = entries
+        .iter()
+        .filter(|row| row.kind == Kind::Deployment)
+        .filter_map(|row| Some((row.contract.clone(), row.redeployed()?)))
+        .collect();
+    for row in entries {
+        row.contract_changed = match (row.kind, row.status) {
+            (Kind::Method, Status::Increased) => redeployed.get(&row.contract).copied(),
+            _ => None,
+        };
+    }
+}
+
+fn weighted_average(entries: &[Row], index: usize) -> Option<u64> {
+    let cells = entries
+        .iter()
+        .filter(|row| row.kind == Kind::Method)
+        .filter_map(|row| row.cells[index].as_ref());
+    let (gas, calls) = cells.fold((0u128, 0u128), |(gas, calls), cell| {
+        let cell_calls = cell.calls as u128;
+        (gas + cell.avg_gas as u128 * cell_calls, calls + cell_calls)
+    });
+    (calls > 0).then(|| u64::try_from(gas / calls).unwrap_or(u64::MAX))
+}
+
+impl Row {
+    /// Gas in the last file containing the entry.
+    pub fn gas(&self) -> u64 {
+        self.cells.iter().flatten().last().map_or(0, |c| c.avg_gas)
+    }
+
+    /// Gas in the last file as a percentage of the block limit, 0 for entries missing from it.
+    pub fn block_share(&self, block_limit: u64) -> f64 {
+        let gas = self
+            .cells
+            .last()
+            .and_then(Option::as_ref)
+            .map_or(0, |c| c.avg_gas);
+        100.0 * gas as f64 / block_limit.max(1) as f64
+    }
+
+    /// How much the largest average gas of the files exceeds the smallest, in percent, if the entry
+    /// is in at least two.
+    pub fn spread(&self) -> Option<f64> {
+        let (min, max) = self
+            .cells
+            .iter()
+            .flatten()
+            .map(|cell| cell.avg_gas)
+            .minmax()
+            .into_option()?;
+        (self.cells.iter().flatten().count() > 1)
+            .then(|| 100.0 * (max - min) as f64 / min.max(1) as f64)
+    }
+
+    /// Whether a deployment's gas, code size or bytecode differs between the first and the last
+    /// file, unknown if it isn't in both.
+    fn redeployed(&self) -> Option<bool> {
+        let (Some(Some(first)), Some(Some(last))) = (self.cells.first(), self.cells.last()) else {
+            return None;
+        };
+        let differs = |l: Option<u64>, r: Option<u64>| l.zip(r).is_some_and(|(l, r)| l != r);
+        Some(
+            first.avg_gas != last.avg_gas
+                || differs(
+                    first.code_size.map(|s| s as u64),
+                    last.code_size.map(|s| s as u64),
+                )
+                || differs(first.code_hash, last.code_hash),
+        )
+    }
+
+    /// Whether the entry changed beyond the margin, or is missing from some of the files.
+    pub fn changed(&self) -> bool {
+        !matches!(self.status, Status::Unchanged) || self.cells.iter().any(Option::is_none)
+    }
+
+    /// Largest change in gas of the entry.
+    pub fn max_delta(&self) -> i64 {
+        self.cells
+            .iter()
+            .flatten()
+            .filter_map(|c| c.delta)
+            .max()
+            .unwrap_or(i64::MIN)
+    }
+
+    /// Largest percentage change of the entry.
+    pub fn max_delta_percent(&self) -> f64 {
+        self.cells
+            .iter()
+            .flatten()
+            .filter_map(|c| c.delta_percent)
+            .fold(f64::NEG_INFINITY, f64::max)
+    }
+
+    fn new(entries: &[Option<Entry>], settings: &Settings) -> Option<Self> {
+        let (kind, contract, method, signature) = match entries.iter().flatten().next()? {
+            Entry::Deployment(depl) => (Kind::Deployment, depl.name.clone(), None, None),
+            Entry::Method(m) => (
+                Kind::Method,
+                m.method.contract.clone(),
+                Some(m.method.method.clone()),
+                Some(m.signature.clone()),
+            ),
+        };
+        let name = match &method {
+            Some(method) => format!("{}.{}", contract, method),
+            None => contract.clone(),
+        };
+
+        // A method's own threshold takes precedence over its contract's.
+        let threshold = [&name, &contract]
+            .into_iter()
+            .find_map(|key| settings.overrides.get(key))
+            .map_or(settings.threshold, |custom| Threshold {
+                percent: custom.margin.unwrap_or(settings.threshold.percent),
+                gas: custom.gas.unwrap_or(settings.threshold.gas),
+            });
+        let mut cells: Vec<Option<Cell>> = vec![];
+        for entry in entries {
+            let cell = match entry {
+                Some(entry) if entry.has_gas_data() => Some(Cell {
+                    tests: entry
+                        .tests()
+                        .into_iter()
+                        .flatten()
+                        .map(|(name, gas_data)| TestGas {
+                            name: name.clone(),
+                            avg_gas: gas_data.aggregate(settings.aggregate),
+                            calls: gas_data.len(),
+                            samples: gas_data.samples().to_vec(),
+                        })
+                        .sorted_by(|l, r| l.name.cmp(&r.name))
+                        .collect(),
+                    code_hash: entry.code_hash(),
+                    ..Cell::new(
+                        entry.gas_data(),
+                        entry.calls(),
+                        entry.code_size(),
+                        match settings.compare_to {
+                            CompareTo::First => cells.first().and_then(Option::as_ref),
+                            CompareTo::Previous => cells.iter().rev().flatten().next(),
+                        },
+                        threshold,
+                        settings,
+                    )
+                }),
+                _ => None,
+            };
+            cells.push(cell);
+        }
+        let status = cells
+            .iter()
+            .flatten()
+            .map(|cell| cell.status)
+            .filter(|status| *status != Status::Baseline)
+            .max()
+            .unwrap_or(Status::Unchanged);
+        let few_samples = kind == Kind::Method
+            && cells
+                .iter()
+                .flatten()
+                .any(|cell| cell.calls < settings.min_samples);
+        let multimodal = cells.iter().flatten().any(|cell| !cell.modes.is_empty());
+
+        Some(Row {
+            name,
+            kind,
+            contract,
+            method,
+            signature,
+            cells,
+            status,
+            few_samples,
+            multimodal,
+            contract_changed: None,
+            unstable: false,
+            location: None,
+        })
+    }
+}
+
+impl Cell {
+    fn new(
+        gas_data: &GasData,
+        calls: usize,
+        code_size: Option<usize>,
+        first: Option<&Cell>,
+        threshold: Threshold,
+        settings: &Settings,
+    ) -> Self {
+        let samples = gas_data.samples().to_vec();
+        let aggregate = settings.aggregate;
+        let avg = match settings.outliers {
+            Some(outliers) => aggregate.apply(&outliers.filter(&samples)),
+            None => gas_data.aggregate(aggregate),
+        };
+        let sampled = first.filter(|first| first.samples.len() > 1 && samples.len() > 1);
+        let p_value = sampled.map(|first| stats::mann_whitney(&first.samples, &samples));
+        let confidence = sampled
+            .filter(|_| settings.confidence)
+            .map(|first| stats::confidence_interval(&first.samples, &samples));
+        let spans_zero = confidence.is_some_and(|confidence| {
+            let delta = first.map_or(0, |first| stats::delta(first.avg_gas, avg));
+            (delta.unsigned_abs() as f64) <= confidence
+        });
+        let noise = spans_zero
+            || matches!((p_value, settings.significance), (Some(p), Some(alpha)) if p >= alpha);
+        let (delta, delta_percent, status) = match first {
+            Some(first) => {
+                let first_avg = first.avg_gas;
+                // Excluding intrinsic gas can leave a baseline of 0, counted as 1 gas.
+                let percent = 100f64 * (avg as f64 - first_avg as f64) / first_avg.max(1) as f64;
+                let delta = stats::delta(first_avg, avg);
+                let status = match noise {
+                    true => Status::Unchanged,
+                    false => threshold.status(delta, percent),
+                };
+                (Some(delta), Some(percent), status)
+            }
+            None => (None, None, Status::Baseline),
+        };
+        let mut cell = Cell {
+            avg_gas: avg,
+            min_gas: gas_data.aggregate(Aggregate::Min),
+            max_gas: gas_data.aggregate(Aggregate::Max),
+            stddev: stats::stddev(&samples),
+            p50: stats::percentile(&samples, 50.0),
+            p90: stats::percentile(&samples, 90.0),
+            p99: stats::percentile(&samples, 99.0),
+            total_gas: gas_data.sum(),
+            calls,
+            calls_delta: first.map(|first| stats::delta(first.calls as u64, calls as u64)),
+            delta,
+            delta_percent,
+            p_value,
+            confidence,
+            noise,
+            status,
+            severity: None,
+            code_size,
+            code_hash: None,
+            code_size_delta: match (first.and_then(|first| first.code_size), code_size) {
+                (Some(from), Some(to)) => Some(stats::delta(from as u64, to as u64)),
+                _ => None,
+            },
+            modes: match stats::modes(&samples).as_slice() {
+                modes @ [_, _, ..] => modes
+                    .iter()
+                    .map(|&(avg_gas, samples)| Mode { avg_gas, samples })
+                    .collect(),
+                _ => vec![],
+            },
+            samples,
+            tests: vec![],
+        };
+        if status == Status::Increased {
+            let exceeded = |limit: Option<Limit>| limit.is_some_and(|l| l.exceeded_by(&cell));
+            cell.severity = match (exceeded(settings.error), exceeded(settings.warn)) {
+                (true, _) => Some(Severity::Error),
+                (false, true) => Some(Severity::Warning),
+                (false, false) => None,
+            };
+        }
+        cell
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::report::{Info, MethodIdentifier, RawMethod};
+
+    /// A report of methods named like `Vault.deposit`, each with its gas samples.
+    pub(crate) fn report(methods: &[(&str, &[u64])]) -> GasReport {
+        let methods = methods
+            .iter()
+            .map(|(name, gas)| {
+                let (contract, method) = name.split_once('.').unwrap();
+                let raw = RawMethod {
+                    key: format!("{}_{}", contract, method),
+                    method: MethodIdentifier {
+                        contract: contract.to_owned(),
+                        method: method.to_owned(),
+                    },
+                    signature: format!("{}()", method),
+                    gas_data: gas.to_vec().into(),
+                    number_of_calls: gas.len(),
+                    tests: HashMap::new(),
+                };
+                (raw.key.clone(), raw)
+            })
+            .collect();
+        GasReport {
+            info: Info {
+                methods,
+                deployments: vec![],
+            },
+        }
+    }
+
+    /// Compares reports named `a.json`, `b.json` and so on.
+    pub(crate) fn compare(reports: &[&[(&str, &[u64])]], config: &Config) -> Comparison {
+        let reports = reports
+            .iter()
+            .zip('a'..)
+            .map(|(methods, name)| (format!("{}.json", name), report(methods)))
+            .collect();
+        Comparison::from_reports(reports, config)
+    }
+
+    /// A method whose name needs escaping in every format, 10% more expensive in `b.json`.
+    pub(crate) const AWKWARD: &str = "Va,ult.dep<o\"sit%&";
+
+    pub(crate) fn regressed() -> Comparison {
+        compare(
+            &[&[(AWKWARD, &[100_000])], &[(AWKWARD, &[110_000])]],
+            &Config::default(),
+        )
+    }
+
+    fn names(comparison: &Comparison) -> Vec<&str> {
+        comparison
+            .entries
+            .iter()
+            .map(|row| row.name.as_str())
+            .collect()
+    }
+
+    const METHODS: &[(&str, &[u64])] = &[
+        ("MockToken.mint", &[30_000]),
+        ("Vault.deposit", &[50_000]),
+        ("Vault.withdraw", &[40_000]),
+        ("Router.swap", &[90_000]),
+    ];
+
+    #[test]
+    fn include_and_exclude_together() {
+        let config = Config::parse(
+            "include = [\"^Vault\\\\.\", \"^Mock\"]\nexclude = [\"withdraw$\", \"^Mock\"]",
+        )
+        .unwrap();
+        assert_eq!(names(&compare(&[METHODS], &config)), ["Vault.deposit"]);
+
+        let config = Config::parse("exclude = [\"^Mock\"]\nignore = [\"*.swap\"]").unwrap();
+        assert_eq!(
+            names(&compare(&[METHODS], &config)),
+            ["Vault.deposit", "Vault.withdraw"]
+        );
+    }
+
+    #[test]
+    fn thresholds_of_contracts_and_methods() {
+        let config = Config::parse(
+            "only_changed = true\n[thresholds]\nmargin = 5.0\n\
+             [thresholds.contracts]\nVault = { margin = 1.0 }\n\"Vault.withdraw\" = { gas = 5000 }",
+        )
+        .unwrap();
+        let after: &[(&str, &[u64])] = &[
+            ("MockToken.mint", &[31_000]),
+            ("Vault.deposit", &[51_000]),
+            ("Vault.withdraw", &[44_000]),
+            ("Router.swap", &[95_000]),
+        ];
+        let comparison = compare(&[METHODS, after], &config);
+        // Up 3.3%, 2%, 10% and 5.6%: the mint stays within 5%, the withdrawal within 5000 gas.
+        assert_eq!(names(&comparison), ["Router.swap", "Vault.deposit"]);
+        assert_eq!(comparison.hidden, 2);
+    }
+
+    #[test]
+    fn few_samples_count_calls_of_snapshots() {
+        let gas: &[(&str, &[u64])] = &[
+            ("Vault.deposit", &[50_000; 3]),
+            ("Vault.withdraw", &[40_000]),
+        ];
+        let snapshot = crate::snapshot::Snapshot::from_report(report(gas));
+        let reports = vec![
+            ("snap.json".to_owned(), snapshot.into_report().unwrap()),
+            (
+                "b.json".to_owned(),
+                report(&[
+                    ("Vault.deposit", &[60_000; 3]),
+                    ("Vault.withdraw", &[40_000]),
+                ]),
+            ),
+        ];
+        let comparison = Comparison::from_reports(reports, &Config::default());
+        // The snapshot holds a single averaged sample of the deposit's three calls.
+        let few_samples: Vec<_> = comparison
+            .entries
+            .iter()
+            .map(|row| row.few_samples)
+            .collect();
+        assert_eq!(few_samples, [false, true]);
+        assert_eq!(crate::gate::regressions(&comparison, None, true).len(), 1);
+    }
+
+    #[test]
+    fn zero_baseline_has_a_finite_percentage() {
+        let mut reports = vec![
+            ("a.json".to_owned(), report(&[("Vault.deposit", &[21_000])])),
+            ("b.json".to_owned(), report(&[("Vault.deposit", &[22_000])])),
+        ];
+        for (_, report) in &mut reports {
+            crate::intrinsic::exclude(report, false);
+        }
+        let comparison = Comparison::from_reports(reports, &Config::default());
+        let cell = comparison.entries[0].cells[1].as_ref().unwrap();
+        assert_eq!(cell.delta, Some(1000));
+        assert_eq!(cell.delta_percent, Some(100_000.0));
+        assert_eq!(cell.status, Status::Increased);
+    }
+}
@@ -0,0 +1,224 @@
+use std::{collections::HashMap, fs, path::Path};
+
+use serde::{de::Error, Deserialize, Deserializer};
+
+use crate::{
+    comparison::{CompareTo, Kind, Network, SortKey},
+    gate::Limit,
+    output::{Format, Theme, ThemePreset},
+    report::RawMethod,
+    rollup::L1Config,
+    stats::{Aggregate, Outliers},
+};
+
+/// Config file looked up in the working directory when `--config` is not given.
+pub const DEFAULT_PATH: &str = "eth-gas-delta.toml";
+
+/// Settings from `eth-gas-delta.toml`. Command line arguments and their `ETH_GAS_DELTA_*` environment
+/// variables take precedence.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub format: Option<Format>,
+    /// Color the output even when it is not a terminal, or never; `--color` and `NO_COLOR` win.
+    pub color: Option<bool>,
+    /// Built-in color theme, `"default"` or `"colorblind"`.
+    pub theme: Option<ThemePreset>,
+    /// Custom colors, taking precedence over `theme`; missing ones come from the default theme.
+    pub colors: Option<Theme>,
+    /// Compare each file to the first one or to the previous one.
+    pub compare_to: Option<CompareTo>,
+    pub sort: Option<SortKey>,
+    /// Reverse the sort order.
+    pub reverse: bool,
+    /// Leave out entries that are unchanged in all files.
+    pub only_changed: bool,
+    /// Block gas limit that the "% of block" values refer to, 30M by default.
+    pub block_limit: Option<u64>,
+    /// Chain whose block gas limit is used unless `block_limit` is set, e.g. `"optimism"`.
+    pub network: Option<Network>,
+    /// Statistic compared for each entry, the mean by default.
+    pub aggregate: Option<Aggregate>,
+    /// Samples left out before aggregating, `"iqr"` or a percentage to trim like `"10%"`.
+    pub outliers: Option<Outliers>,
+    /// Column labels used instead of the file names, in the order the files are given.
+    pub labels: Vec<String>,
+    /// Entries to leave out of the comparison, e.g. `"MockToken"` or `"Vault.initialize"`. `*`
+    /// matches any text, as in `"Mock*"` or `"*.initialize"`.
+    pub ignore: Vec<String>,
+    /// Combine overloaded methods into one entry instead of comparing each signature separately.
+    pub collapse_overloads: bool,
+    /// Only compare deployments or only methods.
+    pub only: Option<Kind>,
+    /// Regular expressions of which an entry's name must match at least one, if any are given.
+    #[serde(deserialize_with = "regexes")]
+    pub include: Vec<String>,
+    /// Regular expressions of entry names to leave out, e.g. `"^Mock"`.
+    #[serde(deserialize_with = "regexes")]
+    pub exclude: Vec<String>,
+    pub thresholds: Thresholds,
+    /// Calldata sizes and fee scalars for the L1 data fee of rollups.
+    pub l1: L1Config,
+    /// Old names mapped to new ones, so renamed entries are compared against each other. Keys
+    /// without a dot rename a contract with its deployment and methods, e.g. `Vault = "VaultV2"`,
+    /// keys with a dot a single method, e.g. `"Vault.deposit" = "Vault.depositFor"`.
+    pub rename: HashMap<String, String>,
+    /// How names are made to match across reporters before renaming.
+    pub normalize: Normalize,
+}
+
+/// Reads regular expressions, rejecting invalid ones so that comparing can't fail on them.
+fn regexes<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<String>, D::Error> {
+    let patterns = Vec::<String>::deserialize(deserializer)?;
+    for pattern in &patterns {
+        regex::Regex::new(pattern).map_err(D::Error::custom)?;
+    }
+    Ok(patterns)
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Normalize {
+    /// Match names regardless of case, showing them as the first file has them.
+    pub ignore_case: bool,
+    /// Strip source paths from contract names, e.g. `contracts/v2/Vault.sol:Vault` to `Vault`.
+    pub strip_paths: bool,
+    /// Trim whitespace around names and remove it from signatures.
+    pub trim: bool,
+}
+
+impl Normalize {
+    /// The name of a contract as reporters are expected to agree on it.
+    pub fn contract(&self, contract: &str) -> String {
+        let mut name = contract;
+        if self.trim {
+            name = name.trim();
+        }
+        if self.strip_paths {
+            name = match name.rsplit_once(':') {
+                Some((_, name)) => name,
+                None => {
+                    let file = name.rsplit(['/', '\\']).next().unwrap_or(name);
+                    file.strip_suffix(".sol").unwrap_or(file)
+                }
+            };
+        }
+        name.to_owned()
+    }
+
+    /// A method's name or signature as reporters are expected to agree on it.
+    pub fn method(&self, method: &str) -> String {
+        match self.trim {
+            true => method.split_whitespace().collect(),
+            false => method.to_owned(),
+        }
+    }
+
+    /// The form of a normalized name that entries are matched by.
+    pub fn key(&self, name: &str) -> String {
+        match self.ignore_case {
+            true => name.to_lowercase(),
+            false => name.to_owned(),
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Thresholds {
+    /// Changes within this percentage are shown as unchanged.
+    pub margin: Option<f64>,
+    /// Changes of at most this much gas are shown as unchanged, 0 by default.
+    pub gas: Option<u64>,
+    /// Thresholds of single contracts or methods, e.g. `Vault = { margin = 1.0 }` or
+    /// `"Vault.deposit" = { gas = 500 }`; what they leave out comes from above.
+    pub contracts: HashMap<String, Threshold>,
+    /// Significance level of the Mann-Whitney U test below which deltas count as changes.
+    pub significance: Option<f64>,
+    /// Show the 95% confidence interval of each delta and count deltas whose interval spans zero
+    /// as unchanged.
+    pub confidence: bool,
+    /// Methods with fewer calls than this in any file are marked as unreliable, 3 by default.
+    pub min_samples: Option<usize>,
+    /// Limit used by `--fail-on-increase` when no value is given.
+    pub fail_on_increase: Option<Limit>,
+    /// Increases beyond this limit are warnings, e.g. `"2%"`.
+    pub warn: Option<Limit>,
+    /// Increases beyond this limit are errors, e.g. `"10%"`.
+    pub error: Option<Limit>,
+    /// Entries whose gas in the last file exceeds this percentage of the block gas limit are
+    /// flagged, whether they changed or not.
+    pub block_share: Option<f64>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Threshold {
+    pub margin: Option<f64>,
+    pub gas: Option<u64>,
+}
+
+impl Config {
+    /// The current name of a contract.
+    pub fn contract_name(&self, contract: &str) -> String {
+        let contract = self.normalize.contract(contract);
+        match self.rename.get(&contract) {
+            Some(name) if !name.contains('.') => name.clone(),
+            _ => contract,
+        }
+    }
+
+    /// The current contract and name of a method.
+    pub fn method_name(&self, contract: &str, method: &str) -> (String, String) {
+        let method = &self.normalize.method(method);
+        let key = format!("{}.{}", self.normalize.contract(contract), method);
+        match self.rename.get(&key) {
+            Some(name) => match name.split_once('.') {
+                Some((contract, method)) => (contract.to_owned(), method.to_owned()),
+                None => (self.contract_name(contract), name.clone()),
+            },
+            None => (self.contract_name(contract), method.to_owned()),
+        }
+    }
+
+    /// Gives a method of a report its current contract, name and signature.
+    pub fn rename_method(&self, method: &mut RawMethod) {
+        let (contract, renamed) = self.method_name(&method.method.contract, &method.method.method);
+        let signature = self.normalize.method(&method.signature);
+        let name = self.normalize.method(&method.method.method);
+        method.signature = match signature.strip_prefix(&name) {
+            Some(args) => format!("{}{}", renamed, args),
+            None => signature,
+        };
+        (method.method.contract, method.method.method) = (contract, renamed);
+    }
+
+    /// Reads the given config file, or `eth-gas-delta.toml` if it exists.
+    pub fn load(path: Option<&str>) -> Result<Config, String> {
+        let path = match path {
+            Some(path) => path,
+            None if Path::new(DEFAULT_PATH).is_file() => DEFAULT_PATH,
+            None => return Ok(Config::default()),
+        };
+        let text = fs::read_to_string(path).map_err(|e| format!("cannot read {}: {}", path, e))?;
+        Config::parse(&text).map_err(|e| format!("invalid config {}: {}", path, e))
+    }
+
+    pub fn parse(text: &str) -> Result<Config, toml::de::Error> {
+        toml::from_str(text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_invalid_patterns() {
+        let error = Config::parse("include = [\"(\"]").unwrap_err();
+        assert!(error.to_string().contains("unclosed group"), "{}", error);
+        assert!(Config::parse("exclude = [\"[\"]").is_err());
+        let config = Config::parse("include = [\"^Vault\\\\.\"]").unwrap();
+        assert_eq!(config.include, ["^Vault\\."]);
+    }
+}
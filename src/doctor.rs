@@ -0,0 +1,220 @@
+use std::{collections::HashMap, fmt};
+
+use itertools::Itertools;
+use serde::{
+    de::{IgnoredAny, MapAccess, Visitor},
+    Deserialize, Deserializer,
+};
+use serde_json::Value;
+
+use crate::{
+    comparison::BLOCK_LIMIT,
+    report::{self, GasData},
+    GasReport,
+};
+
+/// What was found out about a report file.
+#[derive(Debug)]
+pub struct Diagnosis {
+    /// The detected format, e.g. `hardhat-gas-reporter report` or `snapshot version 1`.
+    pub format: Option<String>,
+    pub methods: usize,
+    pub deployments: usize,
+    /// Problems that make the report or some of its entries unusable.
+    pub errors: Vec<String>,
+    /// Entries that are compared, but probably not as intended.
+    pub warnings: Vec<String>,
+}
+
+impl Diagnosis {
+    pub fn is_ok(&self) -> bool {
+        self.errors.is_empty() && self.warnings.is_empty()
+    }
+}
+
+impl fmt::Display for Diagnosis {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.format {
+            Some(format) => write!(
+                f,
+                "{}, {} methods, {} deployments",
+                format, self.methods, self.deployments
+            )?,
+            None => write!(f, "unknown format")?,
+        }
+        for error in &self.errors {
+            write!(f, "\n  error: {}", error)?;
+        }
+        for warning in &self.warnings {
+            write!(f, "\n  warning: {}", warning)?;
+        }
+        Ok(())
+    }
+}
+
+/// The keys of a JSON object, including duplicates that a map would drop.
+struct Keys(Vec<String>);
+
+impl<'de> Deserialize<'de> for Keys {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct KeysVisitor;
+
+        impl<'de> Visitor<'de> for KeysVisitor {
+            type Value = Keys;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("an object")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Keys, A::Error> {
+                let mut keys = vec![];
+                while let Some((key, IgnoredAny)) = map.next_entry::<String, IgnoredAny>()? {
+                    keys.push(key);
+                }
+                Ok(Keys(keys))
+            }
+        }
+
+        deserializer.deserialize_map(KeysVisitor)
+    }
+}
+
+#[derive(Deserialize)]
+struct MethodKeys {
+    info: InfoKeys,
+}
+
+#[derive(Deserialize)]
+struct InfoKeys {
+    methods: Keys,
+}
+
+/// Checks a report or snapshot for anything that would make its comparison misleading.
+pub fn diagnose(bytes: &[u8]) -> Diagnosis {
+    let mut diagnosis = Diagnosis {
+        format: None,
+        methods: 0,
+        deployments: 0,
+        errors: vec![],
+        warnings: vec![],
+    };
+    let value: Value = match serde_json::from_slice(bytes) {
+        Ok(value) => value,
+        Err(e) => {
+            diagnosis.errors.push(format!("invalid JSON: {}", e));
+            return diagnosis;
+        }
+    };
+    diagnosis.format = Some(match value.get("schema") {
+        Some(_) => format!(
+            "snapshot version {}",
+            value.get("version").unwrap_or(&Value::Null)
+        ),
+        None => "hardhat-gas-reporter report".to_owned(),
+    });
+
+    let report = match report::parse_lenient(bytes) {
+        Ok((report, skipped)) => {
+            let malformed = skipped
+                .iter()
+                .map(|e| format!("malformed entry at {}: {}", e.path, e.message));
+            diagnosis.errors.extend(malformed);
+            report
+        }
+        Err(e) => {
+            diagnosis.errors.push(e.to_string());
+            return diagnosis;
+        }
+    };
+    diagnosis.methods = report.info.methods.len();
+    diagnosis.deployments = report.info.deployments.len();
+
+    if let Ok(MethodKeys { info }) = serde_json::from_slice::<MethodKeys>(bytes) {
+        for (key, count) in info.methods.0.iter().counts().into_iter().sorted() {
+            if count > 1 {
+                diagnosis.errors.push(format!(
+                    "method key {} appears {} times, only the last one is used",
+                    key, count
+                ));
+            }
+        }
+    }
+    let snapshot = value.get("schema").is_some();
+    check_entries(&report, snapshot, &mut diagnosis.warnings);
+    diagnosis
+}
+
+fn check_entries(report: &GasReport, snapshot: bool, warnings: &mut Vec<String>) {
+    let mut signatures: HashMap<(&str, &str), Vec<&str>> = HashMap::new();
+    for (key, method) in report.info.methods.iter().sorted_by_key(|(key, _)| *key) {
+        let name = format!("{}.{}", method.method.contract, method.method.method);
+        if method.gas_data.is_empty() {
+            warnings.push(format!("{} has no gas data and is left out", name));
+        }
+        // Snapshots keep a single average per method.
+        if !snapshot && method.number_of_calls != method.gas_data.len() {
+            warnings.push(format!(
+                "{} has {} calls but {} gas samples",
+                name,
+                method.number_of_calls,
+                method.gas_data.len()
+            ));
+        }
+        if key != &method.key {
+            warnings.push(format!("{} is listed under key {}", method.key, key));
+        }
+        check_gas(&name, &method.gas_data, warnings);
+        signatures
+            .entry((&method.method.contract, &method.signature))
+            .or_default()
+            .push(key);
+    }
+    for ((contract, signature), keys) in signatures.iter().sorted() {
+        if keys.len() > 1 {
+            warnings.push(format!(
+                "{}.{} appears under several keys: {}",
+                contract,
+                signature,
+                keys.join(", ")
+            ));
+        }
+    }
+
+    for (name, count) in report
+        .info
+        .deployments
+        .iter()
+        .map(|d| &d.name)
+        .counts()
+        .into_iter()
+        .sorted()
+    {
+        if count > 1 {
+            warnings.push(format!(
+                "deployment {} appears {} times, only the last one is compared",
+                name, count
+            ));
+        }
+    }
+    for deployment in &report.info.deployments {
+        if deployment.gas_data.is_empty() {
+            warnings.push(format!(
+                "deployment {} has no gas data and is left out",
+                deployment.name
+            ));
+        }
+        check_gas(&deployment.name, &deployment.gas_data, warnings);
+    }
+}
+
+fn check_gas(name: &str, gas_data: &GasData, warnings: &mut Vec<String>) {
+    if let Some(gas) = gas_data.min().filter(|gas| *gas == 0) {
+        warnings.push(format!("{} has a gas value of {}", name, gas));
+    }
+    if let Some(gas) = gas_data.max().filter(|gas| *gas > BLOCK_LIMIT) {
+        warnings.push(format!(
+            "{} uses {} gas, more than the block limit of {}",
+            name, gas, BLOCK_LIMIT
+        ));
+    }
+}
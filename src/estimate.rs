@@ -0,0 +1,139 @@
+use std::{collections::HashMap, fs};
+
+use crate::{
+    abi,
+    cache::{self, Expiry},
+    onchain,
+};
+use anyhow::{bail, Context, Result};
+use eth_gas_delta::{
+    report::{Info, MethodIdentifier, RawMethod},
+    GasReport,
+};
+use serde::Deserialize;
+use serde_json::{json, Map};
+
+/// Calls whose gas is estimated on a live network, read from the TOML file given to `--estimate`.
+///
+/// ```toml
+/// rpc_url = "https://eth.llamarpc.com"
+///
+/// [contracts]
+/// Vault = "0x1234…"
+///
+/// [[calls]]
+/// method = "Vault.deposit(uint256)"
+/// args = ["1000000"]
+/// ```
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Estimates {
+    /// Node to ask, instead of `--rpc-url`.
+    rpc_url: Option<String>,
+    /// Sender of the calls that don't name their own.
+    from: Option<String>,
+    /// Deployed address of every contract.
+    contracts: HashMap<String, String>,
+    #[serde(default)]
+    calls: Vec<Call>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Call {
+    /// `<contract>.<signature>`, e.g. `Vault.deposit(uint256)`.
+    method: String,
+    /// Arguments of static types, encoded according to the signature.
+    #[serde(default)]
+    args: Vec<String>,
+    /// Complete calldata in hex, instead of `args`.
+    data: Option<String>,
+    /// Wei sent along, decimal or hex.
+    value: Option<String>,
+    from: Option<String>,
+}
+
+impl Estimates {
+    pub fn load(path: &str) -> Result<Self> {
+        let text = fs::read_to_string(path).with_context(|| format!("cannot read {}", path))?;
+        toml::from_str(&text).with_context(|| format!("cannot parse {}", path))
+    }
+
+    /// Estimates every call and returns the results as a report, with one sample per call.
+    pub fn report(&self, rpc_url: Option<&str>) -> Result<GasReport> {
+        let rpc_url = self
+            .rpc_url
+            .as_deref()
+            .or(rpc_url)
+            .context("estimating gas needs an rpc_url in the file or --rpc-url")?;
+        let mut methods: HashMap<String, RawMethod> = HashMap::new();
+        for call in &self.calls {
+            let gas = call
+                .estimate(rpc_url, self)
+                .with_context(|| format!("cannot estimate {}", call.method))?;
+            log::info!("estimated {}: {} gas", call.method, gas);
+            let (contract, signature) = call.method.split_once('.').unwrap_or_default();
+            let key = format!("{}_{}", contract, signature);
+            let method = methods.entry(key.clone()).or_insert_with(|| RawMethod {
+                key,
+                method: MethodIdentifier {
+                    contract: contract.to_owned(),
+                    method: signature.split('(').next().unwrap_or_default().to_owned(),
+                },
+                signature: signature.to_owned(),
+                gas_data: vec![].into(),
+                number_of_calls: 0,
+                tests: HashMap::new(),
+            });
+            method.gas_data.extend(vec![gas].into());
+            method.number_of_calls += 1;
+        }
+        Ok(GasReport {
+            info: Info {
+                methods,
+                deployments: vec![],
+            },
+        })
+    }
+}
+
+impl Call {
+    fn estimate(&self, rpc_url: &str, estimates: &Estimates) -> Result<u64> {
+        let Some((contract, signature)) = self.method.split_once('.') else {
+            bail!("expected <contract>.<signature>");
+        };
+        let to = estimates
+            .contracts
+            .get(contract)
+            .with_context(|| format!("no address for {} in [contracts]", contract))?;
+        let data = match &self.data {
+            Some(data) => data.clone(),
+            None => format!("0x{}", abi::hex(&abi::calldata(signature, &self.args)?)),
+        };
+        let mut transaction = Map::new();
+        transaction.insert("to".to_owned(), json!(to));
+        transaction.insert("data".to_owned(), json!(data));
+        if let Some(from) = self.from.as_ref().or(estimates.from.as_ref()) {
+            transaction.insert("from".to_owned(), json!(from));
+        }
+        if let Some(value) = &self.value {
+            transaction.insert("value".to_owned(), json!(quantity(value)?));
+        }
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "eth_estimateGas",
+            "params": [transaction],
+        });
+        onchain::quantity(&cache::call(rpc_url, request, Expiry::Ttl)?)
+    }
+}
+
+/// A decimal or hex amount as a JSON-RPC quantity.
+fn quantity(value: &str) -> Result<String> {
+    if value.starts_with("0x") {
+        Ok(value.to_owned())
+    } else {
+        Ok(format!("{:#x}", value.parse::<u128>()?))
+    }
+}
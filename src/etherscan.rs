@@ -0,0 +1,138 @@
+use std::env;
+
+use anyhow::{bail, Context, Result};
+use eth_gas_delta::GasReport;
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::{
+    abi,
+    cache::{self, Expiry},
+    cli,
+    onchain::{self, Names, Transaction},
+};
+
+/// Used unless `ETHERSCAN_API_URL` points to another Etherscan-compatible API.
+const API_URL: &str = "https://api.etherscan.io/v2/api";
+
+/// Recent transactions of verified contracts on Etherscan, given as `NAME=ADDRESS`.
+#[derive(Debug, Clone)]
+pub struct Etherscan {
+    pub contracts: Vec<String>,
+    pub chain_id: u64,
+    /// Number of most recent transactions fetched per contract.
+    pub transactions: usize,
+}
+
+#[derive(Deserialize)]
+struct Response {
+    status: String,
+    message: String,
+    result: Value,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ListedTransaction {
+    to: String,
+    input: String,
+    gas_used: String,
+    is_error: String,
+    contract_address: String,
+}
+
+impl Etherscan {
+    /// Builds a report from the successful transactions, naming their functions by the verified
+    /// ABIs. Needs `ETHERSCAN_API_KEY`.
+    pub fn report(&self) -> Result<GasReport> {
+        let key = cli::token("ETHERSCAN_API_KEY").context("Etherscan needs ETHERSCAN_API_KEY")?;
+        let mut names = Names::default();
+        let mut transactions = vec![];
+        for contract in &self.contracts {
+            let (name, address) = contract
+                .split_once('=')
+                .with_context(|| format!("expected NAME=ADDRESS, got {}", contract))?;
+            names.add_contract(name, address);
+            match self.abi(address, &key) {
+                Ok(abi) => names.add_abi(&abi),
+                Err(e) => log::warn!("no ABI for {}, naming calls by selector: {:#}", name, e),
+            }
+            let listed = self.transactions(address, &key)?;
+            log::info!("fetched {} transactions of {}", listed.len(), name);
+            transactions.extend(listed);
+        }
+        Ok(onchain::report(&transactions, &names))
+    }
+
+    fn abi(&self, address: &str, key: &str) -> Result<Value> {
+        let abi = self.get(&[
+            ("module", "contract"),
+            ("action", "getabi"),
+            ("address", address),
+            ("apikey", key),
+        ])?;
+        let abi = abi.as_str().context("unexpected ABI")?;
+        Ok(serde_json::from_str(abi)?)
+    }
+
+    fn transactions(&self, address: &str, key: &str) -> Result<Vec<Transaction>> {
+        let offset = self.transactions.to_string();
+        let result = self.get(&[
+            ("module", "account"),
+            ("action", "txlist"),
+            ("address", address),
+            ("page", "1"),
+            ("offset", &offset),
+            ("sort", "desc"),
+            ("apikey", key),
+        ])?;
+        let listed: Vec<ListedTransaction> = serde_json::from_value(result)?;
+        let address = address.to_lowercase();
+        let mut transactions = vec![];
+        for listed in listed {
+            let to = listed.to.to_lowercase();
+            let created = listed.contract_address.to_lowercase();
+            if listed.is_error != "0" || (to != address && created != address) {
+                continue;
+            }
+            transactions.push(Transaction {
+                to: (!to.is_empty()).then_some(to),
+                input: abi::unhex(&listed.input)?,
+                gas_used: listed
+                    .gas_used
+                    .parse()
+                    .with_context(|| format!("invalid gas {}", listed.gas_used))?,
+                contract_address: (!created.is_empty()).then_some(created),
+            });
+        }
+        Ok(transactions)
+    }
+
+    fn get(&self, query: &[(&str, &str)]) -> Result<Value> {
+        let url = env::var("ETHERSCAN_API_URL").unwrap_or_else(|_| API_URL.to_owned());
+        let chain_id = self.chain_id.to_string();
+        // Without the API key, which shouldn't end up in the cache.
+        let key = query
+            .iter()
+            .filter(|(name, _)| *name != "apikey")
+            .fold(format!("{} {}", url, chain_id), |key, (name, value)| {
+                format!("{} {}={}", key, name, value)
+            });
+        cache::get(&key, "the Etherscan response", Expiry::Ttl, || {
+            let mut request = ureq::get(&url).query("chainid", &chain_id);
+            for (name, value) in query {
+                request = request.query(name, value);
+            }
+            let response: Response = serde_json::from_reader(request.call()?.into_reader())?;
+            // An empty transaction list also comes with status 0.
+            if response.status != "1" && !response.result.as_array().is_some_and(Vec::is_empty) {
+                bail!(
+                    "Etherscan answered {}: {}",
+                    response.message,
+                    response.result
+                );
+            }
+            Ok(response.result)
+        })
+    }
+}
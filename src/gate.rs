@@ -0,0 +1,224 @@
+use std::{fmt, str::FromStr};
+
+use serde::{de, Deserialize, Deserializer};
+
+use crate::comparison::{Cell, Comparison, Row, Severity, Status};
+
+/// Exit code used when entries regressed beyond the allowed limit.
+pub const EXIT_REGRESSION: i32 = 3;
+/// Exit code used when entries regressed beyond `thresholds.warn` but none beyond
+/// `thresholds.error`.
+pub const EXIT_WARNING: i32 = 4;
+
+/// How much an entry may increase before it counts as a regression.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Limit {
+    Percent(f64),
+    Gas(i64),
+}
+
+impl FromStr for Limit {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        match s.strip_suffix('%') {
+            Some(percent) => percent
+                .trim()
+                .parse()
+                .map(Limit::Percent)
+                .map_err(|_| format!("invalid percentage: {}", s)),
+            None => {
+                s.replace('_', "").parse().map(Limit::Gas).map_err(|_| {
+                    format!("expected a percentage like 1.5% or an amount of gas: {}", s)
+                })
+            }
+        }
+    }
+}
+
+impl fmt::Display for Limit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Limit::Percent(percent) => write!(f, "+{}%", percent),
+            Limit::Gas(gas) => write!(f, "+{} gas", gas),
+        }
+    }
+}
+
+impl Limit {
+    /// Whether the cell increased beyond the limit, which changes within noise never do.
+    pub fn exceeded_by(&self, cell: &Cell) -> bool {
+        if cell.noise {
+            return false;
+        }
+        match self {
+            Limit::Percent(limit) => cell.delta_percent.is_some_and(|p| p > *limit),
+            Limit::Gas(limit) => cell.delta.is_some_and(|d| d > *limit),
+        }
+    }
+}
+
+/// Lists every entry and file whose gas increased beyond the limit, or beyond the entry's threshold
+/// without one, optionally leaving out methods with too few samples. Unstable entries are left out.
+pub fn regressions(
+    comparison: &Comparison,
+    limit: Option<Limit>,
+    skip_few_samples: bool,
+) -> Vec<String> {
+    let mut regressions = vec![];
+    for row in &comparison.entries {
+        if row.unstable || skip_few_samples && row.few_samples {
+            continue;
+        }
+        for (cell, file) in row.cells.iter().zip(&comparison.files) {
+            let exceeded = |cell: &&Cell| match limit {
+                Some(limit) => limit.exceeded_by(cell),
+                None => cell.status == Status::Increased,
+            };
+            let Some(cell) = cell.as_ref().filter(exceeded) else {
+                continue;
+            };
+            regressions.push(describe(row, cell, file));
+        }
+    }
+    regressions
+}
+
+/// Lists every entry and file whose increase has the given severity, leaving out unstable entries.
+pub fn severe(comparison: &Comparison, severity: Severity) -> Vec<String> {
+    let mut severe = vec![];
+    for row in comparison.entries.iter().filter(|row| !row.unstable) {
+        for (cell, file) in row.cells.iter().zip(&comparison.files) {
+            if let Some(cell) = cell.as_ref().filter(|c| c.severity == Some(severity)) {
+                severe.push(describe(row, cell, file));
+            }
+        }
+    }
+    severe
+}
+
+/// The exit code for increases of the given severity.
+pub fn exit_code(severity: Severity) -> i32 {
+    match severity {
+        Severity::Error => EXIT_REGRESSION,
+        Severity::Warning => EXIT_WARNING,
+    }
+}
+
+fn describe(row: &Row, cell: &Cell, file: &str) -> String {
+    format!(
+        "{}: {:+} gas ({:+.1}%) in {}",
+        row.name,
+        cell.delta.unwrap_or_default(),
+        cell.delta_percent.unwrap_or_default(),
+        file
+    )
+}
+
+impl<'de> Deserialize<'de> for Limit {
+    /// Accepts strings like `"2%"` or `"5_000"` as well as a plain amount of gas.
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Raw {
+            Gas(i64),
+            Text(String),
+        }
+        match Raw::deserialize(deserializer)? {
+            Raw::Gas(gas) => Ok(Limit::Gas(gas)),
+            Raw::Text(text) => text.parse().map_err(de::Error::custom),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{comparison::tests::compare, Config};
+
+    /// `Vault.deposit` up 10% and 10000 gas, `Vault.withdraw` up 1% and 500 gas.
+    fn comparison(config: &Config) -> Comparison {
+        compare(
+            &[
+                &[("Vault.deposit", &[100_000]), ("Vault.withdraw", &[50_000])],
+                &[("Vault.deposit", &[110_000]), ("Vault.withdraw", &[50_500])],
+            ],
+            config,
+        )
+    }
+
+    fn names(regressions: Vec<String>) -> Vec<String> {
+        let mut names: Vec<String> = regressions
+            .iter()
+            .map(|regression| regression.split(':').next().unwrap().to_owned())
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn percentage_and_gas_limits() {
+        let comparison = comparison(&Config::default());
+        let limited = |limit: &str| {
+            names(regressions(
+                &comparison,
+                Some(limit.parse().unwrap()),
+                false,
+            ))
+        };
+        assert_eq!(limited("5%"), ["Vault.deposit"]);
+        assert_eq!(limited("0.5%"), ["Vault.deposit", "Vault.withdraw"]);
+        assert_eq!(limited("10%"), Vec::<String>::new());
+        assert_eq!(limited("1_000"), ["Vault.deposit"]);
+        assert_eq!(limited("400"), ["Vault.deposit", "Vault.withdraw"]);
+        assert_eq!(limited("10000"), Vec::<String>::new());
+    }
+
+    #[test]
+    fn without_a_limit_the_thresholds_decide() {
+        let margin = comparison(&Config::parse("[thresholds]\nmargin = 2.0").unwrap());
+        assert_eq!(names(regressions(&margin, None, false)), ["Vault.deposit"]);
+        let all = regressions(&comparison(&Config::default()), None, false);
+        assert_eq!(all[0], "Vault.deposit: +10000 gas (+10.0%) in b.json");
+        assert_eq!(all.len(), 2);
+    }
+
+    #[test]
+    fn pass_warn_and_fail() {
+        let config = |warn: &str, error: &str| {
+            let text = format!("[thresholds]\nwarn = \"{}\"\nerror = \"{}\"", warn, error);
+            comparison(&Config::parse(&text).unwrap())
+        };
+        let passed = config("20%", "50%");
+        assert!(severe(&passed, Severity::Warning).is_empty());
+        assert!(severe(&passed, Severity::Error).is_empty());
+
+        let warned = config("0.5%", "20000");
+        assert_eq!(
+            names(severe(&warned, Severity::Warning)),
+            ["Vault.deposit", "Vault.withdraw"]
+        );
+        assert!(severe(&warned, Severity::Error).is_empty());
+
+        let failed = config("0.5%", "5%");
+        assert_eq!(names(severe(&failed, Severity::Error)), ["Vault.deposit"]);
+        assert_eq!(
+            names(severe(&failed, Severity::Warning)),
+            ["Vault.withdraw"]
+        );
+
+        assert_eq!(exit_code(Severity::Error), EXIT_REGRESSION);
+        assert_eq!(exit_code(Severity::Warning), EXIT_WARNING);
+    }
+
+    #[test]
+    fn unstable_entries_pass() {
+        let mut comparison = comparison(&Config::default());
+        comparison.mark_unstable(&["Vault.deposit".to_owned()].into());
+        assert_eq!(
+            names(regressions(&comparison, Some(Limit::Gas(0)), false)),
+            ["Vault.withdraw"]
+        );
+    }
+}
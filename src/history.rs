@@ -0,0 +1,181 @@
+use std::{
+    collections::HashMap,
+    process::Command,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{Context, Result};
+use eth_gas_delta::{
+    report::{Entry, Info, MethodIdentifier, RawDeployment, RawMethod},
+    GasReport,
+};
+use rusqlite::{params, Connection};
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS runs (
+        id INTEGER PRIMARY KEY,
+        tag TEXT NOT NULL,
+        branch TEXT,
+        recorded INTEGER NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS entries (
+        run INTEGER NOT NULL REFERENCES runs(id) ON DELETE CASCADE,
+        contract TEXT NOT NULL,
+        method TEXT,
+        signature TEXT,
+        gas INTEGER NOT NULL,
+        calls INTEGER,
+        size INTEGER
+    );
+    CREATE INDEX IF NOT EXISTS entries_run ON entries(run);
+";
+
+/// A database of reports recorded per commit, with one averaged gas value per entry like a
+/// snapshot. Deployments are the entries without a method.
+pub struct History {
+    connection: Connection,
+}
+
+impl History {
+    pub fn open(path: &str) -> Result<Self> {
+        let connection = Connection::open(path).with_context(|| format!("cannot open {}", path))?;
+        connection.execute_batch("PRAGMA foreign_keys = ON;")?;
+        connection
+            .execute_batch(SCHEMA)
+            .with_context(|| format!("cannot set up {}", path))?;
+        Ok(History { connection })
+    }
+
+    /// Stores the report as the run of `tag`, replacing an earlier one of the same tag and branch.
+    /// Returns the number of entries stored.
+    pub fn record(&mut self, report: &GasReport, tag: &str, branch: Option<&str>) -> Result<usize> {
+        let recorded = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs() as i64;
+        let transaction = self.connection.transaction()?;
+        transaction.execute(
+            "DELETE FROM runs WHERE tag = ?1 AND branch IS ?2",
+            params![tag, branch],
+        )?;
+        transaction.execute(
+            "INSERT INTO runs (tag, branch, recorded) VALUES (?1, ?2, ?3)",
+            params![tag, branch, recorded],
+        )?;
+        let run = transaction.last_insert_rowid();
+        let mut count = 0;
+        {
+            let mut insert = transaction.prepare(
+                "INSERT INTO entries (run, contract, method, signature, gas, calls, size)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            )?;
+            for method in report.info.methods.values() {
+                if method.gas_data.is_empty() {
+                    continue;
+                }
+                let gas = Entry::Method(method.clone()).avg_gas();
+                insert.execute(params![
+                    run,
+                    method.method.contract,
+                    method.method.method,
+                    method.signature,
+                    i64::try_from(gas).context("gas value too large to record")?,
+                    method.number_of_calls as i64,
+                    None::<i64>,
+                ])?;
+                count += 1;
+            }
+            for deployment in &report.info.deployments {
+                if deployment.gas_data.is_empty() {
+                    continue;
+                }
+                let gas = Entry::Deployment(deployment.clone()).avg_gas();
+                insert.execute(params![
+                    run,
+                    deployment.name,
+                    None::<&str>,
+                    None::<&str>,
+                    i64::try_from(gas).context("gas value too large to record")?,
+                    None::<i64>,
+                    deployment.code_size().map(|size| size as i64),
+                ])?;
+                count += 1;
+            }
+        }
+        transaction.commit()?;
+        Ok(count)
+    }
+
+    /// The tag and report of the most recent run, on `branch` if given.
+    pub fn latest(&self, branch: Option<&str>) -> Result<Option<(String, GasReport)>> {
+        Ok(self.runs(branch, 1)?.pop())
+    }
+
+    /// The tags and reports of the `last` most recent runs, on `branch` if given, oldest first.
+    pub fn runs(&self, branch: Option<&str>, last: usize) -> Result<Vec<(String, GasReport)>> {
+        let mut query = self.connection.prepare(
+            "SELECT id, tag FROM runs WHERE ?1 IS NULL OR branch = ?1
+             ORDER BY recorded DESC, id DESC LIMIT ?2",
+        )?;
+        let runs = query
+            .query_map(params![branch, last as i64], |row| {
+                Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        runs.into_iter()
+            .rev()
+            .map(|(run, tag)| Ok((tag, self.report(run)?)))
+            .collect()
+    }
+
+    fn report(&self, run: i64) -> Result<GasReport> {
+        let mut query = self.connection.prepare(
+            "SELECT contract, method, signature, gas, calls, size FROM entries WHERE run = ?1",
+        )?;
+        let mut rows = query.query(params![run])?;
+        let mut methods = HashMap::new();
+        let mut deployments = vec![];
+        while let Some(row) = rows.next()? {
+            let contract: String = row.get(0)?;
+            let gas = row.get::<_, i64>(3)? as u64;
+            match row.get::<_, Option<String>>(1)? {
+                Some(method) => {
+                    let signature: String = row.get::<_, Option<String>>(2)?.unwrap_or_default();
+                    let key = format!("{}_{}", contract, signature);
+                    let method = RawMethod {
+                        key: key.clone(),
+                        method: MethodIdentifier { contract, method },
+                        signature,
+                        gas_data: vec![gas].into(),
+                        number_of_calls: row.get::<_, Option<i64>>(4)?.unwrap_or(1) as usize,
+                        tests: HashMap::new(),
+                    };
+                    methods.insert(key, method);
+                }
+                None => deployments.push(RawDeployment {
+                    name: contract,
+                    gas_data: vec![gas].into(),
+                    deployed_bytecode: None,
+                    code_size: row.get::<_, Option<i64>>(5)?.map(|size| size as usize),
+                }),
+            }
+        }
+        deployments.sort_by(|l, r| l.name.cmp(&r.name));
+        Ok(GasReport {
+            info: Info {
+                methods,
+                deployments,
+            },
+        })
+    }
+}
+
+/// The checked out git branch, if any.
+pub fn current_branch() -> Option<String> {
+    let output = Command::new("git")
+        .args(["rev-parse", "--abbrev-ref", "HEAD"])
+        .output()
+        .ok()?;
+    let branch = String::from_utf8(output.stdout).ok()?.trim().to_owned();
+    (output.status.success() && branch != "HEAD").then_some(branch)
+}
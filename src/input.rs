@@ -0,0 +1,541 @@
+use std::{
+    borrow::Cow,
+    fmt, fs,
+    io::{self, Read},
+    path::Path,
+    process::Command,
+    time::Instant,
+};
+
+use anyhow::{bail, Context, Result};
+use eth_gas_delta::{
+    intrinsic,
+    parser::{self, ParseOptions},
+    GasReport, Parsers,
+};
+use itertools::Itertools;
+
+use crate::{artifact::Artifact, estimate::Estimates, etherscan::Etherscan, history::History};
+
+/// How the report files are arranged.
+#[derive(clap::ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Order {
+    /// As given on the command line, with directories and patterns sorted by path
+    #[default]
+    Given,
+    /// By path
+    Name,
+    /// By modification time, oldest first
+    Mtime,
+}
+
+/// How reports are parsed.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ReadOptions<'a> {
+    /// Leave out malformed entries with a warning.
+    pub lenient: bool,
+    /// Fold longer gas arrays into a sketch while parsing, unless `lenient`.
+    pub max_samples: Option<usize>,
+    /// Name of the format to parse in instead of detecting it.
+    pub format: Option<&'static str>,
+    /// Network whose results are read from reports holding several.
+    pub network: Option<&'a str>,
+    /// Read the results of every network of such reports as a report of its own.
+    pub across_networks: bool,
+    /// Subtract the base cost of a transaction from every call.
+    pub exclude_intrinsic: bool,
+    /// Subtract the estimated calldata gas too.
+    pub exclude_calldata: bool,
+}
+
+/// The results of one network of a report, or the whole report without networks.
+type Network<'a> = (Option<String>, Cow<'a, [u8]>);
+
+/// Where a report is read from.
+#[derive(Debug, Clone)]
+pub enum Source {
+    Path(String),
+    Stdin,
+    /// An `http://` or `https://` URL that is downloaded.
+    Url(String),
+    /// A `<ref>:<path>` spec resolved with `git show`.
+    GitObject(String),
+    /// A report stored as a CI artifact.
+    Artifact(Artifact),
+    /// The latest run in a history database, given as `<db>[@<branch>]`.
+    Recorded(String),
+    /// `eth_estimateGas` results for the calls listed in a file, from the file's node or the given
+    /// one.
+    Estimated {
+        path: String,
+        rpc_url: Option<String>,
+    },
+    /// Recent transactions of verified contracts.
+    Etherscan(Etherscan),
+    /// Several runs of the same commit pooled into one baseline.
+    Pooled(Vec<Source>),
+    /// A report with a custom column name, given as `<label>=<file>`.
+    Labeled(String, Box<Source>),
+    /// A report listed in a manifest with its own format or network.
+    Configured {
+        source: Box<Source>,
+        format: Option<&'static str>,
+        network: Option<String>,
+    },
+}
+
+impl Source {
+    /// Interprets a command line argument, where `-` stands for stdin and `<label>=<file>` names
+    /// the column.
+    pub fn from_arg(arg: &str) -> Self {
+        if let Some((label, file)) = arg.split_once('=') {
+            if !label.is_empty() && !label.contains(['/', ':']) && !Path::new(arg).exists() {
+                return Source::Labeled(label.to_owned(), Box::new(Source::from_arg(file)));
+            }
+        }
+        match arg {
+            "-" => Source::Stdin,
+            url if url.starts_with("http://") || url.starts_with("https://") => {
+                Source::Url(url.to_owned())
+            }
+            path => Source::Path(path.to_owned()),
+        }
+    }
+
+    /// The file system paths the report is read from.
+    pub fn paths(&self) -> Vec<&str> {
+        match self {
+            Source::Path(path) => vec![path],
+            Source::Pooled(sources) => sources.iter().flat_map(Source::paths).collect(),
+            Source::Labeled(_, source) | Source::Configured { source, .. } => source.paths(),
+            _ => vec![],
+        }
+    }
+
+    /// Name used for the report's column.
+    pub fn name(&self) -> String {
+        match self {
+            Source::Path(path) => Path::file_name(Path::new(path))
+                .unwrap_or_default()
+                .to_string_lossy()
+                .into_owned(),
+            Source::Stdin => "stdin".to_owned(),
+            Source::Url(url) => {
+                let path = url.split(['?', '#']).next().unwrap_or_default();
+                path.trim_end_matches('/')
+                    .rsplit('/')
+                    .next()
+                    .unwrap_or(url)
+                    .to_owned()
+            }
+            Source::GitObject(spec) => spec.clone(),
+            Source::Artifact(artifact) => artifact.spec.clone(),
+            Source::Recorded(spec) => spec.clone(),
+            Source::Estimated { .. } => "estimate".to_owned(),
+            Source::Etherscan(_) => "etherscan".to_owned(),
+            Source::Pooled(_) => "baseline".to_owned(),
+            Source::Labeled(label, _) => label.clone(),
+            Source::Configured { source, .. } => source.name(),
+        }
+    }
+
+    /// The input format a file's extension stands for, `yaml` for `.yaml` and `.yml` files and
+    /// `toml` for `.toml` files.
+    fn format(&self) -> Option<&'static str> {
+        let path = match self {
+            Source::Path(path) | Source::Url(path) => path,
+            Source::Labeled(_, source) | Source::Configured { source, .. } => {
+                return source.format()
+            }
+            _ => return None,
+        };
+        let path = path.trim_end_matches(".gz").trim_end_matches(".zst");
+        match path.rsplit_once('.')?.1.to_ascii_lowercase().as_str() {
+            "yaml" | "yml" => Some("yaml"),
+            "toml" => Some("toml"),
+            _ => None,
+        }
+    }
+
+    /// `options` with the format and network a manifest gives the source, which take precedence.
+    fn options<'b>(&'b self, options: ReadOptions<'b>) -> ReadOptions<'b> {
+        match self {
+            Source::Labeled(_, source) => source.options(options),
+            Source::Configured {
+                source,
+                format,
+                network,
+            } => ReadOptions {
+                format: format.or(options.format),
+                network: network.as_deref().or(options.network),
+                ..source.options(options)
+            },
+            _ => options,
+        }
+    }
+
+    /// Reads the report, decompressing gzip and zstd transparently.
+    pub fn read(&self) -> Result<Vec<u8>> {
+        decompress(self.read_raw()?)
+    }
+
+    fn read_raw(&self) -> Result<Vec<u8>> {
+        Ok(match self {
+            Source::Path(path) => {
+                fs::read(path).with_context(|| format!("cannot read {}", path))?
+            }
+            Source::Stdin => {
+                let mut bytes = vec![];
+                io::stdin()
+                    .read_to_end(&mut bytes)
+                    .context("cannot read stdin")?;
+                bytes
+            }
+            Source::Url(url) => {
+                let mut bytes = vec![];
+                ureq::get(url)
+                    .call()
+                    .with_context(|| format!("cannot download {}", url))?
+                    .into_reader()
+                    .read_to_end(&mut bytes)
+                    .with_context(|| format!("cannot download {}", url))?;
+                bytes
+            }
+            Source::GitObject(spec) => {
+                let output = Command::new("git")
+                    .args(["show", spec])
+                    .output()
+                    .context("cannot run git")?;
+                if !output.status.success() {
+                    bail!(
+                        "git show {} failed: {}",
+                        spec,
+                        String::from_utf8_lossy(&output.stderr).trim()
+                    );
+                }
+                output.stdout
+            }
+            Source::Artifact(artifact) => artifact
+                .fetch()
+                .with_context(|| format!("cannot fetch artifact {}", artifact.spec))?,
+            Source::Recorded(spec) => {
+                let (db, branch) = spec.rsplit_once('@').unwrap_or((spec, "main"));
+                let (tag, report) = History::open(db)?
+                    .latest(Some(branch))?
+                    .with_context(|| format!("no run on {} is recorded in {}", branch, db))?;
+                log::info!("baseline {} from {}", tag, db);
+                serde_json::to_vec(&report)?
+            }
+            Source::Estimated { path, rpc_url } => {
+                serde_json::to_vec(&Estimates::load(path)?.report(rpc_url.as_deref())?)?
+            }
+            Source::Etherscan(etherscan) => serde_json::to_vec(&etherscan.report()?)?,
+            Source::Pooled(_) => bail!("a pooled baseline is only read as a report"),
+            Source::Labeled(_, source) | Source::Configured { source, .. } => source.read_raw()?,
+        })
+    }
+
+    /// Reads and parses the report, of `options.network` if it holds several.
+    pub fn report(&self, options: ReadOptions) -> Result<GasReport> {
+        if let Source::Pooled(sources) = self {
+            let options = ReadOptions {
+                across_networks: false,
+                ..options
+            };
+            return Ok(Source::pool(sources, options)?.remove(0).1);
+        }
+        let start = Instant::now();
+        let bytes = self.read()?;
+        let options = ReadOptions {
+            across_networks: false,
+            ..self.options(options)
+        };
+        let (_, bytes) = self.networks(&bytes, options)?.remove(0);
+        self.parse(&bytes, self.name(), options, start)
+    }
+
+    /// Reads and parses the reports of a JSON array or NDJSON file, which are named after the
+    /// source and numbered, e.g. `runs.json#2`, or the single report of other files. With
+    /// `options.across_networks` every network of a report gets its own, e.g. `gas.json#arbitrum`.
+    pub fn reports(&self, options: ReadOptions) -> Result<Vec<(String, GasReport)>> {
+        if let Source::Pooled(sources) = self {
+            let pooled = Source::pool(sources, options)?.into_iter();
+            return Ok(pooled
+                .map(|(network, report)| match network {
+                    Some(network) if options.across_networks => {
+                        (format!("{}#{}", self.name(), network), report)
+                    }
+                    _ => (self.name(), report),
+                })
+                .collect());
+        }
+        let start = Instant::now();
+        let bytes = self.read()?;
+        let options = self.options(options);
+        let documents = parser::split(&bytes);
+        let bundled = documents.len() > 1;
+        let mut reports = vec![];
+        for (index, document) in documents.iter().enumerate() {
+            let mut name = self.name();
+            if bundled {
+                name = format!("{}#{}", name, index + 1);
+            }
+            for (network, document) in self.networks(document, options)? {
+                let name = match network {
+                    Some(network) if options.across_networks => format!("{}#{}", name, network),
+                    _ => name.clone(),
+                };
+                let report = self.parse(&document, name.clone(), options, start)?;
+                reports.push((name, report));
+            }
+        }
+        Ok(reports)
+    }
+
+    /// The runs of a pooled baseline merged into one report per network `options` asks for, or
+    /// into a single report if they don't hold several.
+    fn pool(sources: &[Source], options: ReadOptions) -> Result<Vec<(Option<String>, GasReport)>> {
+        let mut pooled: Vec<(Option<String>, Vec<GasReport>)> = vec![];
+        for source in sources {
+            let start = Instant::now();
+            let bytes = source.read()?;
+            let options = source.options(options);
+            for (network, document) in source.networks(&bytes, options)? {
+                let report = source.parse(&document, source.name(), options, start)?;
+                match pooled.iter_mut().find(|(name, _)| *name == network) {
+                    Some((_, reports)) => reports.push(report),
+                    None => pooled.push((network, vec![report])),
+                }
+            }
+        }
+        if pooled.is_empty() {
+            pooled.push((None, vec![]));
+        }
+        Ok(pooled
+            .into_iter()
+            .map(|(network, reports)| (network, GasReport::merge(reports)))
+            .collect())
+    }
+
+    /// The results of the networks `options` asks for, or the whole report if it doesn't hold
+    /// several.
+    fn networks<'b>(&self, bytes: &'b [u8], options: ReadOptions) -> Result<Vec<Network<'b>>> {
+        let Some(mut networks) = parser::networks(bytes) else {
+            return Ok(vec![(None, Cow::Borrowed(bytes))]);
+        };
+        let names = || networks.iter().map(|(name, _)| name.as_str()).join(", ");
+        if let Some(network) = options.network {
+            let Some(index) = networks.iter().position(|(name, _)| name == network) else {
+                bail!(
+                    "{} has no results for {}, only for {}",
+                    self.name(),
+                    network,
+                    names()
+                );
+            };
+            networks = vec![networks.remove(index)];
+        } else if networks.len() > 1 && !options.across_networks {
+            bail!(
+                "{} holds results for several networks ({}), pick one with --network-key or \
+                 compare them with --across-networks",
+                self.name(),
+                names()
+            );
+        }
+        if networks.is_empty() {
+            bail!("{} holds results for no network", self.name());
+        }
+        Ok(networks
+            .into_iter()
+            .map(|(name, bytes)| (Some(name), Cow::Owned(bytes)))
+            .collect())
+    }
+
+    fn parse(
+        &self,
+        bytes: &[u8],
+        name: String,
+        options: ReadOptions,
+        start: Instant,
+    ) -> Result<GasReport> {
+        let parse = ParseOptions {
+            lenient: options.lenient,
+            max_samples: options.max_samples,
+        };
+        let (mut report, skipped) = Parsers::default()
+            .parse(bytes, options.format.or(self.format()), parse)
+            .with_context(|| format!("cannot parse {}", name))?;
+        for error in skipped {
+            log::warn!("{}: skipping {}: {}", name, error.path, error.message);
+        }
+        if options.exclude_intrinsic {
+            intrinsic::exclude(&mut report, options.exclude_calldata);
+        }
+        log::info!(
+            "read {} ({} bytes): {} methods, {} deployments in {:.1?}",
+            name,
+            bytes.len(),
+            report.info.methods.len(),
+            report.info.deployments.len(),
+            start.elapsed()
+        );
+        Ok(report)
+    }
+}
+
+/// Expands directories to the (possibly compressed) JSON files they contain and glob patterns to
+/// their matches, both sorted by path.
+pub fn expand(args: &[String]) -> Result<Vec<Source>> {
+    let mut sources = vec![];
+    for arg in args {
+        let source = Source::from_arg(arg);
+        if !matches!(source, Source::Path(_)) {
+            sources.push(source);
+        } else if Path::new(arg).is_dir() {
+            let entries = fs::read_dir(arg).with_context(|| format!("cannot read {}", arg))?;
+            let mut paths = vec![];
+            for entry in entries {
+                let path = entry
+                    .with_context(|| format!("cannot read {}", arg))?
+                    .path();
+                if path.is_file() && is_report(&path) {
+                    paths.push(path);
+                }
+            }
+            paths.sort();
+            sources.extend(paths.iter().map(|path| to_source(path)));
+        } else if arg.contains(['*', '?', '[']) {
+            let matches = glob::glob(arg).with_context(|| format!("invalid pattern {}", arg))?;
+            let mut paths = matches.collect::<Result<Vec<_>, _>>()?;
+            if paths.is_empty() {
+                bail!("no files match {}", arg);
+            }
+            paths.sort();
+            sources.extend(paths.iter().map(|path| to_source(path)));
+        } else {
+            sources.push(source);
+        }
+    }
+    Ok(sources)
+}
+
+impl fmt::Display for Source {
+    /// Where the report comes from, for error messages.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Source::Path(path) | Source::Url(path) | Source::GitObject(path) => f.write_str(path),
+            Source::Labeled(_, source) | Source::Configured { source, .. } => source.fmt(f),
+            _ => f.write_str(&self.name()),
+        }
+    }
+}
+
+/// Arranges the sources, keeping those that are not local files (stdin, URLs) at the end.
+pub fn arrange(sources: &mut [Source], order: Order) {
+    match order {
+        Order::Given => {}
+        Order::Name => sources.sort_by_key(|source| {
+            let path = source.paths().first().map(|path| path.to_string());
+            (path.is_none(), path)
+        }),
+        Order::Mtime => sources.sort_by_key(|source| {
+            // Unreadable files go last and fail when they are read.
+            let modified = source.paths().first().and_then(|path| {
+                fs::metadata(path)
+                    .and_then(|metadata| metadata.modified())
+                    .ok()
+            });
+            (modified.is_none(), modified)
+        }),
+    }
+}
+
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+
+/// Decompresses gzip or zstd data, recognized by its magic bytes, and returns anything else as is.
+fn decompress(bytes: Vec<u8>) -> Result<Vec<u8>> {
+    if bytes.starts_with(&GZIP_MAGIC) {
+        log::debug!("decompressing gzip");
+        let mut decompressed = vec![];
+        flate2::read::MultiGzDecoder::new(&bytes[..])
+            .read_to_end(&mut decompressed)
+            .context("invalid gzip data")?;
+        Ok(decompressed)
+    } else if bytes.starts_with(&ZSTD_MAGIC) {
+        log::debug!("decompressing zstd");
+        zstd::decode_all(&bytes[..]).context("invalid zstd data")
+    } else {
+        Ok(bytes)
+    }
+}
+
+/// Whether a file in a directory looks like a report, possibly compressed.
+fn is_report(path: &Path) -> bool {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    [".json", ".json.gz", ".json.zst", ".ndjson"]
+        .iter()
+        .any(|ext| name.ends_with(ext))
+}
+
+fn to_source(path: &Path) -> Source {
+    Source::Path(path.to_string_lossy().into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    /// Writes a report of a deposit costing `mainnet` and `arbitrum` gas on the two networks.
+    fn networks(name: &str, mainnet: u64, arbitrum: u64) -> Source {
+        let path =
+            std::env::temp_dir().join(format!("eth-gas-delta-{}-{}", std::process::id(), name));
+        let report = |gas| {
+            json!({ "info": { "methods": { "Vault_deposit": {
+                "key": "Vault_deposit",
+                "contract": "Vault",
+                "method": "deposit",
+                "fnSig": "deposit()",
+                "gasData": [gas],
+                "numberOfCalls": 1
+            } }, "deployments": [] } })
+        };
+        let networks =
+            json!({ "networks": { "mainnet": report(mainnet), "arbitrum": report(arbitrum) } });
+        fs::write(&path, networks.to_string()).unwrap();
+        to_source(&path)
+    }
+
+    #[test]
+    fn pools_runs_of_several_networks() {
+        let pooled = Source::Pooled(vec![
+            networks("a.json", 50_000, 5_000),
+            networks("b.json", 52_000, 7_000),
+        ]);
+        let samples = |report: &GasReport| {
+            report.info.methods["Vault_deposit"]
+                .gas_data
+                .samples()
+                .to_vec()
+        };
+
+        let options = ReadOptions {
+            network: Some("arbitrum"),
+            ..ReadOptions::default()
+        };
+        assert_eq!(samples(&pooled.report(options).unwrap()), [5_000, 7_000]);
+        assert!(pooled.report(ReadOptions::default()).is_err());
+
+        let options = ReadOptions {
+            across_networks: true,
+            ..ReadOptions::default()
+        };
+        let reports = pooled.reports(options).unwrap();
+        let names: Vec<_> = reports.iter().map(|(name, _)| name.as_str()).collect();
+        // `json!` sorts the networks by name.
+        assert_eq!(names, ["baseline#arbitrum", "baseline#mainnet"]);
+        assert_eq!(samples(&reports[1].1), [50_000, 52_000]);
+    }
+}
@@ -0,0 +1,99 @@
+//! The gas a transaction is charged before any code runs, subtracted to compare execution gas
+//! alone.
+
+use crate::GasReport;
+
+/// Base cost of every transaction.
+pub const BASE: u64 = 21_000;
+/// Gas per zero and per nonzero byte of calldata.
+pub const ZERO_BYTE: u64 = 4;
+pub const NONZERO_BYTE: u64 = 16;
+
+/// Subtracts the base cost from every call of a method, and with `calldata` the calldata gas
+/// estimated from its signature. Deployments are left as they are.
+pub fn exclude(report: &mut GasReport, calldata: bool) {
+    for method in report.info.methods.values_mut() {
+        let mut gas = BASE;
+        if calldata {
+            gas += self::calldata(&method.signature);
+        }
+        method.gas_data.subtract(gas);
+        for gas_data in method.tests.values_mut() {
+            gas_data.subtract(gas);
+        }
+    }
+}
+
+/// Estimates the calldata gas of a call: the selector, then a word per static argument whose value
+/// takes the width of its type, padded with zeros. Dynamic arguments count their offset only, as
+/// their length is unknown.
+pub fn calldata(signature: &str) -> u64 {
+    let arguments = signature
+        .split_once('(')
+        .and_then(|(_, rest)| rest.strip_suffix(')'))
+        .unwrap_or_default();
+    4 * NONZERO_BYTE + components(arguments).into_iter().map(head).sum::<u64>()
+}
+
+/// The types of a comma-separated list, keeping tuples together.
+fn components(types: &str) -> Vec<&str> {
+    let (mut components, mut depth, mut start) = (vec![], 0, 0);
+    for (index, char) in types.char_indices() {
+        match char {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            ',' if depth == 0 => {
+                components.push(&types[start..index]);
+                start = index + 1;
+            }
+            _ => {}
+        }
+    }
+    components.push(&types[start..]);
+    components.retain(|ty| !ty.is_empty());
+    components
+}
+
+fn is_dynamic(ty: &str) -> bool {
+    if ty.ends_with("[]") || ty == "string" || ty == "bytes" {
+        return true;
+    }
+    if let Some((element, _)) = ty.strip_suffix(']').and_then(|ty| ty.rsplit_once('[')) {
+        return is_dynamic(element);
+    }
+    match ty.strip_prefix('(').and_then(|ty| ty.strip_suffix(')')) {
+        Some(tuple) => components(tuple).into_iter().any(is_dynamic),
+        None => false,
+    }
+}
+
+/// Gas of the words an argument takes in the head of the encoding.
+fn head(ty: &str) -> u64 {
+    if is_dynamic(ty) {
+        return word(1);
+    }
+    if let Some((element, length)) = ty.strip_suffix(']').and_then(|ty| ty.rsplit_once('[')) {
+        return length.parse::<u64>().unwrap_or(1) * head(element);
+    }
+    if let Some(tuple) = ty.strip_prefix('(').and_then(|ty| ty.strip_suffix(')')) {
+        return components(tuple).into_iter().map(head).sum();
+    }
+    let bits = |digits: &str| digits.parse::<u64>().unwrap_or(256);
+    let width = match ty {
+        "address" => 20,
+        "bool" => 1,
+        _ => match ty.strip_prefix("uint").or_else(|| ty.strip_prefix("int")) {
+            Some(digits) => bits(digits) / 8,
+            None => match ty.strip_prefix("bytes") {
+                Some(digits) => digits.parse().unwrap_or(32),
+                None => 32,
+            },
+        },
+    };
+    word(width.min(32))
+}
+
+/// Gas of a word with `nonzero` significant bytes.
+fn word(nonzero: u64) -> u64 {
+    nonzero * NONZERO_BYTE + (32 - nonzero) * ZERO_BYTE
+}
@@ -0,0 +1,29 @@
+//! Compare the gas usage of smart contracts across hardhat-gas-reporter reports.
+//!
+//! Reports are parsed with [`report::parse`], or with [`Parsers`] extended by custom
+//! [`ReportParser`]s, and compared with [`Comparison::from_reports`], which can then be rendered in
+//! any [`Format`].
+
+pub mod budget;
+pub mod comparison;
+pub mod config;
+pub mod doctor;
+pub mod gate;
+pub mod intrinsic;
+pub mod output;
+pub mod parser;
+pub mod report;
+pub mod rollup;
+pub mod snapshot;
+pub mod stats;
+mod tenderly;
+pub mod trace;
+
+pub use comparison::Comparison;
+pub use config::Config;
+pub use gate::Limit;
+pub use output::Format;
+pub use parser::{Parsers, ReportParser};
+pub use report::{Entry, GasReport};
+pub use snapshot::Snapshot;
+pub use stats::Aggregate;
@@ -0,0 +1,53 @@
+use std::time::Instant;
+
+use log::{Level, LevelFilter, Log, Metadata, Record};
+
+/// Writes log messages to stderr with the time since startup.
+struct Logger {
+    start: Instant,
+}
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let level = match record.level() {
+            Level::Error => "error",
+            Level::Warn => "warning",
+            Level::Info => "info",
+            Level::Debug => "debug",
+            Level::Trace => "trace",
+        };
+        eprintln!(
+            "[{:>7.3}s {}] {}",
+            self.start.elapsed().as_secs_f64(),
+            level,
+            record.args()
+        );
+    }
+
+    fn flush(&self) {}
+}
+
+/// Logs warnings by default, each `-v` adding a more detailed level and each `-q` removing one.
+pub fn init(verbose: u8, quiet: u8) {
+    let levels = [
+        LevelFilter::Off,
+        LevelFilter::Error,
+        LevelFilter::Warn,
+        LevelFilter::Info,
+        LevelFilter::Debug,
+        LevelFilter::Trace,
+    ];
+    let index = (2 + verbose as isize - quiet as isize).clamp(0, levels.len() as isize - 1);
+    log::set_max_level(levels[index as usize]);
+    let logger = Box::leak(Box::new(Logger {
+        start: Instant::now(),
+    }));
+    log::set_logger(logger).unwrap();
+}
@@ -1,229 +1,986 @@
-use std::{cmp::Ordering, collections::HashMap, fs::File, path::Path, vec};
-
-use clap::Parser;
-use itertools::Itertools;
-use serde::{Deserialize, Serialize};
-use tabled::{
-    builder::Builder,
-    settings::{object::Columns, Alignment, Modify, Style},
+use std::{
+    env, fs,
+    io::{self, IsTerminal},
+    panic,
+    path::Path,
+    process::ExitCode,
+    thread,
+    time::Duration,
 };
 
-#[derive(Debug, Serialize, Deserialize)]
-struct GasReport {
-    info: Info,
-}
+use anyhow::{bail, Context, Result};
 
-#[derive(Debug, Serialize, Deserialize)]
-struct Info {
-    methods: HashMap<String, RawMethod>,
-    deployments: Vec<RawDeployment>,
-}
+use eth_gas_delta::{
+    budget,
+    comparison::{CompareTo, Kind, Network, Row, Severity, BLOCK_SHARE},
+    doctor,
+    gate::{self, Limit},
+    output,
+    output::{TableStyle, Theme},
+    report::RawMethod,
+    rollup::L1Fee,
+    Comparison, Config, Format, GasReport, Snapshot,
+};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-struct RawDeployment {
-    name: String,
-    #[serde(rename = "gasData")]
-    gas_data: Vec<isize>,
-}
+use artifact::Artifact;
+use cli::{
+    Args, BatchArgs, CheckArgs, ColorChoice, Command, DiffArgs, DoctorArgs, FlakyArgs, GlobalArgs,
+    ImportRpcArgs, MergeArgs, OpcodesArgs, RecordArgs, ReportArgs, RunArgs, ServeArgs,
+    SnapshotArgs, TrendArgs, VersusArgs,
+};
+use etherscan::Etherscan;
+use history::History;
+use input::{ReadOptions, Source};
+use manifest::{Batch, Manifest};
+use pager::Paging;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-struct RawMethod {
-    key: String,
-    #[serde(flatten)]
-    method: MethodIdentifier,
-    #[serde(rename = "fnSig")]
-    signature: String,
-    #[serde(rename = "gasData")]
-    gas_data: Vec<isize>,
-    #[serde(rename = "numberOfCalls")]
-    number_of_calls: usize,
+mod abi;
+mod artifact;
+mod cache;
+mod cli;
+mod estimate;
+mod etherscan;
+mod history;
+mod input;
+mod logger;
+mod manifest;
+mod notify;
+mod onchain;
+mod pager;
+mod prices;
+mod run;
+mod serve;
+mod solidity;
+mod template;
+mod tui;
+mod unstable;
+mod watch;
+
+/// Exit code used when the comparison could not be made, e.g. because a report is missing or
+/// malformed.
+const EXIT_ERROR: u8 = 1;
+
+fn main() -> ExitCode {
+    let (command, global) = Args::parse_with_env().into_command();
+    logger::init(global.verbose, global.quiet);
+    match execute(command, global) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {:#}", e);
+            ExitCode::from(EXIT_ERROR)
+        }
+    }
 }
 
-#[derive(Parser, Debug)]
-#[command(author, version, about, long_about = None)]
-struct Args {
-    files: Vec<String>,
+fn execute(command: Command, mut global: GlobalArgs) -> Result<()> {
+    let mut config = Config::load(global.config.as_deref()).map_err(anyhow::Error::msg)?;
+    cache::configure(cache::Settings {
+        ttl: Duration::from_secs(global.cache_ttl),
+        offline: global.offline,
+    });
+    global.render.color = match global.color {
+        Some(ColorChoice::Always) => true,
+        Some(ColorChoice::Never) => false,
+        _ if env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty()) => false,
+        Some(ColorChoice::Auto) | None => config.color.unwrap_or(io::stdout().is_terminal()),
+    };
+    if io::stdout().is_terminal() {
+        global.render.width = match env::var("COLUMNS").ok().and_then(|c| c.parse().ok()) {
+            Some(columns) => Some(columns),
+            None => ratatui::crossterm::terminal::size()
+                .ok()
+                .map(|(columns, _)| columns.into()),
+        };
+    }
+    global.render.theme = match (global.theme, &config.colors) {
+        (None, Some(colors)) => colors.clone(),
+        (preset, _) => Theme::preset(preset.or(config.theme).unwrap_or_default()),
+    };
+    if global.render.gas_price.is_none() {
+        global.render.gas_price = prices::gas_price(&global);
+    }
+    if global.live_eth_price {
+        global.render.eth_price = prices::eth_usd();
+    }
+    if global.render.eth_price.is_some() && global.render.gas_price.is_none() {
+        log::warn!("--eth-price has no effect without a gas price");
+    }
+    if let Some(base_fee) = global.l1_base_fee {
+        match global.network.or(config.network).and_then(Network::rollup) {
+            Some(rollup) => {
+                let blob_base_fee = global.blob_base_fee.unwrap_or_default();
+                let fee = L1Fee::new(rollup, base_fee, blob_base_fee, &config.l1);
+                global.render.l1_fee = Some(fee);
+            }
+            None => log::warn!("--l1-base-fee needs --network optimism, base or arbitrum"),
+        }
+    }
+    override_config(&mut config, &global);
+
+    if matches!(&command, Command::Diff(args) if args.watch) {
+        global.paging = Paging::Never;
+    }
+    if global.render.plain {
+        let render = &mut global.render;
+        render.color = false;
+        render.width = None;
+        render.theme.arrows = false;
+        if render.style == TableStyle::Rounded {
+            render.style = TableStyle::Psql;
+        }
+        global.paging = Paging::Never;
+    }
+
+    match command {
+        Command::Diff(args) => diff(*args, &global, &config),
+        Command::Batch(args) => batch(args, &global, &config),
+        Command::Check(args) => check(args, &global, config),
+        Command::Report(args) => report(args, &global, config),
+        Command::Snapshot(args) => snapshot(args, &global),
+        Command::Merge(args) => merge(args, &global),
+        Command::Doctor(args) => doctor(args),
+        Command::Run(args) => run(args, &global, &config),
+        Command::Record(args) => record(args, &global),
+        Command::Trend(args) => trend(args, &global, config),
+        Command::ImportRpc(args) => import_rpc(args, &global),
+        Command::Versus(args) => versus(args, &global, config),
+        Command::Opcodes(args) => opcodes(args, &global),
+        Command::Flaky(args) => flaky(args, &global, config),
+        Command::Serve(args) => serve(args, &global, &config),
+        Command::Completions(args) => {
+            let mut command = Args::command_with_env();
+            let name = command.get_name().to_owned();
+            clap_complete::generate(args.shell, &mut command, name, &mut io::stdout());
+            Ok(())
+        }
+        Command::Man(args) => {
+            fs::create_dir_all(&args.dir).with_context(|| format!("cannot create {}", args.dir))?;
+            clap_mangen::generate_to(Args::command_with_env(), &args.dir)
+                .with_context(|| format!("cannot write the man pages to {}", args.dir))?;
+            eprintln!("Wrote the man pages to {}", args.dir);
+            Ok(())
+        }
+    }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
-struct MethodIdentifier {
-    contract: String,
-    method: String,
+/// Lets the command line override the config file. Filter lists from both apply.
+fn override_config(config: &mut Config, global: &GlobalArgs) {
+    if !global.label.is_empty() {
+        config.labels = global.label.clone();
+    }
+    config.block_limit = global
+        .block_limit
+        .or(global.network.map(Network::block_limit))
+        .or(config.block_limit);
+    config.thresholds.margin = global.threshold_percent.or(config.thresholds.margin);
+    config.thresholds.gas = global.threshold_gas.or(config.thresholds.gas);
+    config.compare_to = global.compare_to.or(config.compare_to);
+    config.sort = global.sort.or(config.sort);
+    config.reverse |= global.reverse;
+    config.only_changed |= global.only_changed;
+    config.collapse_overloads |= global.collapse_overloads;
+    config.normalize.ignore_case |= global.ignore_case;
+    config.normalize.strip_paths |= global.strip_paths;
+    config.normalize.trim |= global.trim_names;
+    if global.deployments_only {
+        config.only = Some(Kind::Deployment);
+    } else if global.methods_only {
+        config.only = Some(Kind::Method);
+    }
+    config.include.extend(global.include.iter().cloned());
+    config.exclude.extend(global.exclude.iter().cloned());
+    config.aggregate = global.aggregate.or(config.aggregate);
+    config.outliers = global.outliers.or(config.outliers);
+    config.thresholds.significance = global.significance.or(config.thresholds.significance);
+    config.thresholds.confidence |= global.confidence;
+    config.thresholds.block_share = global.block_share.or(config.thresholds.block_share);
+    config.thresholds.warn = global.warn_at.or(config.thresholds.warn);
+    config.thresholds.error = global.error_at.or(config.thresholds.error);
 }
 
-impl PartialOrd for MethodIdentifier {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.cmp(other))
+/// Compares the reports. If `options.lenient`, malformed entries and unreadable reports are left out with
+/// a warning.
+fn load(sources: &[Source], config: &Config, options: ReadOptions) -> Result<Comparison> {
+    // Parsing dominates with many large reports, so every file gets its own thread.
+    let parsed: Vec<Result<Vec<(String, GasReport)>>> = thread::scope(|scope| {
+        let handles: Vec<_> = sources
+            .iter()
+            .map(|source| scope.spawn(move || source.reports(options)))
+            .collect();
+        handles
+            .into_iter()
+            .map(|handle| handle.join().unwrap_or_else(|e| panic::resume_unwind(e)))
+            .collect()
+    });
+    let mut reports = vec![];
+    for (source, report) in sources.iter().zip(parsed) {
+        match report {
+            Ok(bundled) => reports.extend(bundled),
+            Err(e) if options.lenient => log::warn!("skipping {}: {:#}", source, e),
+            Err(e) => return Err(e),
+        }
+    }
+    if reports.is_empty() && !sources.is_empty() {
+        bail!("none of the reports could be read");
     }
+    Ok(Comparison::from_reports(reports, config))
 }
 
-impl Ord for MethodIdentifier {
-    fn cmp(&self, other: &Self) -> Ordering {
-        if self.contract == other.contract {
-            self.method.cmp(&other.method)
-        } else {
-            self.contract.cmp(&other.contract)
+fn write_output(comparison: &Comparison, global: &GlobalArgs, config: &Config) -> Result<()> {
+    let format = global.format.or(config.format);
+    if global.output.is_empty() {
+        if format.is_some_and(Format::is_binary) && global.template.is_none() {
+            bail!("xlsx can't be printed, write it to a file with --output gas.xlsx");
         }
+        let rendered = match &global.template {
+            Some(template) => template::render(template, comparison)?,
+            None => format
+                .unwrap_or(Format::Table)
+                .render(comparison, &global.render)
+                .map_err(anyhow::Error::msg)?,
+        };
+        pager::print(&rendered, global.paging);
+        return Ok(());
     }
+
+    // The files get their formats, the terminal still the table.
+    let options = output::Options {
+        color: false,
+        width: None,
+        ..global.render.clone()
+    };
+    for path in &global.output {
+        let rendered = match &global.template {
+            Some(template) => template::render(template, comparison)?.into_bytes(),
+            None => file_format(format, path).render_bytes(comparison, &options),
+        };
+        fs::write(path, rendered).with_context(|| format!("cannot write {}", path))?;
+    }
+    let rendered = Format::Table
+        .render(comparison, &global.render)
+        .map_err(anyhow::Error::msg)?;
+    pager::print(&rendered, global.paging);
+    Ok(())
 }
 
-#[derive(Debug, Clone)]
-enum Entry {
-    Deployment(RawDeployment),
-    Method(RawMethod),
+/// The format a file is written in, the extension only picks one when none was asked for.
+fn file_format(format: Option<Format>, path: &str) -> Format {
+    format
+        .or_else(|| Format::from_extension(path))
+        .unwrap_or(Format::Table)
 }
 
-impl PartialEq for Entry {
-    fn eq(&self, other: &Self) -> bool {
-        match (self, other) {
-            (Self::Deployment(l0), Self::Deployment(r0)) => l0.name == r0.name,
-            (Self::Method(l0), Self::Method(r0)) => l0.method == r0.method,
-            _ => false,
+fn diff(args: DiffArgs, global: &GlobalArgs, config: &Config) -> Result<()> {
+    let mut sources: Vec<Source> = args.base_ref.into_iter().map(Source::GitObject).collect();
+    if let Some(spec) = args.baseline_artifact {
+        sources.push(Source::Artifact(Artifact {
+            spec,
+            workflow: args.artifact_workflow,
+        }));
+    }
+    sources.extend(args.baseline_db.map(Source::Recorded));
+    if !args.baseline.is_empty() {
+        sources.push(Source::Pooled(input::expand(&args.baseline)?));
+    }
+    if let Some(path) = &args.manifest {
+        sources.extend(Manifest::load(path)?.sources(path)?);
+    }
+    let mut files = input::expand(&args.files)?;
+    input::arrange(&mut files, args.order);
+    sources.extend(files);
+    if !args.etherscan.is_empty() {
+        sources.push(Source::Etherscan(Etherscan {
+            contracts: args.etherscan,
+            chain_id: global
+                .network
+                .or(config.network)
+                .map_or(1, Network::chain_id),
+            transactions: args.etherscan_transactions,
+        }));
+    }
+    sources.extend(args.estimate.map(|path| Source::Estimated {
+        path,
+        rpc_url: global.rpc_url.clone(),
+    }));
+    if let Some(base) = &args.base {
+        let index = match base.parse::<usize>() {
+            Ok(position) if (1..=sources.len()).contains(&position) => position - 1,
+            _ => sources
+                .iter()
+                .position(|source| {
+                    source.paths().contains(&base.as_str()) || source.name() == *base
+                })
+                .with_context(|| format!("--base {} is not one of the files", base))?,
+        };
+        let source = sources.remove(index);
+        sources.insert(0, source);
+    }
+    if args.watch {
+        if sources.iter().any(|source| matches!(source, Source::Stdin)) {
+            bail!("--watch can't be used with stdin");
         }
+        let paths: Vec<&str> = sources.iter().flat_map(Source::paths).collect();
+        return watch::watch(&paths, || {
+            let mut comparison = load(&sources, config, global.read_options())?;
+            select_columns(&mut comparison, &args.columns)?;
+            artifacts(&mut comparison, &args.artifacts)?;
+            if let Some(path) = &args.unstable {
+                comparison.mark_unstable(&unstable::load(path)?);
+            }
+            write_output(&comparison, global, config)
+        });
     }
-}
 
-impl Eq for Entry {}
+    let mut comparison = load(&sources, config, global.read_options())?;
+    select_columns(&mut comparison, &args.columns)?;
+    artifacts(&mut comparison, &args.artifacts)?;
+    if let Some(path) = &args.unstable {
+        comparison.mark_unstable(&unstable::load(path)?);
+    }
+    if args.matrix {
+        println!("{}", output::matrix(&comparison, global.render.style));
+        return Ok(());
+    }
+    if let Some(name) = &args.detail {
+        let row = comparison
+            .entries
+            .iter()
+            .find(|row| &row.name == name)
+            .with_context(|| format!("no entry named {}", name))?;
+        println!("{}", output::histogram(&comparison, row));
+        return Ok(());
+    }
+    if let Some(name) = &args.by_test {
+        let row = comparison
+            .entries
+            .iter()
+            .find(|row| &row.name == name)
+            .with_context(|| format!("no entry named {}", name))?;
+        println!("{}", output::by_test(&comparison, row, global.render.style));
+        return Ok(());
+    }
+    if let Some(name) = &args.explain {
+        let row = comparison
+            .entries
+            .iter()
+            .find(|row| &row.name == name)
+            .with_context(|| format!("no entry named {}", name))?;
+        let explained = output::explain(&comparison, row, args.align, global.render.style);
+        println!("{}", explained);
+        return Ok(());
+    }
+    if args.interactive {
+        return Ok(tui::run(
+            &comparison,
+            config.sort.unwrap_or_default(),
+            config.reverse,
+        )?);
+    }
 
-impl PartialOrd for Entry {
-    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        Some(self.cmp(other))
+    if let Some(url) = &args.notify_webhook {
+        notify::notify_webhook(url, &comparison)?;
     }
+
+    if let Some(level) = args.github_annotations {
+        let annotations = output::github_annotations(&comparison, level);
+        if !annotations.is_empty() {
+            println!("{}", annotations);
+        }
+    }
+
+    write_output(&comparison, global, config)?;
+
+    if args.update_baseline {
+        update_baseline(
+            &sources,
+            &comparison,
+            args.approve,
+            global.read_options(),
+            config,
+        )?;
+    }
+
+    if let Some(limit) = args.fail_on_increase {
+        let limit = limit.or(config.thresholds.fail_on_increase);
+        let regressions = gate::regressions(&comparison, limit, args.skip_few_samples);
+        if !regressions.is_empty() {
+            match limit {
+                Some(limit) => eprintln!("Gas increased by more than {}:", limit),
+                None => eprintln!("Gas increased beyond the thresholds:"),
+            }
+            for regression in regressions {
+                eprintln!("  {}", regression);
+            }
+            std::process::exit(gate::EXIT_REGRESSION);
+        }
+    }
+    if args.fail_near_block_limit {
+        let near = comparison.near_block_limit();
+        if !near.is_empty() {
+            eprintln!(
+                "Gas exceeds {}% of the block gas limit:",
+                comparison.block_share.unwrap_or(BLOCK_SHARE)
+            );
+            for (row, share) in near {
+                eprintln!("  {}: {} gas ({:.1}%)", row.name, row.gas(), share);
+            }
+            std::process::exit(gate::EXIT_REGRESSION);
+        }
+    }
+    for severity in [Severity::Error, Severity::Warning] {
+        let severe = gate::severe(&comparison, severity);
+        if !severe.is_empty() {
+            match severity {
+                Severity::Error => eprintln!("Gas increased beyond the error threshold:"),
+                Severity::Warning => eprintln!("Gas increased beyond the warning threshold:"),
+            }
+            for entry in severe {
+                eprintln!("  {}", entry);
+            }
+            std::process::exit(gate::exit_code(severity));
+        }
+    }
+    Ok(())
 }
 
-impl Ord for Entry {
-    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        match (self, other) {
-            (Entry::Deployment(l), Entry::Deployment(r)) => l.name.cmp(&r.name),
-            (Entry::Deployment(_), Entry::Method(_)) => Ordering::Greater,
-            (Entry::Method(_), Entry::Deployment(_)) => Ordering::Less,
-            (Entry::Method(l), Entry::Method(r)) => l.method.cmp(&r.method),
+/// Locates the entries in the sources of the last build given to `--artifacts`, and with two
+/// builds checks the bytecode against the gas.
+fn artifacts(comparison: &mut Comparison, dirs: &[String]) -> Result<()> {
+    match dirs {
+        [] => {}
+        [dir] => solidity::locate(comparison, dir)?,
+        [before, after] => {
+            solidity::locate(comparison, after)?;
+            comparison.check_code(&solidity::code_changes(before, after)?);
         }
+        _ => bail!("--artifacts takes at most two builds, of the first and the last file"),
+    }
+    Ok(())
+}
+
+/// Keeps the columns given to `--columns`, all of them without any.
+fn select_columns(comparison: &mut Comparison, columns: &[String]) -> Result<()> {
+    if columns.is_empty() {
+        return Ok(());
     }
+    let count = comparison.files.len();
+    let indices = columns
+        .iter()
+        .map(|column| match column.parse::<i64>() {
+            Ok(position @ 1..) if position as usize <= count => Ok(position as usize - 1),
+            Ok(position @ ..=-1) if position.unsigned_abs() as usize <= count => {
+                Ok(count - position.unsigned_abs() as usize)
+            }
+            _ => comparison
+                .files
+                .iter()
+                .position(|file| file == column)
+                .with_context(|| format!("--columns {} is not one of the {} files", column, count)),
+        })
+        .collect::<Result<Vec<_>>>()?;
+    comparison.select_columns(&indices);
+    Ok(())
 }
 
-impl Entry {
-    fn avg_gas(&self) -> isize {
-        let gas_data = self.gas_data();
-        let sum: isize = gas_data.iter().sum();
-        sum / gas_data.len() as isize
+/// Rewrites the baseline file with the last report, keeping the baseline values of entries that
+/// increased beyond `approve`. Written as a snapshot when the baseline is one.
+fn update_baseline(
+    sources: &[Source],
+    comparison: &Comparison,
+    approve: Option<Limit>,
+    options: ReadOptions,
+    config: &Config,
+) -> Result<()> {
+    let mut baseline = &sources[0];
+    while let Source::Labeled(_, source) | Source::Configured { source, .. } = baseline {
+        baseline = source;
+    }
+    let Source::Path(path) = baseline else {
+        bail!("--update-baseline needs the baseline as a local file");
+    };
+    if sources.len() < 2 {
+        bail!("--update-baseline needs a report to take the new values from");
     }
+    let old = baseline.report(options)?;
+    let mut new = sources[sources.len() - 1].report(options)?;
 
-    fn has_gas_data(&self) -> bool {
-        self.gas_data().len() > 0
+    let rejected: Vec<&Row> = match approve {
+        Some(limit) => comparison
+            .entries
+            .iter()
+            .filter(|row| {
+                row.cells[0].is_some()
+                    && row
+                        .cells
+                        .last()
+                        .unwrap()
+                        .as_ref()
+                        .is_some_and(|cell| limit.exceeded_by(cell))
+            })
+            .collect(),
+        None => vec![],
+    };
+    for row in &rejected {
+        match row.kind {
+            Kind::Method => {
+                // The entries are listed by their current names.
+                let matches = |method: &RawMethod| {
+                    let mut method = method.clone();
+                    config.rename_method(&mut method);
+                    method.method.contract == row.contract
+                        && match &row.signature {
+                            Some(signature) => &method.signature == signature,
+                            None => Some(&method.method.method) == row.method.as_ref(),
+                        }
+                };
+                new.info.methods.retain(|_, method| !matches(method));
+                new.info.methods.extend(
+                    old.info
+                        .methods
+                        .iter()
+                        .filter(|(_, method)| matches(method))
+                        .map(|(key, method)| (key.clone(), method.clone())),
+                );
+            }
+            Kind::Deployment => {
+                let matches = |name: &str| config.contract_name(name) == row.contract;
+                let Some(kept) = old.info.deployments.iter().find(|d| matches(&d.name)) else {
+                    continue;
+                };
+                for deployment in &mut new.info.deployments {
+                    if matches(&deployment.name) {
+                        *deployment = kept.clone();
+                    }
+                }
+            }
+        }
     }
 
-    fn gas_data(&self) -> &Vec<isize> {
-        match self {
-            Entry::Deployment(depl) => &depl.gas_data,
-            Entry::Method(m) => &m.gas_data,
+    let text = fs::read(path).with_context(|| format!("cannot read {}", path))?;
+    let is_snapshot = serde_json::from_slice::<serde_json::Value>(&text)
+        .with_context(|| {
+            format!(
+                "--update-baseline cannot rewrite {}, which is not JSON",
+                path
+            )
+        })?
+        .get("schema")
+        .is_some();
+    let rendered = if is_snapshot {
+        serde_json::to_string_pretty(&Snapshot::from_report(new))?
+    } else {
+        serde_json::to_string_pretty(&new)?
+    };
+    fs::write(path, rendered + "\n").with_context(|| format!("cannot write {}", path))?;
+    eprintln!("Updated baseline {}", path);
+    if let Some(limit) = approve {
+        if !rejected.is_empty() {
+            eprintln!(
+                "Kept the baseline of entries that increased by more than {}:",
+                limit
+            );
+            for row in rejected {
+                eprintln!("  {}", row.name);
+            }
         }
     }
+    Ok(())
 }
 
-const BLOCK_LIMIT: isize = 30_000_000;
-const MARGIN: f64 = 0.1;
+fn check(args: CheckArgs, global: &GlobalArgs, mut config: Config) -> Result<()> {
+    let text = fs::read_to_string(&args.budgets)
+        .with_context(|| format!("cannot read {}", args.budgets))?;
+    let budgets = budget::parse(&text)
+        .map_err(anyhow::Error::msg)
+        .with_context(|| format!("invalid budgets in {}", args.budgets))?;
+    let comparison = load_single(&args.report, global, &mut config)?;
 
-fn main() {
-    let args = Args::parse();
+    let violations = budget::check(&budgets, &comparison);
+    if violations.is_empty() {
+        println!("All {} budgets met", budgets.len());
+        return Ok(());
+    }
+    eprintln!(
+        "{} of {} budgets violated:",
+        violations.len(),
+        budgets.len()
+    );
+    for violation in &violations {
+        eprintln!("  {}", violation);
+    }
+    std::process::exit(gate::EXIT_REGRESSION);
+}
 
-    // let mut reports: Vec<GasReport> = vec![];
+fn report(args: ReportArgs, global: &GlobalArgs, mut config: Config) -> Result<()> {
+    let comparison = load_single(&args.report, global, &mut config)?;
+    write_output(&comparison, global, &config)
+}
 
-    let mut data: HashMap<String, Vec<Option<Entry>>> = HashMap::new();
-    let mut filenames: Vec<&str> = Vec::new();
+/// A single report as a comparison, with every entry shown since none of them changed.
+fn load_single(report: &str, global: &GlobalArgs, config: &mut Config) -> Result<Comparison> {
+    config.only_changed = false;
+    load(&[Source::from_arg(report)], config, global.read_options())
+}
 
-    let file_count = args.files.len();
-    for (index, path) in args.files.iter().enumerate() {
-        let file = File::open(path).unwrap();
-        let report: GasReport = serde_json::from_reader(file).unwrap();
+fn run(args: RunArgs, global: &GlobalArgs, config: &Config) -> Result<()> {
+    let reports = run::collect(
+        &args.base,
+        args.head.as_deref(),
+        &args.command,
+        &args.report_path,
+    )?;
+    let comparison = Comparison::from_reports(reports, config);
+    write_output(&comparison, global, config)
+}
 
-        for depl in report.info.deployments {
-            if depl.gas_data.len() == 0 {
-                continue;
-            }
-            let key = depl.name.to_owned();
-            data.entry(key).or_insert(vec![None; file_count])[index] =
-                Some(Entry::Deployment(depl));
+fn record(args: RecordArgs, global: &GlobalArgs) -> Result<()> {
+    let report = Source::from_arg(&args.report).report(global.read_options())?;
+    let branch = args.branch.or_else(history::current_branch);
+    let count = History::open(&args.db)?.record(&report, &args.tag, branch.as_deref())?;
+    eprintln!(
+        "Recorded {} entries of {} on {} in {}",
+        count,
+        args.tag,
+        branch.as_deref().unwrap_or("no branch"),
+        args.db
+    );
+    Ok(())
+}
+
+fn trend(args: TrendArgs, global: &GlobalArgs, mut config: Config) -> Result<()> {
+    let runs = History::open(&args.db)?.runs(args.branch.as_deref(), args.last)?;
+    if runs.is_empty() {
+        bail!("no runs are recorded in {}", args.db);
+    }
+    // Each run is compared to the one before it, so regressions show where they happened.
+    config.compare_to.get_or_insert(CompareTo::Previous);
+    config.only_changed = false;
+    let comparison = Comparison::from_reports(runs, &config);
+    let row = comparison
+        .entries
+        .iter()
+        .find(|row| row.name == args.entry)
+        .with_context(|| format!("no entry named {} in {}", args.entry, args.db))?;
+    println!("{}", output::trend(&comparison, row, global.render.style));
+    Ok(())
+}
+
+fn opcodes(args: OpcodesArgs, global: &GlobalArgs) -> Result<()> {
+    let names = onchain::Names::new(&args.contracts, &[])?;
+    let before = onchain::breakdown(&args.node, &args.before, &names)?;
+    let after = match &args.after {
+        Some(hash) => {
+            let node = args.after_node.as_deref().unwrap_or(&args.node);
+            Some(onchain::breakdown(node, hash, &names)?)
         }
-        for (_, method) in report.info.methods {
-            if method.gas_data.len() == 0 {
+        None => None,
+    };
+    println!(
+        "{}",
+        output::opcodes(&before, after.as_ref(), global.render.style)
+    );
+    Ok(())
+}
+
+fn batch(args: BatchArgs, global: &GlobalArgs, config: &Config) -> Result<()> {
+    let pairs = Batch::load(&args.manifest)?.pairs(&args.manifest)?;
+    fs::create_dir_all(&args.dir).with_context(|| format!("cannot create {}", args.dir))?;
+    let format = global.format.or(config.format).unwrap_or(Format::Table);
+    let options = output::Options {
+        color: false,
+        width: None,
+        ..global.render.clone()
+    };
+    let mut comparisons = vec![];
+    for (name, sources) in pairs {
+        let comparison = load(&sources, config, global.read_options())
+            .with_context(|| format!("cannot compare {}", name))?;
+        let path = Path::new(&args.dir).join(format!("{}.{}", name, format.extension()));
+        fs::write(&path, format.render_bytes(&comparison, &options))
+            .with_context(|| format!("cannot write {}", path.display()))?;
+        comparisons.push((name, comparison));
+    }
+    let path = Path::new(&args.dir).join("summary.md");
+    fs::write(
+        &path,
+        output::batch(&comparisons, TableStyle::Markdown) + "\n",
+    )
+    .with_context(|| format!("cannot write {}", path.display()))?;
+    pager::print(
+        &output::batch(&comparisons, global.render.style),
+        global.paging,
+    );
+    eprintln!("Wrote {} comparisons to {}", comparisons.len(), args.dir);
+    Ok(())
+}
+
+fn flaky(args: FlakyArgs, global: &GlobalArgs, mut config: Config) -> Result<()> {
+    let sources = input::expand(&args.files)?;
+    config.only_changed = false;
+    let comparison = load(&sources, &config, global.read_options())?;
+    if comparison.files.len() < 2 {
+        bail!("flaky needs at least two runs to compare");
+    }
+    let mut unstable: Vec<(&Row, f64)> = comparison
+        .entries
+        .iter()
+        .filter_map(|row| Some((row, row.spread()?)))
+        .filter(|(_, spread)| *spread > args.threshold)
+        .collect();
+    unstable.sort_by(|(_, l), (_, r)| r.total_cmp(l));
+    if unstable.is_empty() {
+        println!(
+            "No entry varies by more than {}% across {} runs",
+            args.threshold,
+            comparison.files.len()
+        );
+    }
+    for (row, spread) in &unstable {
+        let (min, max) = row
+            .cells
+            .iter()
+            .flatten()
+            .map(|cell| cell.avg_gas)
+            .fold((u64::MAX, 0), |(min, max), gas| {
+                (min.min(gas), max.max(gas))
+            });
+        println!(
+            "{} varies by {:.2}% ({} to {} gas) across {} runs",
+            row.name,
+            spread,
+            min,
+            max,
+            row.cells.iter().flatten().count()
+        );
+    }
+    if let Some(path) = &args.save {
+        let added = unstable::save(path, unstable.iter().map(|(row, _)| row.name.clone()))?;
+        eprintln!("Added {} entries to {}", added, path);
+    }
+    Ok(())
+}
+
+fn serve(args: ServeArgs, global: &GlobalArgs, config: &Config) -> Result<()> {
+    let sources = input::expand(&args.files)?;
+    if sources.iter().any(|source| matches!(source, Source::Stdin)) {
+        bail!("serve can't read from stdin, which doesn't change");
+    }
+    let paths: Vec<&str> = sources.iter().flat_map(Source::paths).collect();
+    serve::serve(&args.address, &paths, || {
+        load(&sources, config, global.read_options())
+    })
+}
+
+fn versus(args: VersusArgs, global: &GlobalArgs, mut config: Config) -> Result<()> {
+    config.compare_to = Some(CompareTo::First);
+    config.only_changed = false;
+    let comparison = |base: &str, head: &str| {
+        let sources = [Source::from_arg(base), Source::from_arg(head)];
+        load(&sources, &config, global.read_options())
+    };
+    let a = comparison(&args.base_a, &args.head_a)?;
+    let b = comparison(&args.base_b, &args.head_b)?;
+    println!("{}", output::versus(&a, &b, global.render.style));
+    Ok(())
+}
+
+fn snapshot(args: SnapshotArgs, global: &GlobalArgs) -> Result<()> {
+    let snapshot =
+        Snapshot::from_report(Source::from_arg(&args.report).report(global.read_options())?);
+    let rendered = serde_json::to_string_pretty(&snapshot)?;
+    write_json(rendered, global)
+}
+
+fn import_rpc(args: ImportRpcArgs, global: &GlobalArgs) -> Result<()> {
+    let names = onchain::Names::new(&args.contracts, &args.abi)?;
+    let mut hashes = args.transactions;
+    if let (Some(address), Some(from)) = (&args.address, args.from_block) {
+        hashes.extend(onchain::block_transactions(
+            &args.node,
+            address,
+            from,
+            args.to_block,
+        )?);
+    }
+    if hashes.is_empty() {
+        bail!("no transactions to import");
+    }
+    let transactions = hashes
+        .iter()
+        .map(|hash| onchain::transaction(&args.node, hash))
+        .collect::<Result<Vec<_>>>()?;
+    let rendered = serde_json::to_string_pretty(&onchain::report(&transactions, &names))?;
+    write_json(rendered, global)
+}
+
+fn merge(args: MergeArgs, global: &GlobalArgs) -> Result<()> {
+    let reports = input::expand(&args.reports)?
+        .iter()
+        .map(|source| source.report(global.read_options()))
+        .collect::<Result<Vec<_>>>()?;
+    let rendered = serde_json::to_string_pretty(&GasReport::merge(reports))?;
+    write_json(rendered, global)
+}
+
+fn doctor(args: DoctorArgs) -> Result<()> {
+    let mut failed = 0;
+    let sources = input::expand(&args.reports)?;
+    for source in &sources {
+        let diagnosis = match source.read() {
+            Ok(bytes) => doctor::diagnose(&bytes),
+            Err(e) => {
+                println!("{}: {:#}", source, e);
+                failed += 1;
                 continue;
             }
-            let key = format!(
-                "\x1b[90m{}.\x1b[0m{}",
-                method.method.contract, method.method.method
-            );
-            data.entry(key).or_insert(vec![None; file_count])[index] = Some(Entry::Method(method))
+        };
+        if !diagnosis.errors.is_empty() {
+            failed += 1;
         }
+        let verdict = if diagnosis.is_ok() { ", ok" } else { "" };
+        println!("{}: {}{}", source, diagnosis, verdict);
+    }
+    if failed > 0 {
+        bail!("{} of {} reports have errors", failed, sources.len());
+    }
+    Ok(())
+}
 
-        filenames.push(
-            Path::file_name(Path::new(path))
-                .unwrap_or_default()
-                .to_str()
-                .unwrap(),
+fn write_json(rendered: String, global: &GlobalArgs) -> Result<()> {
+    if global.output.is_empty() {
+        println!("{}", rendered);
+    }
+    for path in &global.output {
+        fs::write(path, format!("{}\n", rendered))
+            .with_context(|| format!("cannot write {}", path))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+
+    use super::*;
+
+    fn overridden(config: &str, args: &[&str]) -> Config {
+        let mut config = Config::parse(config).unwrap();
+        let args = Args::try_parse_from([&["eth-gas-delta"], args].concat()).unwrap();
+        override_config(&mut config, &args.global);
+        config
+    }
+
+    #[test]
+    fn explicit_formats_win_over_extensions() {
+        assert_eq!(
+            file_format(Some(Format::Badge), "badge.json"),
+            Format::Badge
         );
+        assert_eq!(
+            file_format(Some(Format::Openmetrics), "m.txt"),
+            Format::Openmetrics
+        );
+        assert_eq!(file_format(None, "gas.html"), Format::Html);
+        assert_eq!(file_format(None, "gas.unknown"), Format::Table);
     }
 
-    // let data = vec![("Hello", "World"), ("123", "456"), ("ABC", "XYZ")];
-
-    // let table = Table::new(data).with(Style::psql()).to_string();
-    let mut builder = Builder::default();
-    let mut header = vec!["Deployments"];
-    // header.append(&mut args.files.iter().map(|s| &s[..]).collect());
-    header.append(&mut filenames);
-
-    builder.set_header(header);
-    for (contract_name, entry) in data.iter().sorted() {
-        let mut row: Vec<String> = vec![];
-        row.push(contract_name.clone());
-        let mut first_avg_gas = None;
-        for (i, entry) in entry.iter().enumerate() {
-            match entry {
-                Some(entry) if entry.has_gas_data() => {
-                    let avg: isize = entry.avg_gas();
-                    row.push(match first_avg_gas {
-                        Some(first_avg) => {
-                            let percent =
-                                100f64 * (avg as f64 - first_avg as f64) / first_avg as f64;
-                            let color_code = if percent > MARGIN {
-                                91 // Red
-                            } else if percent < -MARGIN {
-                                92 // Green
-                            } else {
-                                0 // White
-                            };
-                            format!(
-                                "\x1b[{}m{:+} ({:+5.1}%)\x1b[0m",
-                                color_code,
-                                avg - first_avg,
-                                percent
-                            )
-                        }
-                        None => {
-                            format!(
-                                "{} ({:4.1}%)",
-                                avg,
-                                100f64 * avg as f64 / BLOCK_LIMIT as f64
-                            )
-                        }
-                    });
-                    if i == 0 {
-                        first_avg_gas = Some(avg);
-                    }
-                }
-                _ => {
-                    row.push("".to_owned());
-                }
-            }
-        }
-        builder.push_record(row);
+    #[test]
+    fn command_line_adds_filters_to_the_config() {
+        let config = overridden(
+            "include = [\"^Vault\"]\nexclude = [\"^Mock\"]",
+            &["--include", "^Router", "--exclude", "withdraw$", "a.json"],
+        );
+        assert_eq!(config.include, ["^Vault", "^Router"]);
+        assert_eq!(config.exclude, ["^Mock", "withdraw$"]);
+
+        let config = overridden("include = [\"^Vault\"]", &["a.json"]);
+        assert_eq!(config.include, ["^Vault"]);
+        assert!(Args::try_parse_from(["eth-gas-delta", "--exclude", "(", "a.json"]).is_err());
+    }
+
+    #[test]
+    fn command_line_overrides_thresholds() {
+        let config = "only = \"method\"\n[thresholds]\nmargin = 2.0\ngas = 100";
+        let config = overridden(
+            config,
+            &["--threshold-percent", "0.5", "--deployments-only"],
+        );
+        assert_eq!(config.thresholds.margin, Some(0.5));
+        assert_eq!(config.thresholds.gas, Some(100));
+        assert_eq!(config.only, Some(Kind::Deployment));
+
+        let config = overridden("[thresholds]\nmargin = 2.0", &["--threshold-gas", "10"]);
+        assert_eq!(config.thresholds.margin, Some(2.0));
+        assert_eq!(config.thresholds.gas, Some(10));
+    }
+
+    /// Writes a report of `Vault` methods with the gas of their single call to a temporary file.
+    fn write_report(name: &str, methods: &[(&str, u64)]) -> String {
+        let methods: serde_json::Map<_, _> = methods
+            .iter()
+            .map(|(method, gas)| {
+                let method = serde_json::json!({
+                    "key": format!("Vault_{}", method),
+                    "contract": "Vault",
+                    "method": method,
+                    "fnSig": format!("{}()", method),
+                    "gasData": [gas],
+                    "numberOfCalls": 1
+                });
+                (method["key"].as_str().unwrap().to_owned(), method)
+            })
+            .collect();
+        let report = serde_json::json!({ "info": { "methods": methods, "deployments": [] } });
+        let path =
+            std::env::temp_dir().join(format!("eth-gas-delta-{}-{}", std::process::id(), name));
+        fs::write(&path, report.to_string()).unwrap();
+        path.to_string_lossy().into_owned()
     }
 
-    let table = builder
-        .build()
-        .with(Style::rounded())
-        .with(Modify::new(Columns::new(1..)).with(Alignment::right()))
-        .to_string();
+    #[test]
+    fn single_reports_show_every_entry() {
+        let path = write_report("single.json", &[("deposit", 50_000)]);
+        let args = Args::try_parse_from(["eth-gas-delta", "check", &path]).unwrap();
+        let mut config = Config::parse("only_changed = true").unwrap();
+
+        let comparison = load_single(&path, &args.global, &mut config).unwrap();
+        assert_eq!(comparison.entries.len(), 1);
+        let budgets = budget::parse("Vault.deposit <= 60000").unwrap();
+        assert!(budget::check(&budgets, &comparison).is_empty());
+    }
 
-    println!("{}", table);
+    #[test]
+    fn approving_keeps_renamed_entries_that_increased_too_much() {
+        let baseline = write_report(
+            "approve-a.json",
+            &[("deposit", 50_000), ("withdraw", 40_000)],
+        );
+        let report = write_report(
+            "approve-b.json",
+            &[("deposit", 60_000), ("withdraw", 40_400)],
+        );
+        let config =
+            Config::parse("[rename]\nVault = \"Safe\"\n\"Vault.deposit\" = \"store\"").unwrap();
+        let sources = [Source::Path(baseline.clone()), Source::Path(report)];
+        let options = ReadOptions::default();
+        let reports = sources
+            .iter()
+            .map(|source| Ok((source.name(), source.report(options)?)))
+            .collect::<Result<_>>()
+            .unwrap();
+        let comparison = Comparison::from_reports(reports, &config);
+        update_baseline(
+            &sources,
+            &comparison,
+            Some(Limit::Percent(10.0)),
+            options,
+            &config,
+        )
+        .unwrap();
+
+        let updated = sources[0].report(options).unwrap();
+        let gas = |key: &str| updated.info.methods[key].gas_data.samples().to_vec();
+        assert_eq!(gas("Vault_deposit"), [50_000]);
+        assert_eq!(gas("Vault_withdraw"), [40_400]);
+    }
 }
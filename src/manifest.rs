@@ -0,0 +1,171 @@
+use std::{fs, path::Path};
+
+use anyhow::{bail, Context, Result};
+use eth_gas_delta::Parsers;
+use serde::{de::DeserializeOwned, Deserialize};
+
+use crate::input::{self, Source};
+
+/// The inputs of a comparison, read from the TOML file given to `--manifest`, so that it can be
+/// repeated later.
+///
+/// ```toml
+/// [[input]]
+/// path = "reports/main.json"
+/// label = "main"
+/// role = "baseline"
+///
+/// [[input]]
+/// path = "reports/arbitrum.yaml"
+/// format = "yaml"
+/// network = "arbitrum"
+/// ```
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Manifest {
+    #[serde(default)]
+    input: Vec<Input>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Input {
+    /// File, directory, glob pattern or URL, with relative paths resolved against the manifest.
+    path: String,
+    /// Column name instead of the file name.
+    label: Option<String>,
+    #[serde(default)]
+    role: Role,
+    /// Input format instead of the detected one.
+    format: Option<String>,
+    /// Network to read from a report holding results for several.
+    network: Option<String>,
+}
+
+/// The comparisons of the `batch` subcommand, each of a baseline and a candidate.
+///
+/// ```toml
+/// [[pair]]
+/// name = "pr-123"
+/// baseline = "reports/main.json"
+/// candidate = "reports/pr-123.json"
+/// ```
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Batch {
+    #[serde(default)]
+    pair: Vec<Pair>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Pair {
+    /// Name in the summary and of the output file, by default the candidate's file name.
+    name: Option<String>,
+    /// Files or URLs, with relative paths resolved against the manifest.
+    baseline: String,
+    candidate: String,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Role {
+    /// Compared against, placed before all candidates.
+    Baseline,
+    #[default]
+    Candidate,
+}
+
+fn read<T: DeserializeOwned>(path: &str) -> Result<T> {
+    let text = fs::read_to_string(path).with_context(|| format!("cannot read {}", path))?;
+    toml::from_str(&text).with_context(|| format!("cannot parse {}", path))
+}
+
+/// A path of the manifest in `path`, relative to it unless it is a URL.
+fn resolve(path: &str, file: &str) -> String {
+    match file {
+        url if url.starts_with("http://") || url.starts_with("https://") => url.to_owned(),
+        file => {
+            let dir = Path::new(path).parent().unwrap_or(Path::new(""));
+            dir.join(file).to_string_lossy().into_owned()
+        }
+    }
+}
+
+impl Manifest {
+    pub fn load(path: &str) -> Result<Self> {
+        read(path)
+    }
+
+    /// The inputs as sources, the baselines first, each in the order listed. `path` is the
+    /// manifest's own.
+    pub fn sources(&self, path: &str) -> Result<Vec<Source>> {
+        let parsers = Parsers::default();
+        let mut inputs: Vec<&Input> = self.input.iter().collect();
+        inputs.sort_by_key(|input| input.role != Role::Baseline);
+        let mut sources = vec![];
+        for input in inputs {
+            let format = match &input.format {
+                Some(name) => match parsers.get(name) {
+                    Some(parser) => Some(parser.name()),
+                    None => bail!(
+                        "{}: unknown format {} of {}, expected one of {}",
+                        path,
+                        name,
+                        input.path,
+                        parsers.names().collect::<Vec<_>>().join(", ")
+                    ),
+                },
+                None => None,
+            };
+            let expanded = input::expand(&[resolve(path, &input.path)])?;
+            let single = expanded.len() == 1;
+            for source in expanded {
+                let mut source = Source::Configured {
+                    source: Box::new(source),
+                    format,
+                    network: input.network.clone(),
+                };
+                if let (Some(label), true) = (&input.label, single) {
+                    source = Source::Labeled(label.clone(), Box::new(source));
+                }
+                sources.push(source);
+            }
+        }
+        Ok(sources)
+    }
+}
+
+impl Batch {
+    pub fn load(path: &str) -> Result<Self> {
+        read(path)
+    }
+
+    /// The name, baseline and candidate of every pair, in the order listed. `path` is the
+    /// manifest's own.
+    pub fn pairs(&self, path: &str) -> Result<Vec<(String, [Source; 2])>> {
+        let mut pairs: Vec<(String, [Source; 2])> = vec![];
+        for pair in &self.pair {
+            let name = match &pair.name {
+                Some(name) => name.clone(),
+                None => Path::new(&pair.candidate)
+                    .file_stem()
+                    .map_or(pair.candidate.clone(), |stem| {
+                        stem.to_string_lossy().into_owned()
+                    }),
+            };
+            if name.is_empty() || name.contains(['/', '\\']) || name.starts_with('.') {
+                bail!("{}: {:?} can't name an output file", path, name);
+            }
+            if pairs.iter().any(|(other, _)| *other == name) {
+                bail!("{}: several pairs are named {}", path, name);
+            }
+            let source = |file: &str| Source::from_arg(&resolve(path, file));
+            pairs.push((name, [source(&pair.baseline), source(&pair.candidate)]));
+        }
+        if pairs.is_empty() {
+            bail!("{} has no [[pair]]", path);
+        }
+        Ok(pairs)
+    }
+}
@@ -0,0 +1,72 @@
+use std::env;
+
+use anyhow::{Context, Result};
+use serde_json::json;
+
+use eth_gas_delta::comparison::{Comparison, Status};
+
+const TOP_REGRESSIONS: usize = 5;
+
+/// Posts a short summary to a Slack or Discord compatible webhook if any entry regressed.
+pub fn notify_webhook(url: &str, comparison: &Comparison) -> Result<()> {
+    let Some(summary) = summary(comparison) else {
+        return Ok(());
+    };
+    // Slack reads `text`, Discord reads `content`; both ignore the other field.
+    let payload = json!({ "text": summary, "content": summary });
+    ureq::post(url)
+        .set("Content-Type", "application/json")
+        .send_string(&payload.to_string())
+        .context("cannot post to the webhook")?;
+    Ok(())
+}
+
+fn summary(comparison: &Comparison) -> Option<String> {
+    let regressions = comparison.ranked(Status::Increased);
+    if regressions.is_empty() {
+        return None;
+    }
+
+    let improvements = comparison
+        .entries
+        .iter()
+        .filter(|row| row.status == Status::Decreased)
+        .count();
+    let mut lines = vec![format!(
+        "Gas regressions: {} entries got more expensive, {} got cheaper.",
+        regressions.len(),
+        improvements
+    )];
+    for (row, cell) in regressions.iter().take(TOP_REGRESSIONS) {
+        lines.push(format!(
+            "• {}: {:+} gas ({:+.1}%)",
+            row.name,
+            cell.delta.unwrap_or_default(),
+            cell.delta_percent.unwrap_or_default()
+        ));
+    }
+    if regressions.len() > TOP_REGRESSIONS {
+        lines.push(format!(
+            "… and {} more",
+            regressions.len() - TOP_REGRESSIONS
+        ));
+    }
+    if let Some(link) = ci_link() {
+        lines.push(link);
+    }
+    Some(lines.join("\n"))
+}
+
+/// Link to the current CI run, if we're running in a known CI system.
+fn ci_link() -> Option<String> {
+    if let (Ok(server), Ok(repo), Ok(run)) = (
+        env::var("GITHUB_SERVER_URL"),
+        env::var("GITHUB_REPOSITORY"),
+        env::var("GITHUB_RUN_ID"),
+    ) {
+        return Some(format!("{}/{}/actions/runs/{}", server, repo, run));
+    }
+    env::var("CI_JOB_URL")
+        .or_else(|_| env::var("BUILD_URL"))
+        .ok()
+}
@@ -0,0 +1,247 @@
+use std::{collections::HashMap, fs};
+
+use anyhow::{bail, Context, Result};
+use eth_gas_delta::{
+    report::{Info, MethodIdentifier, RawDeployment, RawMethod},
+    trace::{Breakdown, Step},
+    GasReport,
+};
+use serde_json::{json, Value};
+
+use crate::{
+    abi,
+    cache::{self, Expiry},
+};
+
+/// A mined transaction, as far as its gas is concerned.
+#[derive(Debug, Clone)]
+pub struct Transaction {
+    /// Lowercase address of the called contract, `None` for deployments.
+    pub to: Option<String>,
+    pub input: Vec<u8>,
+    pub gas_used: u64,
+    /// Lowercase address of the deployed contract.
+    pub contract_address: Option<String>,
+}
+
+/// Names for the contracts and functions of on-chain transactions, which only know addresses and
+/// selectors.
+#[derive(Debug, Default)]
+pub struct Names {
+    contracts: HashMap<String, String>,
+    functions: HashMap<[u8; 4], String>,
+}
+
+impl Names {
+    /// Reads `NAME=ADDRESS` pairs and the functions of JSON ABI files.
+    pub fn new(contracts: &[String], abis: &[String]) -> Result<Self> {
+        let mut names = Names::default();
+        for contract in contracts {
+            let (name, address) = contract
+                .split_once('=')
+                .with_context(|| format!("expected NAME=ADDRESS, got {}", contract))?;
+            names.add_contract(name, address);
+        }
+        for path in abis {
+            let text = fs::read(path).with_context(|| format!("cannot read {}", path))?;
+            let abi: Value =
+                serde_json::from_slice(&text).with_context(|| format!("cannot parse {}", path))?;
+            names.add_abi(&abi);
+        }
+        Ok(names)
+    }
+
+    pub fn add_contract(&mut self, name: &str, address: &str) {
+        self.contracts
+            .insert(address.to_lowercase(), name.to_owned());
+    }
+
+    pub fn add_abi(&mut self, abi: &Value) {
+        for signature in abi::signatures(abi) {
+            self.functions.insert(abi::selector(&signature), signature);
+        }
+    }
+
+    pub fn contract(&self, address: &str) -> String {
+        self.contracts
+            .get(address)
+            .cloned()
+            .unwrap_or_else(|| address.to_owned())
+    }
+
+    /// The signature of the called function, or its selector when it is not in any ABI.
+    pub fn function(&self, input: &[u8]) -> String {
+        match input {
+            [] => "receive()".to_owned(),
+            [a, b, c, d, ..] => self
+                .functions
+                .get(&[*a, *b, *c, *d])
+                .cloned()
+                .unwrap_or_else(|| format!("0x{}", abi::hex(&input[..4]))),
+            _ => "fallback()".to_owned(),
+        }
+    }
+}
+
+/// Builds a report with one sample per transaction, grouping calls by contract and function.
+pub fn report(transactions: &[Transaction], names: &Names) -> GasReport {
+    let mut methods: HashMap<String, RawMethod> = HashMap::new();
+    let mut deployments: HashMap<String, RawDeployment> = HashMap::new();
+    for transaction in transactions {
+        match (&transaction.to, &transaction.contract_address) {
+            (Some(to), _) => {
+                let contract = names.contract(to);
+                let signature = names.function(&transaction.input);
+                let key = format!("{}_{}", contract, signature);
+                let method = methods.entry(key.clone()).or_insert_with(|| RawMethod {
+                    key,
+                    method: MethodIdentifier {
+                        contract,
+                        method: signature.split('(').next().unwrap_or_default().to_owned(),
+                    },
+                    signature,
+                    gas_data: vec![].into(),
+                    number_of_calls: 0,
+                    tests: HashMap::new(),
+                });
+                method.gas_data.extend(vec![transaction.gas_used].into());
+                method.number_of_calls += 1;
+            }
+            (None, Some(address)) => {
+                let name = names.contract(address);
+                deployments
+                    .entry(name.clone())
+                    .or_insert_with(|| RawDeployment {
+                        name,
+                        gas_data: vec![].into(),
+                        deployed_bytecode: None,
+                        code_size: None,
+                    })
+                    .gas_data
+                    .extend(vec![transaction.gas_used].into());
+            }
+            (None, None) => {}
+        }
+    }
+    let mut deployments: Vec<_> = deployments.into_values().collect();
+    deployments.sort_by(|l, r| l.name.cmp(&r.name));
+    GasReport {
+        info: Info {
+            methods,
+            deployments,
+        },
+    }
+}
+
+/// Fetches a transaction and its receipt.
+pub fn transaction(rpc_url: &str, hash: &str) -> Result<Transaction> {
+    let request = |method: &str| {
+        json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": method,
+            "params": [hash],
+        })
+    };
+    let transaction = cache::call(rpc_url, request("eth_getTransactionByHash"), Expiry::Never)?;
+    if transaction.is_null() {
+        bail!("transaction {} not found", hash);
+    }
+    let receipt = cache::call(rpc_url, request("eth_getTransactionReceipt"), Expiry::Never)?;
+    if receipt.is_null() {
+        bail!("transaction {} is not mined yet", hash);
+    }
+    let address = |value: &Value| value.as_str().map(str::to_lowercase);
+    let input = transaction
+        .get("input")
+        .and_then(Value::as_str)
+        .context("the transaction has no input")?;
+    Ok(Transaction {
+        to: address(&transaction["to"]),
+        input: abi::unhex(input)?,
+        gas_used: quantity(&receipt["gasUsed"])?,
+        contract_address: address(&receipt["contractAddress"]),
+    })
+}
+
+/// Traces a transaction and breaks its gas down by opcode and contract, naming the contracts.
+pub fn breakdown(rpc_url: &str, hash: &str, names: &Names) -> Result<Breakdown> {
+    let transaction = self::transaction(rpc_url, hash)?;
+    let request = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "debug_traceTransaction",
+        "params": [hash, {"disableStorage": true, "enableMemory": false}],
+    });
+    let trace =
+        cache::call(rpc_url, request, Expiry::Never).context("cannot trace the transaction")?;
+    let steps: Vec<Step> = serde_json::from_value(trace["structLogs"].clone())
+        .context("the trace has no struct logs")?;
+    log::info!("traced {} steps of {}", steps.len(), hash);
+    let mut breakdown = Breakdown::new(&steps, transaction.to.as_deref(), transaction.gas_used);
+    breakdown.frames = breakdown
+        .frames
+        .into_iter()
+        .map(|(address, usage)| (names.contract(&address), usage))
+        .collect();
+    Ok(breakdown)
+}
+
+/// The hashes of the transactions to `address` in the blocks `from` to `to`, or to the latest
+/// block.
+pub fn block_transactions(
+    rpc_url: &str,
+    address: &str,
+    from: u64,
+    to: Option<u64>,
+) -> Result<Vec<String>> {
+    let to = match to {
+        Some(to) => to,
+        None => {
+            let request = json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "method": "eth_blockNumber",
+                "params": [],
+            });
+            quantity(&cache::call(rpc_url, request, Expiry::Ttl)?)?
+        }
+    };
+    let address = address.to_lowercase();
+    let mut hashes = vec![];
+    for number in from..=to {
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "eth_getBlockByNumber",
+            "params": [format!("{:#x}", number), true],
+        });
+        let block = cache::call(rpc_url, request, Expiry::Never)?;
+        let transactions = block
+            .get("transactions")
+            .and_then(Value::as_array)
+            .with_context(|| format!("block {} not found", number))?;
+        for transaction in transactions {
+            let to = transaction.get("to").and_then(Value::as_str);
+            if to.is_some_and(|to| to.to_lowercase() == address) {
+                let hash = transaction.get("hash").and_then(Value::as_str);
+                hashes.push(hash.context("transaction without hash")?.to_owned());
+            }
+        }
+    }
+    log::info!(
+        "found {} transactions to {} in blocks {} to {}",
+        hashes.len(),
+        address,
+        from,
+        to
+    );
+    Ok(hashes)
+}
+
+/// Parses a hex quantity.
+pub fn quantity(value: &Value) -> Result<u64> {
+    let hex = value.as_str().context("expected a hex quantity")?;
+    u64::from_str_radix(hex.trim_start_matches("0x"), 16)
+        .with_context(|| format!("invalid quantity {}", hex))
+}
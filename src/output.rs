@@ -0,0 +1,518 @@
+use std::str::FromStr;
+
+use clap::ValueEnum;
+use serde::Deserialize;
+use tabled::{
+    settings::{
+        style::{HorizontalLine, Line},
+        Padding, Style,
+    },
+    Table,
+};
+
+use crate::{comparison::Comparison, rollup::L1Fee, stats::Stat};
+
+pub use batch::render as batch;
+pub use by_test::render as by_test;
+pub use explain::render as explain;
+pub use github::{annotations as github_annotations, AnnotationLevel};
+pub use histogram::render as histogram;
+pub use matrix::render as matrix;
+pub use opcodes::render as opcodes;
+pub use trend::render as trend;
+pub use versus::render as versus;
+
+mod badge;
+mod batch;
+mod by_test;
+mod csv;
+mod explain;
+mod github;
+mod histogram;
+mod html;
+mod json;
+mod junit;
+mod matrix;
+mod opcodes;
+mod openmetrics;
+mod sarif;
+mod table;
+mod trend;
+mod unified;
+mod versus;
+mod xlsx;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Format {
+    /// Colored table for the terminal
+    Table,
+    /// GitHub-flavored Markdown table, e.g. for pull request comments
+    Markdown,
+    /// The computed comparison as JSON
+    Json,
+    /// Comma-separated values, one row per entry
+    Csv,
+    /// Tab-separated values, one row per entry
+    Tsv,
+    /// Self-contained HTML report with a sortable table
+    Html,
+    /// JUnit XML with a failing test case per regressed entry
+    Junit,
+    /// SARIF 2.1.0 log with a result per regressed entry
+    Sarif,
+    /// OpenMetrics samples, e.g. for a Prometheus Pushgateway
+    Openmetrics,
+    /// shields.io endpoint badge JSON
+    Badge,
+    /// Unified diff of the gas from the first file to the last, for diff highlighters
+    Diff,
+    /// Excel workbook with a sheet for methods and one for deployments, only written to files
+    Xlsx,
+}
+
+/// How the calls of two files are paired up by `--explain`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum Align {
+    /// The n-th call of one file with the n-th of the other
+    #[default]
+    Index,
+    /// The n-th call of a test with the n-th call of the same test
+    Test,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum BadgeMetric {
+    /// The largest percentage change of any entry
+    WorstDelta,
+    /// The summed average gas of all entries in the last file
+    TotalGas,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum TableStyle {
+    /// Unicode borders with rounded corners
+    #[default]
+    Rounded,
+    /// ASCII borders around every cell
+    Ascii,
+    /// Columns separated by `|` and a line under the header
+    Psql,
+    /// Like psql without padding
+    Compact,
+    /// Columns separated by spaces only
+    Borderless,
+    /// GitHub-flavored Markdown
+    Markdown,
+}
+
+impl TableStyle {
+    fn apply(self, table: &mut Table) {
+        match self {
+            TableStyle::Rounded => table.with(Style::rounded()),
+            TableStyle::Ascii => table.with(Style::ascii()),
+            TableStyle::Psql => table.with(Style::psql()),
+            TableStyle::Compact => table.with(Style::psql()).with(Padding::zero()),
+            TableStyle::Borderless => table.with(Style::blank()),
+            TableStyle::Markdown => table.with(Style::markdown()),
+        };
+    }
+
+    /// Draws a line like the one under the header above a row, to start another section.
+    fn divide(self, table: &mut Table, row: usize) {
+        let line = match self {
+            TableStyle::Rounded => Style::modern().get_horizontal(),
+            TableStyle::Psql | TableStyle::Compact => Line::new(Some('-'), Some('+'), None, None),
+            // ASCII has lines between all rows already.
+            TableStyle::Ascii | TableStyle::Borderless | TableStyle::Markdown => return,
+        };
+        table.with(HorizontalLine::new(row, line));
+    }
+}
+
+/// How gas numbers are written in the table.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum NumberFormat {
+    /// 1234567
+    #[default]
+    Plain,
+    /// 1,234,567
+    Comma,
+    /// 1_234_567
+    Underscore,
+    /// 1.23M, with k and M suffixes
+    Short,
+}
+
+impl NumberFormat {
+    /// Writes a number, with a leading `+` for positive ones if `sign` is set.
+    pub fn format(self, value: impl Into<i128>, sign: bool) -> String {
+        let value = value.into();
+        let prefix = match value {
+            v if v < 0 => "-",
+            _ if sign => "+",
+            _ => "",
+        };
+        let abs = value.unsigned_abs();
+        let separator = match self {
+            NumberFormat::Plain => return format!("{}{}", prefix, abs),
+            NumberFormat::Short => {
+                return match abs {
+                    a if a >= 1_000_000 => format!("{}{:.2}M", prefix, a as f64 / 1e6),
+                    a if a >= 1_000 => format!("{}{:.1}k", prefix, a as f64 / 1e3),
+                    a => format!("{}{}", prefix, a),
+                }
+            }
+            NumberFormat::Comma => ',',
+            NumberFormat::Underscore => '_',
+        };
+        let digits = abs.to_string();
+        let mut grouped = String::from(prefix);
+        for (index, digit) in digits.chars().enumerate() {
+            if index > 0 && (digits.len() - index).is_multiple_of(3) {
+                grouped.push(separator);
+            }
+            grouped.push(digit);
+        }
+        grouped
+    }
+}
+
+/// Built-in color themes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ThemePreset {
+    /// Red regressions and green improvements
+    #[default]
+    Default,
+    /// Orange regressions and blue improvements, marked with arrows
+    Colorblind,
+}
+
+/// Colors of the table, each given as ANSI SGR parameters like `"91"` or `"38;5;208"`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Theme {
+    pub increased: String,
+    /// Increases beyond `thresholds.warn` but not `thresholds.error`.
+    pub warning: String,
+    pub decreased: String,
+    pub new: String,
+    pub removed: String,
+    /// Prefix increases with ▲ and decreases with ▼.
+    pub arrows: bool,
+}
+
+impl Theme {
+    pub fn preset(preset: ThemePreset) -> Theme {
+        match preset {
+            ThemePreset::Default => Theme {
+                increased: "91".to_owned(), // Red
+                warning: "33".to_owned(),   // Dark yellow
+                decreased: "92".to_owned(), // Green
+                new: "93".to_owned(),       // Yellow
+                removed: "91".to_owned(),   // Red
+                arrows: false,
+            },
+            ThemePreset::Colorblind => Theme {
+                increased: "38;5;208".to_owned(), // Orange
+                warning: "93".to_owned(),         // Yellow
+                decreased: "94".to_owned(),       // Blue
+                new: "95".to_owned(),             // Magenta
+                removed: "38;5;208".to_owned(),   // Orange
+                arrows: true,
+            },
+        }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme::preset(ThemePreset::Default)
+    }
+}
+
+// Options that affect how a comparison is rendered.
+#[derive(clap::Args, Debug, Clone)]
+pub struct Options {
+    /// Value shown by the badge format
+    #[arg(long, value_enum, global = true, default_value_t = BadgeMetric::WorstDelta)]
+    pub badge_metric: BadgeMetric,
+
+    /// Extra statistics shown for every file in the table, CSV and TSV formats
+    #[arg(long, value_enum, value_delimiter = ',', global = true)]
+    pub stats: Vec<Stat>,
+
+    /// Print the largest regressions and improvements before the table
+    #[arg(long, value_name = "N", global = true)]
+    pub top: Option<usize>,
+
+    /// Show only the N entries with the largest changes in the table, 0 for all
+    #[arg(long, value_name = "N", global = true, default_value_t = 0)]
+    pub max_rows: usize,
+
+    /// Add a row with the average gas of all deployments plus the total gas of all methods
+    #[arg(long, global = true)]
+    pub totals: bool,
+
+    /// Add a row with the average gas of all methods weighted by their number of calls
+    #[arg(long, global = true)]
+    pub weighted: bool,
+
+    /// Nest the methods in the table under their contract, collapsing contracts without changes
+    /// into a single row
+    #[arg(long, global = true)]
+    pub group_by_contract: bool,
+
+    /// Show the summed gas of each contract's methods on its row
+    #[arg(long, global = true, requires = "group_by_contract")]
+    pub subtotals: bool,
+
+    /// List the gas levels of entries whose samples form separate clusters below the table, with
+    /// their changes
+    #[arg(long, global = true)]
+    pub modes: bool,
+
+    /// Show each change in percent of the file to its left rather than of the file the deltas are
+    /// relative to
+    #[arg(long, global = true)]
+    pub percent_of_previous: bool,
+
+    /// Show the gas and its percentage of the block limit, or of all deployments, in every file's
+    /// column next to the change, not only in the first
+    #[arg(long, global = true)]
+    pub absolute: bool,
+
+    /// Prefix the entries in the table with a symbol of their status and add them to the summary,
+    /// for PR comments and other places without colors
+    #[arg(long, global = true)]
+    pub emoji: bool,
+
+    /// Digit grouping of gas numbers in the table
+    #[arg(long, value_enum, global = true, default_value_t)]
+    pub number_format: NumberFormat,
+
+    /// Decimal places of percentages in the table
+    #[arg(long, value_name = "N", global = true, default_value_t = 1)]
+    pub decimals: usize,
+
+    /// Gas price in gwei, adding a column with each entry's cost per call in ETH to the table
+    #[arg(long, value_name = "GWEI", global = true)]
+    pub gas_price: Option<f64>,
+
+    /// ETH price in USD, adding a column with the cost per call in USD as well
+    #[arg(long, value_name = "USD", global = true)]
+    pub eth_price: Option<f64>,
+
+    /// Rollup data fee added to the cost columns, from `--l1-base-fee` and the network.
+    #[arg(skip)]
+    pub l1_fee: Option<L1Fee>,
+
+    /// Add a column with each deployment's code size and its share of the 24,576-byte EIP-170 limit
+    #[arg(long, global = true)]
+    pub sizes: bool,
+
+    /// Border style of the table and matrix
+    #[arg(long, value_enum, global = true, default_value_t)]
+    pub style: TableStyle,
+
+    /// List the files on rows and the entries on columns in the table format
+    #[arg(long, global = true)]
+    pub transpose: bool,
+
+    /// Name methods by their full signature, e.g. `Vault.deposit(uint256)`, in the table, and add a
+    /// signature column to CSV and TSV
+    #[arg(long, global = true)]
+    pub show_signatures: bool,
+
+    /// Show entry names in full even if the table gets wider than the terminal
+    #[arg(long, global = true)]
+    pub full_names: bool,
+
+    /// Stable output for golden tests: ASCII borders, no colors, arrows or sparklines, and the
+    /// same width on any terminal
+    #[arg(long, global = true)]
+    pub plain: bool,
+
+    /// Width the table is fitted to by shortening entry names, the terminal's if it is one.
+    #[arg(skip)]
+    pub width: Option<usize>,
+
+    /// Colors used when `color` is set, from `--theme` or the config file.
+    #[arg(skip)]
+    pub theme: Theme,
+
+    /// Whether to emit ANSI color codes, set from `--color`, `NO_COLOR` and the config file.
+    #[arg(skip = true)]
+    pub color: bool,
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        <Format as ValueEnum>::from_str(s, true)
+    }
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            badge_metric: BadgeMetric::WorstDelta,
+            stats: vec![],
+            top: None,
+            max_rows: 0,
+            totals: false,
+            weighted: false,
+            group_by_contract: false,
+            subtotals: false,
+            modes: false,
+            percent_of_previous: false,
+            absolute: false,
+            emoji: false,
+            transpose: false,
+            show_signatures: false,
+            number_format: NumberFormat::Plain,
+            decimals: 1,
+            gas_price: None,
+            eth_price: None,
+            l1_fee: None,
+            sizes: false,
+            style: TableStyle::Rounded,
+            full_names: false,
+            plain: false,
+            width: None,
+            theme: Theme::default(),
+            color: true,
+        }
+    }
+}
+
+impl Format {
+    /// The format a file name's extension stands for, if any.
+    pub fn from_extension(path: &str) -> Option<Format> {
+        let extension = path.rsplit_once('.')?.1.to_ascii_lowercase();
+        Some(match extension.as_str() {
+            "md" | "markdown" => Format::Markdown,
+            "json" => Format::Json,
+            "csv" => Format::Csv,
+            "tsv" => Format::Tsv,
+            "html" | "htm" => Format::Html,
+            "xml" => Format::Junit,
+            "sarif" => Format::Sarif,
+            "diff" | "patch" => Format::Diff,
+            "prom" => Format::Openmetrics,
+            "xlsx" => Format::Xlsx,
+            "txt" => Format::Table,
+            _ => return None,
+        })
+    }
+
+    /// The extension of files in the format.
+    pub fn extension(self) -> &'static str {
+        match self {
+            Format::Table => "txt",
+            Format::Markdown => "md",
+            Format::Json | Format::Badge => "json",
+            Format::Csv => "csv",
+            Format::Tsv => "tsv",
+            Format::Html => "html",
+            Format::Junit => "xml",
+            Format::Sarif => "sarif",
+            Format::Openmetrics => "prom",
+            Format::Diff => "diff",
+            Format::Xlsx => "xlsx",
+        }
+    }
+
+    /// Renders a text format. Binary formats, which [`Format::render_bytes`] renders, are an
+    /// error.
+    pub fn render(self, comparison: &Comparison, options: &Options) -> Result<String, String> {
+        self.render_text(comparison, options).ok_or_else(|| {
+            format!(
+                "{} is a binary format and can only be written to a file",
+                self.extension()
+            )
+        })
+    }
+
+    fn render_text(self, comparison: &Comparison, options: &Options) -> Option<String> {
+        Some(match self {
+            Format::Table => table::render(comparison, options),
+            Format::Markdown => table::render(
+                comparison,
+                &Options {
+                    style: TableStyle::Markdown,
+                    color: false,
+                    width: None,
+                    ..options.clone()
+                },
+            ),
+            Format::Json => json::render(comparison),
+            Format::Csv => csv::render(comparison, options, ','),
+            Format::Tsv => csv::render(comparison, options, '\t'),
+            Format::Html => html::render(comparison),
+            Format::Junit => junit::render(comparison),
+            Format::Sarif => sarif::render(comparison),
+            Format::Openmetrics => openmetrics::render(comparison),
+            Format::Badge => badge::render(comparison, options),
+            Format::Diff => unified::render(comparison, options),
+            Format::Xlsx => return None,
+        })
+    }
+
+    /// Whether the format is not text, so it can't be printed.
+    pub fn is_binary(self) -> bool {
+        self == Format::Xlsx
+    }
+
+    /// Renders any format as the bytes of a file.
+    pub fn render_bytes(self, comparison: &Comparison, options: &Options) -> Vec<u8> {
+        match self.render_text(comparison, options) {
+            Some(text) => text.into_bytes(),
+            None => xlsx::render(comparison),
+        }
+    }
+}
+
+/// Escapes text for use in HTML and XML content and attribute values.
+pub fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn binary_formats_are_not_rendered_as_text() {
+        let report = crate::comparison::tests::report(&[("Vault.deposit", &[50_000])]);
+        let comparison =
+            Comparison::from_reports(vec![("a.json".to_owned(), report)], &Default::default());
+        let options = Options::default();
+        assert!(Format::Xlsx.render(&comparison, &options).is_err());
+        assert!(Format::Xlsx
+            .render_bytes(&comparison, &options)
+            .starts_with(b"PK"));
+        assert!(Format::Csv
+            .render(&comparison, &options)
+            .unwrap()
+            .contains("Vault"));
+    }
+
+    #[test]
+    fn extensions_name_their_formats() {
+        for &format in Format::value_variants() {
+            let path = format!("gas.{}", format.extension());
+            let expected = match format {
+                // Badges are JSON too, and `.json` files get the comparison.
+                Format::Badge => Format::Json,
+                format => format,
+            };
+            assert_eq!(Format::from_extension(&path), Some(expected), "{}", path);
+        }
+    }
+}
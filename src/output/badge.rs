@@ -0,0 +1,80 @@
+use serde_json::json;
+
+use super::{BadgeMetric, Options};
+use crate::{
+    comparison::{Comparison, Status},
+    stats,
+};
+
+/// Renders a shields.io endpoint badge (https://shields.io/badges/endpoint-badge).
+pub fn render(comparison: &Comparison, options: &Options) -> String {
+    let (label, message, color) = match options.badge_metric {
+        BadgeMetric::WorstDelta => worst_delta(comparison),
+        BadgeMetric::TotalGas => total_gas(comparison),
+    };
+    let badge = json!({
+        "schemaVersion": 1,
+        "label": label,
+        "message": message,
+        "color": color,
+    });
+    serde_json::to_string_pretty(&badge).unwrap()
+}
+
+fn worst_delta(comparison: &Comparison) -> (&'static str, String, &'static str) {
+    let cells = comparison
+        .entries
+        .iter()
+        .flat_map(|row| row.cells.iter().flatten())
+        .filter(|cell| cell.delta_percent.is_some());
+    let worst = |status: Status| {
+        cells
+            .clone()
+            .filter(|cell| cell.status == status)
+            .filter_map(|cell| cell.delta_percent)
+            .max_by(|l, r| l.partial_cmp(r).unwrap())
+    };
+    let all_decreased = cells.clone().all(|cell| cell.status == Status::Decreased);
+    match (worst(Status::Increased), worst(Status::Decreased)) {
+        (Some(percent), _) => ("gas delta", format!("{:+.1}%", percent), "red"),
+        (None, Some(percent)) if all_decreased => {
+            ("gas delta", format!("{:+.1}%", percent), "brightgreen")
+        }
+        _ if cells.clone().next().is_some() => ("gas delta", "±0%".to_owned(), "lightgrey"),
+        _ => ("gas delta", "n/a".to_owned(), "lightgrey"),
+    }
+}
+
+/// Sum of the average gas of every entry in the last file.
+fn total_gas(comparison: &Comparison) -> (&'static str, String, &'static str) {
+    let gas: Vec<u64> = comparison
+        .entries
+        .iter()
+        .filter_map(|row| row.cells.last()?.as_ref())
+        .map(|cell| cell.avg_gas)
+        .collect();
+    let total = stats::sum(&gas);
+    ("total gas", total.to_string(), "blue")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::comparison::tests::regressed;
+
+    #[test]
+    fn shows_the_worst_delta() {
+        let badge: serde_json::Value =
+            serde_json::from_str(&render(&regressed(), &Options::default())).unwrap();
+        assert_eq!(badge["schemaVersion"], 1);
+        assert_eq!(badge["message"], "+10.0%");
+        assert_eq!(badge["color"], "red");
+        let options = Options {
+            badge_metric: BadgeMetric::TotalGas,
+            ..Options::default()
+        };
+        let badge: serde_json::Value =
+            serde_json::from_str(&render(&regressed(), &options)).unwrap();
+        assert_eq!(badge["message"], "110000");
+    }
+}
@@ -0,0 +1,63 @@
+use tabled::{
+    builder::Builder,
+    settings::{object::Columns, Alignment, Modify},
+};
+
+use super::TableStyle;
+use crate::comparison::Comparison;
+
+/// Summarizes several comparisons, one row each with how many entries changed in which way and
+/// the largest change, followed by how many of them regressed.
+pub fn render(pairs: &[(String, Comparison)], style: TableStyle) -> String {
+    let mut builder = Builder::default();
+    builder.set_header([
+        "Pair",
+        "Regressions",
+        "Improvements",
+        "Unchanged",
+        "New",
+        "Removed",
+        "Largest change",
+    ]);
+    for (name, comparison) in pairs {
+        let summary = comparison.summary();
+        let largest = match comparison.largest_change() {
+            Some((row, cell)) => format!(
+                "{} {:+} gas ({:+.1}%)",
+                row.name,
+                cell.delta.unwrap_or_default(),
+                cell.delta_percent.unwrap_or_default()
+            ),
+            None => String::new(),
+        };
+        builder.push_record([
+            name.clone(),
+            summary.regressions.to_string(),
+            summary.improvements.to_string(),
+            summary.unchanged.to_string(),
+            summary.new.to_string(),
+            summary.removed.to_string(),
+            largest,
+        ]);
+    }
+
+    let mut table = builder.build();
+    style.apply(&mut table);
+    table.with(Modify::new(Columns::new(1..6)).with(Alignment::right()));
+    let regressed = pairs
+        .iter()
+        .filter(|(_, comparison)| comparison.summary().regressions > 0)
+        .count();
+    // Without a blank line the text below would continue a Markdown table.
+    let separator = match style {
+        TableStyle::Markdown => "\n\n",
+        _ => "\n",
+    };
+    format!(
+        "{}{}{} of {} pairs regressed",
+        table,
+        separator,
+        regressed,
+        pairs.len()
+    )
+}
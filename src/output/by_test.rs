@@ -0,0 +1,66 @@
+use std::collections::BTreeSet;
+
+use tabled::{
+    builder::Builder,
+    settings::{object::Columns, Alignment, Modify},
+};
+
+use super::TableStyle;
+use crate::{
+    comparison::{Comparison, Row, TestGas},
+    stats,
+};
+
+/// Lists a method's average gas and calls per test in every file, the tests whose calls added the
+/// most gas in the last file first.
+pub fn render(comparison: &Comparison, row: &Row, style: TableStyle) -> String {
+    let tests: BTreeSet<&str> = row
+        .cells
+        .iter()
+        .flatten()
+        .flat_map(|cell| &cell.tests)
+        .map(|test| test.name.as_str())
+        .collect();
+    if tests.is_empty() {
+        return format!("{}: the reports don't attribute calls to tests", row.name);
+    }
+    let find = |index: usize, name: &str| -> Option<&TestGas> {
+        let cell = row.cells[index].as_ref()?;
+        cell.tests.iter().find(|test| test.name == name)
+    };
+    let last = row.cells.len() - 1;
+    // The gas a test's calls added in the last file, compared to the first.
+    let added = |name: &str| -> i128 {
+        let total =
+            |test: Option<&TestGas>| test.map_or(0, |t| t.avg_gas as i128 * t.calls as i128);
+        total(find(last, name)) - total(find(0, name))
+    };
+    let mut tests: Vec<&str> = tests.into_iter().collect();
+    tests.sort_by_key(|name| -added(name));
+
+    let mut builder = Builder::default();
+    let mut header = vec!["Test".to_owned()];
+    header.extend(comparison.files.iter().cloned());
+    builder.set_header(header);
+    for name in tests {
+        let mut record = vec![name.to_owned()];
+        let first = find(0, name);
+        for index in 0..row.cells.len() {
+            record.push(match (find(index, name), first) {
+                (Some(test), Some(first)) if index > 0 => {
+                    let delta = stats::delta(first.avg_gas, test.avg_gas);
+                    let percent = 100f64 * delta as f64 / first.avg_gas.max(1) as f64;
+                    format!("{:+} ({:+.1}%) × {}", delta, percent, test.calls)
+                }
+                (Some(test), _) => format!("{} × {}", test.avg_gas, test.calls),
+                (None, _) => "".to_owned(),
+            });
+        }
+        builder.push_record(record);
+    }
+
+    let mut table = builder.build();
+    style.apply(&mut table);
+    table.with(Modify::new(Columns::new(1..)).with(Alignment::right()));
+    format!("{} by test\n{}", row.name, table)
+}
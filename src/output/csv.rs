@@ -0,0 +1,88 @@
+use super::Options;
+use crate::comparison::Comparison;
+
+/// Renders one row per entry, with average, delta, delta percentage and the selected statistics
+/// columns for every file.
+pub fn render(comparison: &Comparison, options: &Options, delimiter: char) -> String {
+    let mut header = vec!["name".to_owned(), "kind".to_owned()];
+    if options.show_signatures {
+        header.push("signature".to_owned());
+    }
+    for file in &comparison.files {
+        header.push(format!("{} avg", file));
+        header.push(format!("{} delta", file));
+        header.push(format!("{} delta %", file));
+        for stat in &options.stats {
+            header.push(format!("{} {}", file, stat));
+        }
+    }
+
+    let mut lines = vec![record(&header, delimiter)];
+    for row in &comparison.entries {
+        let mut fields = vec![row.name.clone(), row.kind.to_string()];
+        if options.show_signatures {
+            fields.push(row.signature.clone().unwrap_or_default());
+        }
+        for cell in &row.cells {
+            match cell {
+                Some(cell) => {
+                    fields.push(cell.avg_gas.to_string());
+                    fields.push(cell.delta.map(|d| d.to_string()).unwrap_or_default());
+                    fields.push(
+                        cell.delta_percent
+                            .map(|p| format!("{:.2}", p))
+                            .unwrap_or_default(),
+                    );
+                    for stat in &options.stats {
+                        fields.push(stat.of(cell).to_string());
+                    }
+                }
+                None => fields.extend(vec![String::new(); 3 + options.stats.len()]),
+            }
+        }
+        lines.push(record(&fields, delimiter));
+    }
+    lines.join("\n")
+}
+
+fn record(fields: &[String], delimiter: char) -> String {
+    fields
+        .iter()
+        .map(|field| escape(field, delimiter))
+        .collect::<Vec<_>>()
+        .join(&delimiter.to_string())
+}
+
+fn escape(field: &str, delimiter: char) -> String {
+    if delimiter == '\t' {
+        // TSV has no quoting, so fields simply must not contain tabs or newlines.
+        field.replace(['\t', '\n', '\r'], " ")
+    } else if field.contains([delimiter, '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_owned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::comparison::tests::regressed;
+
+    #[test]
+    fn quotes_fields() {
+        let csv = render(&regressed(), &Options::default(), ',');
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("name,kind,a.json avg,a.json delta,a.json delta %"));
+        assert!(
+            lines[1].starts_with("\"Va,ult.dep<o\"\"sit%&\",method,100000,,,110000,10000,10.00")
+        );
+    }
+
+    #[test]
+    fn replaces_tabs() {
+        assert_eq!(escape("a\tb\nc", '\t'), "a b c");
+        assert_eq!(escape("a,b", '\t'), "a,b");
+    }
+}
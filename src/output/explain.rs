@@ -0,0 +1,101 @@
+use tabled::{
+    builder::Builder,
+    settings::{object::Columns, Alignment, Modify},
+};
+
+use super::{Align, TableStyle};
+use crate::{
+    comparison::{Comparison, Row, TestGas},
+    stats,
+};
+
+/// Pairs up the calls of an entry in the first and the last file and lists those whose gas
+/// differs, in the order they were made.
+pub fn render(comparison: &Comparison, row: &Row, align: Align, style: TableStyle) -> String {
+    let (Some(Some(first)), Some(Some(last))) = (row.cells.first(), row.cells.last()) else {
+        return format!("{}: not in both the first and the last file", row.name);
+    };
+    // Runs of calls that line up, each with a label.
+    let runs: Vec<(String, &[u64], &[u64])> = match align {
+        Align::Index => vec![("".to_owned(), &first.samples, &last.samples)],
+        Align::Test => {
+            if first.tests.is_empty() || last.tests.is_empty() {
+                return format!("{}: the reports don't attribute calls to tests", row.name);
+            }
+            let mut names: Vec<&str> = first
+                .tests
+                .iter()
+                .chain(&last.tests)
+                .map(|test| test.name.as_str())
+                .collect();
+            names.sort_unstable();
+            names.dedup();
+            names
+                .into_iter()
+                .map(|name| {
+                    let label = format!("{} ", name);
+                    (
+                        label,
+                        samples(&first.tests, name),
+                        samples(&last.tests, name),
+                    )
+                })
+                .collect()
+        }
+    };
+
+    let files = (
+        &comparison.files[0],
+        &comparison.files[comparison.files.len() - 1],
+    );
+    let mut builder = Builder::default();
+    builder.set_header([
+        "Call".to_owned(),
+        files.0.clone(),
+        files.1.clone(),
+        "change".to_owned(),
+    ]);
+    let (mut calls, mut changed) = (0, 0);
+    for (label, before, after) in runs {
+        for index in 0..before.len().max(after.len()) {
+            calls += 1;
+            let (before, after) = (before.get(index), after.get(index));
+            if before == after {
+                continue;
+            }
+            changed += 1;
+            let gas = |gas: Option<&u64>| gas.map_or("".to_owned(), u64::to_string);
+            let change = match (before, after) {
+                (Some(&before), Some(&after)) => {
+                    let delta = stats::delta(before, after);
+                    let percent = 100f64 * delta as f64 / before.max(1) as f64;
+                    format!("{:+} ({:+.1}%)", delta, percent)
+                }
+                (None, _) => "NEW".to_owned(),
+                (_, None) => "REMOVED".to_owned(),
+            };
+            builder.push_record([
+                format!("{}#{}", label, index + 1),
+                gas(before),
+                gas(after),
+                change,
+            ]);
+        }
+    }
+    if changed == 0 {
+        return format!("{}: all {} calls use the same gas", row.name, calls);
+    }
+
+    let mut table = builder.build();
+    style.apply(&mut table);
+    table.with(Modify::new(Columns::new(1..)).with(Alignment::right()));
+    format!(
+        "{} call by call\n{}\n{} of {} calls changed",
+        row.name, table, changed, calls
+    )
+}
+
+fn samples<'a>(tests: &'a [TestGas], name: &str) -> &'a [u64] {
+    let test = tests.iter().find(|test| test.name == name);
+    test.map_or(&[], |test| &test.samples)
+}
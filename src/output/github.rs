@@ -0,0 +1,79 @@
+use clap::ValueEnum;
+
+use crate::comparison::{Comparison, Severity, Status};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum AnnotationLevel {
+    Notice,
+    Warning,
+    Error,
+}
+
+/// Renders a GitHub Actions workflow command for every entry whose gas increased beyond the margin,
+/// at the level of its severity or else at `level`.
+pub fn annotations(comparison: &Comparison, level: AnnotationLevel) -> String {
+    let mut lines = vec![];
+    for row in &comparison.entries {
+        for (cell, file) in row.cells.iter().zip(&comparison.files) {
+            let Some(cell) = cell.as_ref().filter(|c| c.status == Status::Increased) else {
+                continue;
+            };
+            let command = match (cell.severity, level) {
+                (Some(Severity::Error), _) | (None, AnnotationLevel::Error) => "error",
+                (Some(Severity::Warning), _) | (None, AnnotationLevel::Warning) => "warning",
+                (None, AnnotationLevel::Notice) => "notice",
+            };
+            let message = format!(
+                "{} {} uses {} gas in {} ({:+}, {:+.1}%)",
+                row.kind,
+                row.name,
+                cell.avg_gas,
+                file,
+                cell.delta.unwrap_or_default(),
+                cell.delta_percent.unwrap_or_default()
+            );
+            let location = row.location.as_ref().map_or(String::new(), |location| {
+                format!(
+                    "file={},line={},",
+                    escape_property(&location.file),
+                    location.line
+                )
+            });
+            lines.push(format!(
+                "::{} {}title={}::{}",
+                command,
+                location,
+                escape_property(&format!("Gas regression in {}", row.name)),
+                escape_data(&message)
+            ));
+        }
+    }
+    lines.join("\n")
+}
+
+fn escape_data(text: &str) -> String {
+    text.replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+fn escape_property(text: &str) -> String {
+    escape_data(text).replace(':', "%3A").replace(',', "%2C")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::comparison::tests::regressed;
+
+    #[test]
+    fn escapes_commands() {
+        let warnings = annotations(&regressed(), AnnotationLevel::Warning);
+        assert_eq!(
+            warnings,
+            "::warning title=Gas regression in Va%2Cult.dep<o\"sit%25&::method Va,ult.dep<o\"sit%25& \
+             uses 110000 gas in b.json (+10000, +10.0%25)"
+        );
+        assert!(annotations(&regressed(), AnnotationLevel::Error).starts_with("::error "));
+    }
+}
@@ -0,0 +1,99 @@
+use crate::comparison::{Comparison, Row};
+
+const BINS: usize = 10;
+const BAR_WIDTH: usize = 20;
+
+/// Draws the distribution of an entry's samples in every file side by side, using the same bins
+/// for all files.
+pub fn render(comparison: &Comparison, row: &Row) -> String {
+    let samples = row.cells.iter().flatten().flat_map(|cell| &cell.samples);
+    let (Some(&min), Some(&max)) = (samples.clone().min(), samples.max()) else {
+        return format!("{}: no samples", row.name);
+    };
+    // 0 to u64::MAX holds one value more than u64 can count. Narrow ranges get a bin per value.
+    let range = (max - min) as u128 + 1;
+    let bins = range.min(BINS as u128) as usize;
+    let bin_width = range.div_ceil(bins as u128) as u64;
+    let bin = |gas: u64| (((gas - min) / bin_width) as usize).min(bins - 1);
+
+    let counts: Vec<Vec<usize>> = row
+        .cells
+        .iter()
+        .map(|cell| {
+            let mut counts = vec![0; bins];
+            for &gas in cell.iter().flat_map(|cell| &cell.samples) {
+                counts[bin(gas)] += 1;
+            }
+            counts
+        })
+        .collect();
+    let most = counts.iter().flatten().copied().max().unwrap_or(1).max(1);
+
+    let ranges: Vec<String> = (0..bins)
+        .map(|i| {
+            let start = min.saturating_add(i as u64 * bin_width);
+            let end = start.saturating_add(bin_width - 1).min(max);
+            if start == end {
+                start.to_string()
+            } else {
+                format!("{}-{}", start, end)
+            }
+        })
+        .collect();
+    let range_width = ranges.iter().map(String::len).max().unwrap_or(0).max(3);
+    let column_width = BAR_WIDTH + 1 + most.to_string().len();
+
+    let mut lines = vec![row.name.clone()];
+    let mut header = format!("{:range_width$}", "gas");
+    for file in &comparison.files {
+        header.push_str(&format!("  {:column_width$}", file));
+    }
+    lines.push(header.trim_end().to_owned());
+    for (i, range) in ranges.iter().enumerate() {
+        let mut line = format!("{:>range_width$}", range);
+        for (cell, counts) in row.cells.iter().zip(&counts) {
+            let column = match cell {
+                Some(_) => {
+                    let bar = "█".repeat((counts[i] * BAR_WIDTH).div_ceil(most));
+                    format!("{:BAR_WIDTH$} {}", bar, counts[i])
+                }
+                None => "".to_owned(),
+            };
+            line.push_str(&format!("  {:column_width$}", column));
+        }
+        lines.push(line.trim_end().to_owned());
+    }
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::comparison::tests::report;
+
+    fn histogram(gas: &[u64]) -> String {
+        let reports = vec![("a.json".to_owned(), report(&[("Vault.deposit", gas)]))];
+        let comparison = Comparison::from_reports(reports, &Default::default());
+        render(&comparison, &comparison.entries[0])
+    }
+
+    #[test]
+    fn spans_all_of_u64() {
+        let histogram = histogram(&[0, u64::MAX]);
+        let lines: Vec<&str> = histogram.lines().collect();
+        assert_eq!(lines.len(), 2 + BINS);
+        assert!(lines[2].trim_start().starts_with("0-"), "{}", histogram);
+        assert!(
+            lines[11].contains(&format!("-{} ", u64::MAX)),
+            "{}",
+            histogram
+        );
+    }
+
+    #[test]
+    fn has_a_bin_per_value_of_narrow_ranges() {
+        let histogram = histogram(&[100, 101, 102]);
+        assert_eq!(histogram.lines().count(), 2 + 3, "{}", histogram);
+        assert!(histogram.contains("\n102 "), "{}", histogram);
+    }
+}
@@ -0,0 +1,153 @@
+use super::escape;
+use crate::comparison::{Cell, Comparison, Row, Status};
+
+const STYLE: &str = r#"
+body { font-family: system-ui, sans-serif; margin: 2em; color: #222; }
+input { padding: 0.4em; width: 24em; margin-bottom: 1em; }
+table { border-collapse: collapse; }
+th, td { padding: 0.3em 0.8em; border-bottom: 1px solid #ddd; }
+th { cursor: pointer; user-select: none; text-align: left; background: #f4f4f4; }
+td.num { text-align: right; font-variant-numeric: tabular-nums; }
+.contract { color: #888; }
+.increased { color: #c62828; }
+.decreased { color: #2e7d32; }
+.bars { display: flex; align-items: flex-end; gap: 2px; height: 1.6em; }
+.bars div { width: 0.7em; background: #5c6bc0; }
+"#;
+
+const SCRIPT: &str = r#"
+const table = document.getElementById("comparison");
+const body = table.tBodies[0];
+document.getElementById("filter").addEventListener("input", (e) => {
+  const needle = e.target.value.toLowerCase();
+  for (const row of body.rows) {
+    row.hidden = !row.dataset.name.toLowerCase().includes(needle);
+  }
+});
+table.tHead.querySelectorAll("th").forEach((th, column) => {
+  th.addEventListener("click", () => {
+    const ascending = th.dataset.order !== "asc";
+    th.dataset.order = ascending ? "asc" : "desc";
+    const key = (row) => {
+      const cell = row.cells[column];
+      return cell.dataset.sort !== undefined ? parseFloat(cell.dataset.sort) : cell.textContent;
+    };
+    const rows = Array.from(body.rows).sort((a, b) => {
+      const l = key(a), r = key(b);
+      const order = typeof l === "number" ? l - r : l.localeCompare(r);
+      return ascending ? order : -order;
+    });
+    body.append(...rows);
+  });
+});
+"#;
+
+/// Renders a single self-contained HTML page with a sortable and filterable table.
+pub fn render(comparison: &Comparison) -> String {
+    let mut html = String::new();
+    html.push_str("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+    html.push_str("<title>Gas comparison</title>\n");
+    html.push_str(&format!("<style>{}</style>\n", STYLE));
+    html.push_str("</head>\n<body>\n<h1>Gas comparison</h1>\n");
+    html.push_str("<input id=\"filter\" type=\"search\" placeholder=\"Filter entries\">\n");
+    html.push_str("<table id=\"comparison\">\n<thead>\n<tr><th>Entry</th><th>Kind</th>");
+    for file in &comparison.files {
+        html.push_str(&format!("<th>{}</th>", escape(file)));
+    }
+    html.push_str("<th>Gas</th></tr>\n</thead>\n<tbody>\n");
+    for row in &comparison.entries {
+        html.push_str(&format!(
+            "<tr data-name=\"{}\"><td>{}</td><td>{}</td>",
+            escape(&row.name),
+            name(row),
+            row.kind
+        ));
+        for cell in &row.cells {
+            html.push_str(&match cell {
+                Some(cell) => format_cell(cell),
+                None => "<td class=\"num\" data-sort=\"0\"></td>".to_owned(),
+            });
+        }
+        html.push_str(&format!("<td>{}</td></tr>\n", bars(row)));
+    }
+    html.push_str("</tbody>\n</table>\n");
+    html.push_str(&format!("<script>{}</script>\n", SCRIPT));
+    html.push_str("</body>\n</html>");
+    html
+}
+
+fn name(row: &Row) -> String {
+    match &row.method {
+        Some(method) => format!(
+            "<span class=\"contract\">{}.</span>{}",
+            escape(&row.contract),
+            escape(method)
+        ),
+        None => escape(&row.contract),
+    }
+}
+
+fn format_cell(cell: &Cell) -> String {
+    let class = match cell.status {
+        Status::Increased => "num increased",
+        Status::Decreased => "num decreased",
+        _ => "num",
+    };
+    let text = match (cell.delta, cell.delta_percent) {
+        (Some(delta), Some(percent)) => format!("{:+} ({:+.1}%)", delta, percent),
+        _ => cell.avg_gas.to_string(),
+    };
+    format!(
+        "<td class=\"{}\" data-sort=\"{}\" title=\"{} gas\">{}</td>",
+        class,
+        cell.delta.map_or(cell.avg_gas as i128, i128::from),
+        cell.avg_gas,
+        text
+    )
+}
+
+/// A small bar chart of the average gas in every file, scaled to the largest one.
+fn bars(row: &Row) -> String {
+    let max = row
+        .cells
+        .iter()
+        .flatten()
+        .map(|c| c.avg_gas)
+        .max()
+        .unwrap_or(0);
+    let mut bars = String::from("<div class=\"bars\">");
+    for cell in &row.cells {
+        let height = match cell {
+            Some(cell) if max > 0 => 100 * cell.avg_gas / max,
+            _ => 0,
+        };
+        bars.push_str(&format!("<div style=\"height: {}%\"></div>", height));
+    }
+    bars.push_str("</div>");
+    bars
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::comparison::tests::regressed;
+
+    #[test]
+    fn escapes_names() {
+        let html = render(&regressed());
+        assert!(html.starts_with("<!DOCTYPE html>") && html.ends_with("</html>"));
+        assert_eq!(html.matches("<tr data-name=").count(), 1);
+        assert!(
+            html.contains("data-name=\"Va,ult.dep&lt;o&quot;sit%&amp;\""),
+            "{}",
+            html
+        );
+        assert!(
+            html.contains("</span>dep&lt;o&quot;sit%&amp;</td>"),
+            "{}",
+            html
+        );
+        assert!(html.contains("class=\"num increased\""));
+        assert!(!html.contains("<o\""));
+    }
+}
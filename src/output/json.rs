@@ -0,0 +1,5 @@
+use crate::comparison::Comparison;
+
+pub fn render(comparison: &Comparison) -> String {
+    serde_json::to_string_pretty(comparison).unwrap()
+}
@@ -0,0 +1,74 @@
+use super::escape;
+use crate::comparison::{Comparison, Kind, Row, Status};
+
+/// Renders every entry as a test case that fails when its gas increased beyond the margin.
+pub fn render(comparison: &Comparison) -> String {
+    let failures = comparison
+        .entries
+        .iter()
+        .filter(|row| row.status == Status::Increased)
+        .count();
+
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str(&format!(
+        "<testsuites name=\"eth-gas-delta\" tests=\"{tests}\" failures=\"{failures}\">\n\
+         <testsuite name=\"gas\" tests=\"{tests}\" failures=\"{failures}\">\n",
+        tests = comparison.entries.len(),
+    ));
+    for row in &comparison.entries {
+        let (classname, name) = match (row.kind, &row.method) {
+            (Kind::Method, Some(method)) => (row.contract.as_str(), method.as_str()),
+            _ => (row.contract.as_str(), "deployment"),
+        };
+        xml.push_str(&format!(
+            "<testcase classname=\"{}\" name=\"{}\"",
+            escape(classname),
+            escape(name)
+        ));
+        if row.status == Status::Increased {
+            let message = failure_message(comparison, row);
+            xml.push_str(&format!(
+                ">\n<failure message=\"{}\" type=\"GasRegression\">{}</failure>\n</testcase>\n",
+                escape(&message),
+                escape(&message)
+            ));
+        } else {
+            xml.push_str("/>\n");
+        }
+    }
+    xml.push_str("</testsuite>\n</testsuites>");
+    xml
+}
+
+fn failure_message(comparison: &Comparison, row: &Row) -> String {
+    row.cells
+        .iter()
+        .zip(&comparison.files)
+        .filter_map(|(cell, file)| {
+            let cell = cell.as_ref().filter(|c| c.status == Status::Increased)?;
+            Some(format!(
+                "{}: {} gas ({:+}, {:+.1}%)",
+                file, cell.avg_gas, cell.delta?, cell.delta_percent?
+            ))
+        })
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::comparison::tests::regressed;
+
+    #[test]
+    fn fails_regressions_with_escaped_names() {
+        let xml = render(&regressed());
+        assert!(xml.contains("<testsuite name=\"gas\" tests=\"1\" failures=\"1\">"));
+        assert!(xml.contains("<testcase classname=\"Va,ult\" name=\"dep&lt;o&quot;sit%&amp;\">"));
+        assert!(
+            xml.contains("type=\"GasRegression\">b.json: 110000 gas (+10000, +10.0%)</failure>")
+        );
+        assert!(xml.ends_with("</testsuite>\n</testsuites>"));
+        assert!(!xml.contains("<o\""));
+    }
+}
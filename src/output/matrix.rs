@@ -0,0 +1,71 @@
+use tabled::{
+    builder::Builder,
+    settings::{object::Columns, Alignment, Modify},
+};
+
+use super::TableStyle;
+use crate::comparison::Comparison;
+
+/// Renders the overall change from every file (rows) to every other file (columns), summing the
+/// gas of the entries both files contain.
+pub fn render(comparison: &Comparison, style: TableStyle) -> String {
+    let mut builder = Builder::default();
+    let mut header = vec!["from \\ to".to_owned()];
+    header.extend(comparison.files.iter().cloned());
+    builder.set_header(header);
+
+    for (from, file) in comparison.files.iter().enumerate() {
+        let mut record = vec![file.clone()];
+        for to in 0..comparison.files.len() {
+            record.push(if from == to {
+                "".to_owned()
+            } else {
+                let (before, after) = common_gas(comparison, from, to);
+                if before == 0 {
+                    "".to_owned()
+                } else {
+                    let change = after as i128 - before as i128;
+                    let percent = 100f64 * change as f64 / before as f64;
+                    format!("{:+} ({:+.1}%)", change, percent)
+                }
+            });
+        }
+        builder.push_record(record);
+    }
+
+    let mut table = builder.build();
+    style.apply(&mut table);
+    table
+        .with(Modify::new(Columns::new(1..)).with(Alignment::right()))
+        .to_string()
+}
+
+fn common_gas(comparison: &Comparison, from: usize, to: usize) -> (u128, u128) {
+    comparison
+        .entries
+        .iter()
+        .filter_map(|row| Some((row.cells[from].as_ref()?, row.cells[to].as_ref()?)))
+        .fold((0, 0), |(before, after), (l, r)| {
+            (before + l.avg_gas as u128, after + r.avg_gas as u128)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{comparison::tests::compare, Config};
+
+    #[test]
+    fn shows_changes_both_ways() {
+        let comparison = compare(
+            &[
+                &[("Vault.deposit", &[50_000])],
+                &[("Vault.deposit", &[40_000])],
+            ],
+            &Config::default(),
+        );
+        let text = render(&comparison, TableStyle::Rounded);
+        assert!(text.contains("-10000 (-20.0%)"), "{}", text);
+        assert!(text.contains("+10000 (+25.0%)"), "{}", text);
+    }
+}
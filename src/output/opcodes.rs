@@ -0,0 +1,113 @@
+use std::{
+    cmp::Reverse,
+    collections::{BTreeMap, BTreeSet},
+};
+
+use tabled::{
+    builder::Builder,
+    settings::{object::Columns, Alignment, Modify},
+};
+
+use super::TableStyle;
+use crate::{
+    stats,
+    trace::{Breakdown, Usage},
+};
+
+/// Lists the gas of a transaction by opcode and by contract, or how it changed from `before` to
+/// `after`, the largest changes first.
+pub fn render(before: &Breakdown, after: Option<&Breakdown>, style: TableStyle) -> String {
+    let intrinsic = |breakdown: &Breakdown| Usage {
+        gas: breakdown.intrinsic().max(0) as u64,
+        count: 1,
+    };
+    let mut opcodes = vec![(
+        "(intrinsic - refunds)",
+        intrinsic(before),
+        after.map(intrinsic),
+    )];
+    opcodes.extend(rows(&before.opcodes, after.map(|after| &after.opcodes)));
+    let frames = rows(&before.frames, after.map(|after| &after.frames));
+    let total = (
+        "Total",
+        Usage {
+            gas: before.gas_used,
+            count: 1,
+        },
+        after.map(|after| Usage {
+            gas: after.gas_used,
+            count: 1,
+        }),
+    );
+    [
+        table("Opcode", opcodes, Some(total), style),
+        table("Contract", frames, None, style),
+    ]
+    .join("\n")
+}
+
+type Record<'a> = (&'a str, Usage, Option<Usage>);
+
+fn rows<'a>(
+    before: &'a BTreeMap<String, Usage>,
+    after: Option<&'a BTreeMap<String, Usage>>,
+) -> Vec<Record<'a>> {
+    let names = before
+        .keys()
+        .chain(after.into_iter().flat_map(|a| a.keys()));
+    let mut rows: Vec<Record> = names
+        .map(String::as_str)
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .map(|name| {
+            let before = before.get(name).copied().unwrap_or_default();
+            let after = after.map(|after| after.get(name).copied().unwrap_or_default());
+            (name, before, after)
+        })
+        .collect();
+    rows.sort_by_key(|(_, before, after)| match after {
+        Some(after) => (
+            Reverse(change(*before, *after).unsigned_abs()),
+            Reverse(after.gas),
+        ),
+        None => (Reverse(0), Reverse(before.gas)),
+    });
+    rows
+}
+
+fn change(before: Usage, after: Usage) -> i64 {
+    stats::delta(before.gas, after.gas)
+}
+
+fn table(title: &str, rows: Vec<Record>, footer: Option<Record>, style: TableStyle) -> String {
+    let usage = |usage: Usage| match usage.count {
+        0 => "".to_owned(),
+        1 => usage.gas.to_string(),
+        count => format!("{} ({}×)", usage.gas, count),
+    };
+    let mut builder = Builder::default();
+    let comparing = rows.first().is_some_and(|(_, _, after)| after.is_some());
+    if comparing {
+        builder.set_header([title, "before", "after", "change"]);
+    } else {
+        builder.set_header([title, "gas"]);
+    }
+    for (name, before, after) in rows.into_iter().chain(footer) {
+        match after {
+            Some(after) => builder.push_record([
+                name.to_owned(),
+                usage(before),
+                usage(after),
+                match change(before, after) {
+                    0 => "".to_owned(),
+                    change => format!("{:+}", change),
+                },
+            ]),
+            None => builder.push_record([name.to_owned(), usage(before)]),
+        };
+    }
+    let mut table = builder.build();
+    style.apply(&mut table);
+    table.with(Modify::new(Columns::new(1..)).with(Alignment::right()));
+    table.to_string()
+}
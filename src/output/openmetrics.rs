@@ -0,0 +1,116 @@
+use crate::comparison::{Cell, Comparison, Row};
+
+type Metric = (&'static str, &'static str, fn(&Cell) -> Option<String>);
+
+const METRICS: [Metric; 3] = [
+    (
+        "eth_gas_average",
+        "Average gas used per call or deployment.",
+        |cell| Some(cell.avg_gas.to_string()),
+    ),
+    (
+        "eth_gas_delta",
+        "Difference in average gas to the first file.",
+        |cell| cell.delta.map(|delta| delta.to_string()),
+    ),
+    (
+        "eth_gas_delta_percent",
+        "Difference in average gas to the first file in percent.",
+        |cell| cell.delta_percent.map(|percent| percent.to_string()),
+    ),
+];
+
+/// Renders gas averages and deltas in the OpenMetrics text exposition format.
+pub fn render(comparison: &Comparison) -> String {
+    let mut text = String::new();
+    for (name, help, value) in METRICS {
+        text.push_str(&format!(
+            "# TYPE {} gauge\n# HELP {} {}\n",
+            name, name, help
+        ));
+        for row in &comparison.entries {
+            for (cell, file) in row.cells.iter().zip(&comparison.files) {
+                if let Some(value) = cell.as_ref().and_then(value) {
+                    text.push_str(&format!("{}{{{}}} {}\n", name, labels(row, file), value));
+                }
+            }
+        }
+    }
+    text.push_str("# EOF\n");
+    text
+}
+
+fn labels(row: &Row, file: &str) -> String {
+    let mut labels = vec![
+        ("kind", row.kind.to_string()),
+        ("contract", row.contract.clone()),
+    ];
+    if let Some(method) = &row.method {
+        labels.push(("method", method.clone()));
+    }
+    // Tells overloads apart, which share the method name.
+    if let Some(signature) = &row.signature {
+        labels.push(("signature", signature.clone()));
+    }
+    labels.push(("file", file.to_owned()));
+    labels
+        .iter()
+        .map(|(name, value)| format!("{}=\"{}\"", name, escape(value)))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use itertools::Itertools;
+
+    use super::*;
+    use crate::{
+        comparison::tests::{regressed, report},
+        Config,
+    };
+
+    #[test]
+    fn escapes_labels() {
+        let text = render(&regressed());
+        assert!(
+            text.contains(
+                "eth_gas_delta{kind=\"method\",contract=\"Va,ult\",method=\"dep<o\\\"sit%&\",\
+             signature=\"dep<o\\\"sit%&()\",file=\"b.json\"} 10000\n"
+            ),
+            "{}",
+            text
+        );
+        assert_eq!(text.matches("# TYPE").count(), METRICS.len());
+        assert!(text.ends_with("# EOF\n"));
+        assert_eq!(escape("a\\b\nc"), "a\\\\b\\nc");
+    }
+
+    #[test]
+    fn tells_overloads_apart() {
+        let overloads: &[(&str, &[u64])] = &[("Token.transfer", &[30_000])];
+        let mut reports: Vec<_> = [overloads, overloads]
+            .into_iter()
+            .zip(["a.json", "b.json"])
+            .map(|(methods, name)| (name.to_owned(), report(methods)))
+            .collect();
+        for (_, report) in &mut reports {
+            let mut overload = report.info.methods["Token_transfer"].clone();
+            overload.key = "Token_transfer_bytes".to_owned();
+            overload.signature = "transfer(bytes)".to_owned();
+            report.info.methods.insert(overload.key.clone(), overload);
+        }
+        let text = render(&Comparison::from_reports(reports, &Config::default()));
+        let series: Vec<_> = text.lines().filter(|line| !line.starts_with('#')).collect();
+        // Averages of both files, deltas of the second one.
+        assert_eq!(series.len(), 8, "{}", text);
+        assert!(series.iter().all_unique(), "{}", text);
+    }
+}
@@ -0,0 +1,104 @@
+use serde_json::{json, Value};
+
+use crate::comparison::{Comparison, Kind, Row, Severity, Status};
+
+const RULE_ID: &str = "gas-regression";
+
+/// Renders a SARIF 2.1.0 log with one result per entry whose gas increased beyond the margin.
+pub fn render(comparison: &Comparison) -> String {
+    let results: Vec<Value> = comparison
+        .entries
+        .iter()
+        .filter(|row| row.status == Status::Increased)
+        .map(|row| result(comparison, row))
+        .collect();
+
+    let log = json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": env!("CARGO_PKG_NAME"),
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "rules": [{
+                        "id": RULE_ID,
+                        "shortDescription": { "text": "Gas usage increased" },
+                        "defaultConfiguration": { "level": "warning" },
+                    }],
+                },
+            },
+            "results": results,
+        }],
+    });
+    serde_json::to_string_pretty(&log).unwrap()
+}
+
+fn result(comparison: &Comparison, row: &Row) -> Value {
+    let worst = row
+        .cells
+        .iter()
+        .zip(&comparison.files)
+        .filter_map(|(cell, file)| Some((cell.as_ref()?, file)))
+        .filter(|(cell, _)| cell.status == Status::Increased)
+        .max_by_key(|(cell, _)| cell.delta);
+    let message = match worst {
+        Some((cell, file)) => format!(
+            "{} {} got more expensive in {}: {} gas ({:+}, {:+.1}%)",
+            row.kind,
+            row.name,
+            file,
+            cell.avg_gas,
+            cell.delta.unwrap_or_default(),
+            cell.delta_percent.unwrap_or_default()
+        ),
+        None => format!("{} {} got more expensive", row.kind, row.name),
+    };
+    let kind = match row.kind {
+        Kind::Deployment => "type",
+        Kind::Method => "function",
+    };
+
+    let mut location = json!({
+        "logicalLocations": [{
+            "name": row.method.as_deref().unwrap_or(&row.contract),
+            "fullyQualifiedName": row.name,
+            "kind": kind,
+        }],
+    });
+    if let Some(source) = &row.location {
+        location["physicalLocation"] = json!({
+            "artifactLocation": { "uri": source.file },
+            "region": { "startLine": source.line },
+        });
+    }
+
+    json!({
+        "ruleId": RULE_ID,
+        "level": match row.cells.iter().flatten().filter_map(|cell| cell.severity).max() {
+            Some(Severity::Error) => "error",
+            _ => "warning",
+        },
+        "message": { "text": message },
+        "locations": [location],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::comparison::tests::{regressed, AWKWARD};
+
+    #[test]
+    fn reports_a_result_per_regression() {
+        let log: Value = serde_json::from_str(&render(&regressed())).unwrap();
+        assert_eq!(log["version"], "2.1.0");
+        let results = log["runs"][0]["results"].as_array().unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0]["ruleId"], RULE_ID);
+        assert_eq!(results[0]["level"], "warning");
+        let location = &results[0]["locations"][0]["logicalLocations"][0];
+        assert_eq!(location["fullyQualifiedName"], AWKWARD);
+        assert_eq!(location["kind"], "function");
+    }
+}
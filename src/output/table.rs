@@ -0,0 +1,977 @@
+use std::{cmp::Reverse, collections::HashSet};
+
+use tabled::{
+    builder::Builder,
+    settings::{object::Columns, Alignment, Modify},
+    Table,
+};
+
+use super::{Options, TableStyle};
+use crate::{
+    comparison::{
+        Cell, Comparison, Kind, Row, Severity, Status, CODE_SIZE_LIMIT, CODE_SIZE_WARNING,
+    },
+    stats::{self, Stat},
+};
+
+/// Entry names are not shortened below this width to fit the terminal.
+const MIN_NAME_WIDTH: usize = 16;
+
+/// Files needed before a trend column is shown.
+const TREND_MIN_FILES: usize = 3;
+
+pub fn render(comparison: &Comparison, options: &Options) -> String {
+    let warnings = comparison.block_share.is_some() && !comparison.near_block_limit().is_empty();
+    if comparison.unchanged() && !warnings {
+        let count = comparison.entries.len() + comparison.hidden;
+        let symbol = if options.emoji { "⚪ " } else { "" };
+        return format!("{}No gas changes detected across {} entries", symbol, count);
+    }
+    let trend = !options.plain && comparison.files.len() >= TREND_MIN_FILES;
+    let header = |title: &str| {
+        let mut header = vec![title.to_owned()];
+        for file in &comparison.files {
+            header.push(file.clone());
+            for stat in &options.stats {
+                header.push(format!("{} {}", file, stat));
+            }
+            for (currency, _, _) in costs(options) {
+                header.push(format!("{} {}", file, currency.code()));
+            }
+            if options.sizes {
+                header.push(format!("{} size", file));
+            }
+        }
+        if trend {
+            header.push("Trend".to_owned());
+        }
+        header
+    };
+
+    // Methods and deployments get a section each, starting with its own header row. The rows
+    // are kept along to shorten their names later.
+    let shown = most_significant(comparison, options.max_rows);
+    let mut records = vec![];
+    let mut rows = vec![];
+    let mut sections = vec![];
+    for (kind, title) in [(Kind::Method, "Methods"), (Kind::Deployment, "Deployments")] {
+        let section: Vec<&Row> = comparison
+            .entries
+            .iter()
+            .enumerate()
+            .filter(|(i, row)| row.kind == kind && shown.contains(i))
+            .map(|(_, row)| row)
+            .collect();
+        if section.is_empty() {
+            continue;
+        }
+        if records.is_empty() || !options.transpose {
+            if !records.is_empty() {
+                sections.push(records.len());
+            }
+            records.push(header(title));
+            rows.push(None);
+        }
+        if kind == Kind::Method && grouped(options) {
+            for (contract, methods) in by_contract(&section) {
+                let mut record = contract_record(contract, &methods, comparison, options);
+                if trend {
+                    record.push("".to_owned());
+                }
+                records.push(record);
+                rows.push(None);
+                if methods.iter().any(|row| changed(row)) {
+                    for row in methods {
+                        records.push(self::record(row, comparison, options, trend));
+                        rows.push(Some(row));
+                    }
+                }
+            }
+            continue;
+        }
+        for row in section {
+            records.push(record(row, comparison, options, trend));
+            rows.push(Some(row));
+        }
+    }
+    if records.is_empty() {
+        records.push(header("Entry"));
+        rows.push(None);
+    }
+    let mut footers = vec![];
+    if options.totals {
+        let totals: Vec<_> = comparison.totals.iter().copied().map(Some).collect();
+        footers.push(summary_record("Totals", &totals, comparison, options));
+    }
+    if options.weighted {
+        footers.push(summary_record(
+            "Weighted by calls",
+            &comparison.weighted_average,
+            comparison,
+            options,
+        ));
+    }
+    for mut record in footers {
+        if trend {
+            record.push("".to_owned());
+        }
+        records.push(record);
+    }
+    if options.stats.contains(&Stat::Total) {
+        let mut record = total_record(comparison, options);
+        if trend {
+            record.push("".to_owned());
+        }
+        records.push(record);
+    }
+    if options.transpose {
+        records = transpose(records);
+        records[0][0] = "File".to_owned();
+    }
+
+    let mut table = build(records.clone(), &sections, options);
+    if let (Some(width), false) = (options.width, options.full_names || options.transpose) {
+        // Shorten the names so the table fits, as far as they stay readable.
+        let excess = table.total_width().saturating_sub(width);
+        if excess > 0 {
+            let longest = comparison
+                .entries
+                .iter()
+                .map(|row| name_width(row, options))
+                .max();
+            let limit = longest
+                .unwrap_or_default()
+                .saturating_sub(excess)
+                .max(MIN_NAME_WIDTH);
+            for (record, row) in records.iter_mut().zip(&rows) {
+                if let Some(row) = row {
+                    record[0] = name(row, options, Some(limit));
+                }
+            }
+            table = build(records, &sections, options);
+        }
+    }
+    let table = table.to_string();
+    let mut lines = vec![];
+    if let Some(n) = options.top {
+        lines.push(top(
+            comparison,
+            Status::Increased,
+            n,
+            "regressions",
+            options,
+        ));
+        lines.push(top(
+            comparison,
+            Status::Decreased,
+            n,
+            "improvements",
+            options,
+        ));
+    }
+    lines.push(table);
+    if options.style == TableStyle::Markdown {
+        // Without a blank line the text below would continue the table.
+        lines.push("".to_owned());
+    }
+    if shown.len() < comparison.entries.len() {
+        lines.push(format!(
+            "… and {} more entries (use --max-rows 0 to show all)",
+            comparison.entries.len() - shown.len()
+        ));
+    }
+    if comparison.entries.iter().any(|row| row.few_samples) {
+        lines.push(format!(
+            "* fewer than {} calls in at least one file, the delta may be misleading",
+            comparison.min_samples
+        ));
+    }
+    if comparison.entries.iter().any(|row| row.multimodal) {
+        lines.push(
+            "~ separate gas levels in at least one file, the average matches none of them"
+                .to_owned(),
+        );
+        if options.modes {
+            lines.extend(modes(comparison, options));
+        }
+    }
+    if comparison.entries.iter().any(|row| row.unstable) {
+        lines.push("? varies between identical runs, its changes don't fail checks".to_owned());
+    }
+    lines.extend(correlation(comparison));
+    lines.extend(code_mismatches(comparison));
+    if options.sizes {
+        lines.extend(size_warnings(comparison));
+    }
+    if comparison.block_share.is_some() {
+        for (row, percent) in comparison.near_block_limit() {
+            let warning = format!(
+                "! {} uses {} gas, {:.1}% of the {}-gas block limit",
+                row.name,
+                options.number_format.format(row.gas(), false),
+                percent,
+                options.number_format.format(comparison.block_limit, false)
+            );
+            lines.push(paint(&warning, &options.theme.increased, options));
+        }
+    }
+    if comparison.hidden > 0 {
+        lines.push(format!("{} unchanged entries hidden", comparison.hidden));
+    }
+    if comparison.files.len() > 1 {
+        lines.push(summary(comparison, options));
+    }
+    lines.join("\n")
+}
+
+/// The contracts whose gas doesn't reflect whether their bytecode changed.
+fn code_mismatches(comparison: &Comparison) -> Vec<String> {
+    let contracts = |code_changed: bool| {
+        let mismatches = comparison.code_mismatches.iter();
+        mismatches
+            .filter(|mismatch| mismatch.code_changed == code_changed)
+            .map(|mismatch| mismatch.contract.as_str())
+            .collect::<Vec<_>>()
+    };
+    let mut lines = vec![];
+    let untested = contracts(true);
+    if !untested.is_empty() {
+        lines.push(format!(
+            "Bytecode changed but gas stayed the same, the tests may not run the new code: {}",
+            untested.join(", ")
+        ));
+    }
+    let identical = contracts(false);
+    if !identical.is_empty() {
+        lines.push(format!(
+            "Gas changed with identical bytecode, e.g. from different test inputs: {}",
+            identical.join(", ")
+        ));
+    }
+    lines
+}
+
+/// The contracts with regressed methods, split by whether their deployment changed too, which
+/// points to their own code, or is identical, which points to a library or dependency.
+fn correlation(comparison: &Comparison) -> Vec<String> {
+    let contracts = |changed: bool| {
+        let rows = comparison
+            .entries
+            .iter()
+            .filter(|row| row.contract_changed == Some(changed));
+        by_contract(&rows.collect::<Vec<_>>())
+            .into_iter()
+            .map(|(contract, rows)| {
+                let methods = rows.iter().filter_map(|row| row.method.as_deref());
+                format!("{} ({})", contract, methods.collect::<Vec<_>>().join(", "))
+            })
+            .collect::<Vec<_>>()
+    };
+    let mut lines = vec![];
+    let changed = contracts(true);
+    if !changed.is_empty() {
+        lines.push(format!(
+            "Regressions in contracts whose code changed: {}",
+            changed.join(", ")
+        ));
+    }
+    let identical = contracts(false);
+    if !identical.is_empty() {
+        lines.push(format!(
+            "Regressions in contracts with an identical deployment, likely from a library or \
+             dependency: {}",
+            identical.join(", ")
+        ));
+    }
+    lines
+}
+
+/// The indices of the `n` entries with the largest absolute delta in any file, or of all entries
+/// for 0. New and removed entries count with their whole gas.
+fn most_significant(comparison: &Comparison, n: usize) -> HashSet<usize> {
+    let change = |row: &Row| {
+        let present = row.cells.iter().flatten();
+        if row.cells.iter().any(Option::is_none) {
+            present.map(|cell| cell.avg_gas).max().unwrap_or_default()
+        } else {
+            present
+                .filter_map(|cell| cell.delta)
+                .map(i64::unsigned_abs)
+                .max()
+                .unwrap_or_default()
+        }
+    };
+    let mut ranked: Vec<_> = comparison.entries.iter().enumerate().collect();
+    if n > 0 {
+        ranked.sort_by_key(|(_, row)| Reverse(change(row)));
+        ranked.truncate(n);
+    }
+    ranked.into_iter().map(|(i, _)| i).collect()
+}
+
+/// The gas levels of every multimodal entry per file, with their changes where the first file has
+/// as many.
+fn modes(comparison: &Comparison, options: &Options) -> Vec<String> {
+    let mut lines = vec![];
+    for row in comparison.entries.iter().filter(|row| row.multimodal) {
+        lines.push(format!("{}:", row.name));
+        let first = row.cells.first().and_then(Option::as_ref);
+        for (index, (cell, file)) in row.cells.iter().zip(&comparison.files).enumerate() {
+            let Some(cell) = cell else {
+                continue;
+            };
+            let modes = match cell.modes.as_slice() {
+                [] => vec![(cell.avg_gas, cell.samples.len())],
+                modes => modes
+                    .iter()
+                    .map(|mode| (mode.avg_gas, mode.samples))
+                    .collect(),
+            };
+            let before = first
+                .filter(|first| index > 0 && first.modes.len() == modes.len())
+                .map(|first| &first.modes);
+            let levels: Vec<String> = modes
+                .iter()
+                .enumerate()
+                .map(|(level, (gas, samples))| {
+                    let gas = options.number_format.format(*gas, false);
+                    match before {
+                        Some(before) => {
+                            let delta = stats::delta(before[level].avg_gas, modes[level].0);
+                            let delta = options.number_format.format(delta, true);
+                            format!("{} ({}) × {}", gas, delta, samples)
+                        }
+                        None => format!("{} × {}", gas, samples),
+                    }
+                })
+                .collect();
+            lines.push(format!("  {}: {}", file, levels.join(", ")));
+        }
+    }
+    lines
+}
+
+fn grouped(options: &Options) -> bool {
+    options.group_by_contract && !options.transpose
+}
+
+/// The rows grouped by contract, in the order the contracts first appear.
+fn by_contract<'a>(rows: &[&'a Row]) -> Vec<(&'a str, Vec<&'a Row>)> {
+    let mut groups: Vec<(&str, Vec<&Row>)> = vec![];
+    for &row in rows {
+        match groups
+            .iter_mut()
+            .find(|(contract, _)| *contract == row.contract)
+        {
+            Some((_, group)) => group.push(row),
+            None => groups.push((&row.contract, vec![row])),
+        }
+    }
+    groups
+}
+
+/// Whether an entry changed, appeared or disappeared in any file.
+fn changed(row: &Row) -> bool {
+    row.cells
+        .iter()
+        .enumerate()
+        .any(|(index, cell)| match cell {
+            Some(cell) => {
+                matches!(cell.status, Status::Increased | Status::Decreased)
+                    || (index > 0 && cell.status == Status::Baseline)
+            }
+            None => true,
+        })
+}
+
+/// The row of a contract, with the summed gas of its methods if asked for or if they are collapsed
+/// into it.
+fn contract_record(
+    contract: &str,
+    methods: &[&Row],
+    comparison: &Comparison,
+    options: &Options,
+) -> Vec<String> {
+    let collapsed = !methods.iter().any(|row| changed(row));
+    let title = if collapsed {
+        format!("{} ({} unchanged)", contract, methods.len())
+    } else {
+        contract.to_owned()
+    };
+    let subtotals: Vec<Option<u64>> = (0..comparison.files.len())
+        .map(|index| {
+            let gas: Vec<u64> = methods
+                .iter()
+                .filter_map(|row| Some(row.cells[index].as_ref()?.avg_gas))
+                .collect();
+            (!gas.is_empty() && (collapsed || options.subtotals)).then(|| stats::sum(&gas))
+        })
+        .collect();
+    summary_record(&title, &subtotals, comparison, options)
+}
+
+fn record(row: &Row, comparison: &Comparison, options: &Options, trend: bool) -> Vec<String> {
+    let mut record = vec![name(row, options, None)];
+    let in_first = row.cells.first().is_some_and(Option::is_some);
+    for (index, cell) in row.cells.iter().enumerate() {
+        // Methods are relative to the block limit, deployments to all deployments of the file.
+        let base = match row.kind {
+            Kind::Method => comparison.block_limit,
+            Kind::Deployment => comparison.deployment_gas[index],
+        };
+        let previous = match index {
+            0 => None,
+            _ if options.percent_of_previous => row.cells[index - 1].as_ref(),
+            _ => None,
+        };
+        record.push(match cell {
+            Some(cell) if index > 0 && cell.status == Status::Baseline => {
+                let text = format!("NEW {}", format_cell(cell, base, None, options));
+                paint(&text, &options.theme.new, options)
+            }
+            Some(cell) => format_cell(cell, base, previous, options),
+            None if in_first => paint("REMOVED", &options.theme.removed, options),
+            None => "".to_owned(),
+        });
+        for stat in &options.stats {
+            record.push(
+                cell.as_ref()
+                    .map_or(String::new(), |c| format_stat(*stat, c, options)),
+            );
+        }
+        for (currency, per_gas, per_eth) in costs(options) {
+            record.push(cell.as_ref().map_or(String::new(), |c| {
+                let l1 = options
+                    .l1_fee
+                    .as_ref()
+                    .map(|fee| fee.eth(&row.name) * per_eth);
+                format_cost(c, currency, per_gas, l1, options)
+            }));
+        }
+        if options.sizes {
+            record.push(
+                cell.as_ref()
+                    .map_or(String::new(), |c| format_size(c, options)),
+            );
+        }
+    }
+    if trend {
+        record.push(sparkline(row));
+    }
+    record
+}
+
+/// Builds the table, with a line above each of the `sections` after the first.
+fn build(records: Vec<Vec<String>>, sections: &[usize], options: &Options) -> Table {
+    let mut table = Builder::from(records).build();
+    options.style.apply(&mut table);
+    for &row in sections {
+        options.style.divide(&mut table, row);
+    }
+    table.with(Modify::new(Columns::new(1..)).with(Alignment::right()));
+    table
+}
+
+/// Swaps rows and columns, so files are listed on rows and entries on columns.
+fn transpose(records: Vec<Vec<String>>) -> Vec<Vec<String>> {
+    let columns = records.first().map_or(0, Vec::len);
+    (0..columns)
+        .map(|column| {
+            records
+                .iter()
+                .map(|record| record[column].clone())
+                .collect()
+        })
+        .collect()
+}
+
+fn summary(comparison: &Comparison, options: &Options) -> String {
+    let counts = comparison.summary();
+    let mut summary = match options.emoji {
+        true => format!(
+            "🔴 {} regressions, 🟢 {} improvements, ⚪ {} unchanged, 🆕 {} new, ❌ {} removed",
+            counts.regressions, counts.improvements, counts.unchanged, counts.new, counts.removed
+        ),
+        false => counts.to_string(),
+    };
+    if let Some((row, cell)) = comparison.largest_change() {
+        summary.push_str(&format!(
+            "; largest change: {} {} gas ({:+.*}%)",
+            row.name,
+            options
+                .number_format
+                .format(cell.delta.unwrap_or_default(), true),
+            options.decimals,
+            cell.delta_percent.unwrap_or_default()
+        ));
+    }
+    summary
+}
+
+fn top(
+    comparison: &Comparison,
+    status: Status,
+    n: usize,
+    title: &str,
+    options: &Options,
+) -> String {
+    let ranked = comparison.ranked(status);
+    let mut lines = vec![format!("Top {} {}:", n, title)];
+    for (row, cell) in ranked.iter().take(n) {
+        lines.push(format!(
+            "  {}: {} gas ({:+.*}%)",
+            row.name,
+            options
+                .number_format
+                .format(cell.delta.unwrap_or_default(), true),
+            options.decimals,
+            cell.delta_percent.unwrap_or_default()
+        ));
+    }
+    if ranked.is_empty() {
+        lines.push("  none".to_owned());
+    }
+    lines.join("\n") + "\n"
+}
+
+/// The name of an entry with its contract in gray, shortened to `limit` characters by cutting
+/// the contract before the method.
+fn name(row: &Row, options: &Options, limit: Option<usize>) -> String {
+    let suffix = suffix(row);
+    let prefix = match options.emoji {
+        true => format!("{} ", symbol(row)),
+        false => String::new(),
+    };
+    let limit = limit.map_or(usize::MAX, |limit| {
+        limit - suffix.len() - prefix_width(options)
+    });
+    let method = match (&row.signature, options.show_signatures) {
+        (Some(signature), true) => Some(signature.as_str()),
+        _ => row.name.strip_prefix(&format!("{}.", row.contract)),
+    };
+    let name = match method {
+        // Grouped methods are indented under their contract's row.
+        Some(method) if grouped(options) => format!("  {}", shorten(method, limit - 2)),
+        Some(method) => {
+            let method = shorten(method, limit.saturating_sub(2));
+            let contract = shorten(&row.contract, limit - method.chars().count() - 1);
+            if options.color {
+                format!("\x1b[90m{}.\x1b[0m{}", contract, method)
+            } else {
+                format!("{}.{}", contract, method)
+            }
+        }
+        None => shorten(&row.name, limit),
+    };
+    prefix + &name + &suffix
+}
+
+/// The status of an entry as a symbol, which survives where colors don't.
+fn symbol(row: &Row) -> &'static str {
+    if row.cells.first().is_some_and(Option::is_none) {
+        return "🆕";
+    }
+    if row.cells.last().is_some_and(Option::is_none) {
+        return "❌";
+    }
+    let severity = row
+        .cells
+        .iter()
+        .flatten()
+        .filter_map(|cell| cell.severity)
+        .max();
+    match (row.status, severity) {
+        (Status::Increased, Some(Severity::Warning)) => "🟡",
+        (Status::Increased, _) => "🔴",
+        (Status::Decreased, _) => "🟢",
+        _ => "⚪",
+    }
+}
+
+fn name_width(row: &Row, options: &Options) -> usize {
+    let name = match (&row.signature, options.show_signatures) {
+        (Some(signature), true) => row.contract.chars().count() + 1 + signature.chars().count(),
+        _ => row.name.chars().count(),
+    };
+    name + suffix(row).len() + prefix_width(options)
+}
+
+/// Columns taken by the status symbol, two for the emoji and one for the space after it.
+fn prefix_width(options: &Options) -> usize {
+    if options.emoji {
+        3
+    } else {
+        0
+    }
+}
+
+/// Marks entries whose average is unreliable, explained below the table.
+fn suffix(row: &Row) -> String {
+    let mut suffix = String::new();
+    for (marked, marker) in [
+        (row.few_samples, '*'),
+        (row.multimodal, '~'),
+        (row.unstable, '?'),
+    ] {
+        if marked {
+            suffix.push(marker);
+        }
+    }
+    match suffix.is_empty() {
+        true => suffix,
+        false => format!(" {}", suffix),
+    }
+}
+
+/// Cuts text to a number of characters, ending it with an ellipsis if anything was left out.
+fn shorten(text: &str, length: usize) -> String {
+    if text.chars().count() <= length {
+        text.to_owned()
+    } else {
+        let mut short: String = text.chars().take(length.saturating_sub(1)).collect();
+        short.push('…');
+        short
+    }
+}
+
+/// Draws the gas of every file relative to the row's smallest and largest value, leaving gaps for
+/// files without the entry.
+pub(super) fn sparkline(row: &Row) -> String {
+    const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+    let gas = row.cells.iter().flatten().map(|cell| cell.avg_gas);
+    let (Some(min), Some(max)) = (gas.clone().min(), gas.max()) else {
+        return "".to_owned();
+    };
+    row.cells
+        .iter()
+        .map(|cell| match cell {
+            Some(_) if min == max => BARS[BARS.len() / 2],
+            Some(cell) => {
+                let level =
+                    (cell.avg_gas - min) as u128 * (BARS.len() as u128 - 1) / (max - min) as u128;
+                BARS[level as usize]
+            }
+            None => ' ',
+        })
+        .collect()
+}
+
+fn format_stat(stat: Stat, cell: &Cell, options: &Options) -> String {
+    match (stat, cell.calls_delta) {
+        (Stat::Calls, Some(delta)) if delta != 0 => {
+            let text = format!(
+                "{} ({})",
+                options.number_format.format(cell.calls as u64, false),
+                options.number_format.format(delta, true)
+            );
+            paint(&text, &options.theme.new, options)
+        }
+        (Stat::Stddev, _) => stat.of(cell).to_string(),
+        _ => options.number_format.format(stat.of(cell), false),
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Currency {
+    Eth,
+    Usd,
+}
+
+impl Currency {
+    fn code(self) -> &'static str {
+        match self {
+            Currency::Eth => "ETH",
+            Currency::Usd => "USD",
+        }
+    }
+}
+
+/// The currencies of the cost columns with the price of one gas and of one ETH in them.
+fn costs(options: &Options) -> Vec<(Currency, f64, f64)> {
+    let Some(gwei) = options.gas_price else {
+        return vec![];
+    };
+    let eth = gwei * 1e-9;
+    let mut costs = vec![(Currency::Eth, eth, 1.0)];
+    if let Some(usd) = options.eth_price {
+        costs.push((Currency::Usd, eth * usd, usd));
+    }
+    costs
+}
+
+/// The cost of a call including its L1 data fee, or the delta once there is one. With an L1 fee
+/// the delta is also given relative to the whole cost, which the execution gas alone overstates.
+fn format_cost(
+    cell: &Cell,
+    currency: Currency,
+    per_gas: f64,
+    l1: Option<f64>,
+    options: &Options,
+) -> String {
+    let before = |delta: i64, l1: f64| (cell.avg_gas as f64 - delta as f64) * per_gas + l1;
+    match (cell.delta, l1) {
+        // Without a cost before there is no share of it.
+        (Some(delta), Some(l1)) if before(delta, l1) > 0.0 => {
+            let before = before(delta, l1);
+            let change = delta as f64 * per_gas;
+            let text = format!(
+                "{} ({:+.*}%)",
+                money(change, true, currency),
+                options.decimals,
+                100.0 * change / before
+            );
+            highlight(&text, cell.status, options)
+        }
+        (Some(delta), _) => {
+            let text = money(delta as f64 * per_gas, true, currency);
+            highlight(&text, cell.status, options)
+        }
+        (None, l1) => money(
+            cell.avg_gas as f64 * per_gas + l1.unwrap_or_default(),
+            false,
+            currency,
+        ),
+    }
+}
+
+/// Empty cells for the columns of a file after its statistics.
+fn blanks(options: &Options) -> impl Iterator<Item = String> {
+    let columns = costs(options).len() + usize::from(options.sizes);
+    (0..columns).map(|_| "".to_owned())
+}
+
+/// A deployment's code size with its share of the limit, as a delta once there is one.
+fn format_size(cell: &Cell, options: &Options) -> String {
+    let Some(size) = cell.code_size else {
+        return "".to_owned();
+    };
+    let share = 100.0 * size as f64 / CODE_SIZE_LIMIT as f64;
+    match cell.code_size_delta {
+        Some(delta) => {
+            let status = match delta {
+                d if d > 0 => Status::Increased,
+                d if d < 0 => Status::Decreased,
+                _ => Status::Unchanged,
+            };
+            let text = format!(
+                "{} ({:.*}%)",
+                options.number_format.format(delta, true),
+                options.decimals,
+                share
+            );
+            highlight(&text, status, options)
+        }
+        None => format!(
+            "{} ({:.*}%)",
+            options.number_format.format(size as u64, false),
+            options.decimals,
+            share
+        ),
+    }
+}
+
+/// Flags contracts whose code in the last file comes close to the EIP-170 limit.
+fn size_warnings(comparison: &Comparison) -> Vec<String> {
+    let mut warnings = vec![];
+    for row in &comparison.entries {
+        let Some(size) = row.cells.iter().flatten().last().and_then(|c| c.code_size) else {
+            continue;
+        };
+        let share = 100.0 * size as f64 / CODE_SIZE_LIMIT as f64;
+        if size > CODE_SIZE_LIMIT {
+            warnings.push(format!(
+                "! {} is {} bytes, over the {}-byte EIP-170 limit",
+                row.name, size, CODE_SIZE_LIMIT
+            ));
+        } else if share >= CODE_SIZE_WARNING {
+            warnings.push(format!(
+                "! {} is {} bytes, {:.1}% of the {}-byte EIP-170 limit",
+                row.name, size, share, CODE_SIZE_LIMIT
+            ));
+        }
+    }
+    warnings
+}
+
+/// Writes an amount with three significant digits, cents at least for USD.
+fn money(amount: f64, sign: bool, currency: Currency) -> String {
+    let prefix = match amount {
+        a if a < 0.0 => "-",
+        _ if sign => "+",
+        _ => "",
+    };
+    let abs = amount.abs();
+    let (symbol, min_decimals) = match currency {
+        Currency::Eth => ("", 0),
+        Currency::Usd => ("$", 2),
+    };
+    let decimals = match abs {
+        0.0 => min_decimals,
+        abs => (2 - abs.log10().floor() as i32).clamp(min_decimals, 18),
+    };
+    format!("{}{}{:.*}", prefix, symbol, decimals as usize, abs)
+}
+
+/// Sums the total gas of each file in its total column.
+fn total_record(comparison: &Comparison, options: &Options) -> Vec<String> {
+    let mut record = vec!["Total".to_owned()];
+    for total in &comparison.total_gas {
+        record.push("".to_owned());
+        record.extend(options.stats.iter().map(|stat| match stat {
+            Stat::Total => options.number_format.format(*total, false),
+            _ => "".to_owned(),
+        }));
+        record.extend(blanks(options));
+    }
+    record
+}
+
+/// A row with one value per file, shown relative to the first one.
+fn summary_record(
+    title: &str,
+    values: &[Option<u64>],
+    comparison: &Comparison,
+    options: &Options,
+) -> Vec<String> {
+    let first = values.first().copied().flatten();
+    let mut record = vec![title.to_owned()];
+    for (index, value) in values.iter().enumerate() {
+        record.push(match (value, first) {
+            (Some(value), Some(first)) if index > 0 => {
+                let change = stats::delta(first, *value);
+                let percent = 100f64 * change as f64 / first.max(1) as f64;
+                let text = delta(change, percent, None, options);
+                let status = comparison.threshold().status(change, percent);
+                highlight(&text, status, options)
+            }
+            (Some(value), _) => options.number_format.format(*value, false),
+            (None, _) => "".to_owned(),
+        });
+        record.extend(options.stats.iter().map(|_| "".to_owned()));
+        record.extend(blanks(options));
+    }
+    record
+}
+
+/// Writes a delta with its confidence interval, if any, and its percentage, padded so the
+/// percentages line up.
+fn delta(delta: i64, percent: f64, confidence: Option<f64>, options: &Options) -> String {
+    let confidence = confidence.map_or(String::new(), |confidence| {
+        let confidence = options
+            .number_format
+            .format(confidence.round() as u64, false);
+        format!(" ±{}", confidence)
+    });
+    format!(
+        "{}{} ({:+w$.d$}%)",
+        options.number_format.format(delta, true),
+        confidence,
+        percent,
+        w = options.decimals + 4,
+        d = options.decimals
+    )
+}
+
+/// Colors a delta by its status, prefixing an arrow if the theme asks for one.
+fn highlight(text: &str, status: Status, options: &Options) -> String {
+    let theme = &options.theme;
+    let (color, arrow) = match status {
+        Status::Increased => (&theme.increased, "▲ "),
+        Status::Decreased => (&theme.decreased, "▼ "),
+        _ => return text.to_owned(),
+    };
+    mark(text, color, arrow, options)
+}
+
+fn mark(text: &str, color: &str, arrow: &str, options: &Options) -> String {
+    if options.theme.arrows {
+        paint(&format!("{}{}", arrow, text), color, options)
+    } else {
+        paint(text, color, options)
+    }
+}
+
+pub(super) fn paint(text: &str, color: &str, options: &Options) -> String {
+    if options.color {
+        format!("\x1b[{}m{}\x1b[0m", color, text)
+    } else {
+        text.to_owned()
+    }
+}
+
+/// The gas of a file's cell, as a percentage of `base`, or its change, in percent of `previous`
+/// if given. With [`Options::absolute`] the change follows the gas.
+fn format_cell(cell: &Cell, base: u64, previous: Option<&Cell>, options: &Options) -> String {
+    let gas = format!(
+        "{} ({:w$.d$}%)",
+        options.number_format.format(cell.avg_gas, false),
+        100f64 * cell.avg_gas as f64 / base.max(1) as f64,
+        w = options.decimals + 3,
+        d = options.decimals
+    );
+    match (cell.delta, cell.delta_percent) {
+        (Some(delta), Some(percent)) => {
+            let percent = previous.map_or(percent, |previous| {
+                100f64 * (cell.avg_gas as f64 - previous.avg_gas as f64)
+                    / previous.avg_gas.max(1) as f64
+            });
+            let mut text = self::delta(delta, percent, cell.confidence, options);
+            if cell.noise {
+                text = format!("{} within noise", text);
+            }
+            let text = match cell.severity {
+                Some(Severity::Warning) => mark(&text, &options.theme.warning, "▲ ", options),
+                _ => highlight(&text, cell.status, options),
+            };
+            if options.absolute {
+                format!("{} {}", gas, text)
+            } else {
+                text
+            }
+        }
+        _ => gas,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{comparison::tests::report, Config};
+
+    /// A deposit that costs nothing but the intrinsic gas in the first file.
+    fn from_zero() -> Comparison {
+        let mut reports = vec![
+            ("a.json".to_owned(), report(&[("Vault.deposit", &[21_000])])),
+            ("b.json".to_owned(), report(&[("Vault.deposit", &[22_000])])),
+        ];
+        for (_, report) in &mut reports {
+            crate::intrinsic::exclude(report, false);
+        }
+        Comparison::from_reports(reports, &Config::default())
+    }
+
+    #[test]
+    fn totals_of_zero_have_finite_percentages() {
+        let options = Options {
+            totals: true,
+            weighted: true,
+            color: false,
+            ..Options::default()
+        };
+        let text = render(&from_zero(), &options);
+        assert!(!text.contains("inf") && !text.contains("NaN"), "{}", text);
+        assert_eq!(text.matches("+1000 (+100000.0%)").count(), 3, "{}", text);
+    }
+
+    #[test]
+    fn costs_of_zero_have_no_share() {
+        let comparison = from_zero();
+        let cell = comparison.entries[0].cells[1].as_ref().unwrap();
+        let options = Options {
+            color: false,
+            ..Options::default()
+        };
+        let text = format_cost(cell, Currency::Eth, 0.0, Some(0.0), &options);
+        assert!(!text.contains('%'), "{}", text);
+    }
+}
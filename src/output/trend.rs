@@ -0,0 +1,42 @@
+use tabled::{
+    builder::Builder,
+    settings::{object::Columns, Alignment, Modify},
+};
+
+use super::{table::sparkline, TableStyle};
+use crate::comparison::{Comparison, Row, Status};
+
+/// Lists an entry's gas in every recorded run, oldest first, marking the runs where it regressed,
+/// followed by a sparkline of the whole history.
+pub fn render(comparison: &Comparison, row: &Row, style: TableStyle) -> String {
+    let mut builder = Builder::default();
+    builder.set_header(["run", "gas", "change", ""]);
+    for (file, cell) in comparison.files.iter().zip(&row.cells) {
+        let record = match cell {
+            Some(cell) => {
+                let change = match (cell.delta, cell.delta_percent) {
+                    (Some(delta), Some(percent)) => format!("{:+} ({:+.1}%)", delta, percent),
+                    _ => "".to_owned(),
+                };
+                let marker = match cell.status {
+                    Status::Increased => "▲ regression",
+                    Status::Decreased => "▼",
+                    Status::Baseline | Status::Unchanged => "",
+                };
+                [
+                    file.clone(),
+                    cell.avg_gas.to_string(),
+                    change,
+                    marker.to_owned(),
+                ]
+            }
+            None => [file.clone(), "-".to_owned(), "".to_owned(), "".to_owned()],
+        };
+        builder.push_record(record);
+    }
+
+    let mut table = builder.build();
+    style.apply(&mut table);
+    table.with(Modify::new(Columns::new(1..3)).with(Alignment::right()));
+    format!("{}\n{} {}", table, row.name, sparkline(row))
+}
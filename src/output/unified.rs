@@ -0,0 +1,114 @@
+use super::{table, Options};
+use crate::comparison::{Comparison, Kind, Row, Status};
+
+/// Lists the entries like a unified diff from the first file to the last: changed entries as a
+/// removed and an added line, unchanged ones as context.
+pub fn render(comparison: &Comparison, options: &Options) -> String {
+    let (Some(first), Some(last)) = (comparison.files.first(), comparison.files.last()) else {
+        return String::new();
+    };
+    let width = comparison
+        .entries
+        .iter()
+        .map(|row| row.name.chars().count())
+        .max()
+        .unwrap_or_default();
+    let mut lines = vec![format!("--- {}", first), format!("+++ {}", last)];
+    for (kind, title) in [(Kind::Method, "methods"), (Kind::Deployment, "deployments")] {
+        let rows: Vec<&Row> = comparison
+            .entries
+            .iter()
+            .filter(|row| row.kind == kind)
+            .collect();
+        if rows.is_empty() {
+            continue;
+        }
+        lines.push(format!("@@ {} @@", title));
+        for row in rows {
+            lines.extend(self::lines(row, width, options));
+        }
+    }
+    lines.join("\n")
+}
+
+fn lines(row: &Row, width: usize, options: &Options) -> Vec<String> {
+    let gas = |gas: u64| options.number_format.format(gas, false);
+    let line = |sign: char, gas: String| format!("{} {:width$} {}", sign, row.name, gas);
+    let removed = |text: String| table::paint(&text, &options.theme.removed, options);
+    let added = |text: String| table::paint(&text, &options.theme.new, options);
+    let first = row.cells.first().and_then(Option::as_ref);
+    let last = row.cells.last().and_then(Option::as_ref);
+    match (first, last) {
+        (Some(first), Some(last))
+            if matches!(last.status, Status::Increased | Status::Decreased) =>
+        {
+            let delta = last.avg_gas as f64 - first.avg_gas as f64;
+            let percent = 100f64 * delta / first.avg_gas.max(1) as f64;
+            vec![
+                removed(line('-', gas(first.avg_gas))),
+                added(format!(
+                    "{}  ({:+.d$}%)",
+                    line('+', gas(last.avg_gas)),
+                    percent,
+                    d = options.decimals
+                )),
+            ]
+        }
+        (_, Some(last)) if first.is_none() => vec![added(line('+', gas(last.avg_gas)))],
+        (Some(first), None) => vec![removed(line('-', gas(first.avg_gas)))],
+        (_, Some(last)) => vec![line(' ', gas(last.avg_gas))],
+        (None, None) => vec![],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{comparison::tests::compare, Config};
+
+    #[test]
+    fn lists_changes_from_the_first_file_to_the_last() {
+        let comparison = compare(
+            &[
+                &[
+                    ("Vault.deposit", &[50_000]),
+                    ("Vault.withdraw", &[40_000]),
+                    ("Vault.mint", &[30_000]),
+                ],
+                &[
+                    ("Vault.deposit", &[52_000]),
+                    ("Vault.withdraw", &[40_000]),
+                    ("Router.swap", &[90_000]),
+                ],
+                &[
+                    ("Vault.deposit", &[55_000]),
+                    ("Vault.withdraw", &[40_000]),
+                    ("Vault.burn", &[20_000]),
+                ],
+            ],
+            &Config::default(),
+        );
+        let options = Options {
+            color: false,
+            ..Options::default()
+        };
+        // The swap only measured in the middle file has no line.
+        let lines = [
+            "--- a.json",
+            "+++ c.json",
+            "@@ methods @@",
+            "+ Vault.burn     20000",
+            "- Vault.deposit  50000",
+            "+ Vault.deposit  55000  (+10.0%)",
+            "- Vault.mint     30000",
+            "  Vault.withdraw 40000",
+        ];
+        assert_eq!(render(&comparison, &options), lines.join("\n"));
+    }
+
+    #[test]
+    fn renders_nothing_without_files() {
+        let comparison = Comparison::from_reports(vec![], &Config::default());
+        assert_eq!(render(&comparison, &Options::default()), "");
+    }
+}
@@ -0,0 +1,96 @@
+use std::{cmp::Reverse, collections::BTreeSet};
+
+use tabled::{
+    builder::Builder,
+    settings::{object::Columns, Alignment, Modify},
+};
+
+use super::TableStyle;
+use crate::comparison::{Cell, Comparison};
+
+/// Compares the changes of two branches, each against its own baseline, the entries where the
+/// branches differ most first. A negative difference means the second branch adds less gas.
+///
+/// Both comparisons should be made against their first file and include unchanged entries.
+pub fn render(a: &Comparison, b: &Comparison, style: TableStyle) -> String {
+    let label = |comparison: &Comparison| comparison.files.last().cloned().unwrap_or_default();
+    let (label_a, label_b) = (label(a), label(b));
+
+    let names: BTreeSet<&str> = a
+        .entries
+        .iter()
+        .chain(&b.entries)
+        .map(|row| row.name.as_str())
+        .collect();
+    let mut rows: Vec<_> = names
+        .into_iter()
+        .map(|name| {
+            let (a, b) = (change(a, name), change(b, name));
+            let difference = match (a.and_then(|a| a.delta), b.and_then(|b| b.delta)) {
+                (Some(a), Some(b)) => Some(b.saturating_sub(a)),
+                _ => None,
+            };
+            (name, a, b, difference)
+        })
+        .collect();
+    // Entries new or removed on a branch have no change to compare and come last.
+    rows.sort_by_key(|(_, _, _, difference)| {
+        (
+            difference.is_none(),
+            Reverse(difference.map(i64::unsigned_abs)),
+        )
+    });
+
+    let mut builder = Builder::default();
+    builder.set_header([
+        "Entry".to_owned(),
+        label_a.clone(),
+        label_b.clone(),
+        "Difference".to_owned(),
+    ]);
+    let format = |cell: Option<&Cell>| match cell {
+        Some(cell) => format!(
+            "{:+} ({:+.1}%)",
+            cell.delta.unwrap_or_default(),
+            cell.delta_percent.unwrap_or_default()
+        ),
+        None => "".to_owned(),
+    };
+    let mut total: i64 = 0;
+    for (name, a, b, difference) in rows {
+        total = total.saturating_add(difference.unwrap_or_default());
+        builder.push_record([
+            name.to_owned(),
+            format(a),
+            format(b),
+            difference.map_or(String::new(), |d| format!("{:+}", d)),
+        ]);
+    }
+
+    let mut table = builder.build();
+    style.apply(&mut table);
+    table.with(Modify::new(Columns::new(1..)).with(Alignment::right()));
+    let verdict = match total {
+        0 => format!(
+            "{} and {} add the same gas to their baselines",
+            label_b, label_a
+        ),
+        total => format!(
+            "{} adds {} {} gas than {} to its baseline, summed over the entries in both",
+            label_b,
+            total.unsigned_abs(),
+            if total < 0 { "less" } else { "more" },
+            label_a
+        ),
+    };
+    format!("{}\n{}", table, verdict)
+}
+
+/// The entry's cell in the second file, if it has a delta there.
+fn change<'a>(comparison: &'a Comparison, name: &str) -> Option<&'a Cell> {
+    let row = comparison.entries.iter().find(|row| row.name == name)?;
+    row.cells
+        .get(1)?
+        .as_ref()
+        .filter(|cell| cell.delta.is_some())
+}
@@ -0,0 +1,252 @@
+use super::escape;
+use crate::comparison::{Cell, Comparison, Kind, Row};
+
+const CONTENT_TYPES: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types">
+<Default Extension="rels" ContentType="application/vnd.openxmlformats-package.relationships+xml"/>
+<Default Extension="xml" ContentType="application/xml"/>
+<Override PartName="/xl/workbook.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.sheet.main+xml"/>
+<Override PartName="/xl/styles.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.styles+xml"/>
+<Override PartName="/xl/worksheets/sheet1.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.worksheet+xml"/>
+<Override PartName="/xl/worksheets/sheet2.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.worksheet+xml"/>
+</Types>"#;
+
+const RELS: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">
+<Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument" Target="xl/workbook.xml"/>
+</Relationships>"#;
+
+const WORKBOOK: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<workbook xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main" xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships">
+<sheets>
+<sheet name="Methods" sheetId="1" r:id="rId1"/>
+<sheet name="Deployments" sheetId="2" r:id="rId2"/>
+</sheets>
+</workbook>"#;
+
+const WORKBOOK_RELS: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">
+<Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/worksheet" Target="worksheets/sheet1.xml"/>
+<Relationship Id="rId2" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/worksheet" Target="worksheets/sheet2.xml"/>
+<Relationship Id="rId3" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/styles" Target="styles.xml"/>
+</Relationships>"#;
+
+/// Cell styles by index: plain, bold headers, signed gas deltas and signed percentages.
+const STYLES: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<styleSheet xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main">
+<numFmts count="2">
+<numFmt numFmtId="164" formatCode="+0;-0;0"/>
+<numFmt numFmtId="165" formatCode="+0.00%;-0.00%;0.00%"/>
+</numFmts>
+<fonts count="2"><font><sz val="11"/><name val="Calibri"/></font><font><b/><sz val="11"/><name val="Calibri"/></font></fonts>
+<fills count="2"><fill><patternFill patternType="none"/></fill><fill><patternFill patternType="gray125"/></fill></fills>
+<borders count="1"><border><left/><right/><top/><bottom/><diagonal/></border></borders>
+<cellStyleXfs count="1"><xf numFmtId="0" fontId="0" fillId="0" borderId="0"/></cellStyleXfs>
+<cellXfs count="4">
+<xf numFmtId="0" fontId="0" fillId="0" borderId="0" xfId="0"/>
+<xf numFmtId="0" fontId="1" fillId="0" borderId="0" xfId="0" applyFont="1"/>
+<xf numFmtId="164" fontId="0" fillId="0" borderId="0" xfId="0" applyNumberFormat="1"/>
+<xf numFmtId="165" fontId="0" fillId="0" borderId="0" xfId="0" applyNumberFormat="1"/>
+</cellXfs>
+<cellStyles count="1"><cellStyle name="Normal" xfId="0" builtinId="0"/></cellStyles>
+</styleSheet>"#;
+
+const HEADER: u8 = 1;
+const DELTA: u8 = 2;
+const PERCENT: u8 = 3;
+
+/// A value of a sheet, written with a type of its own.
+enum Value {
+    Text(String),
+    Number(f64, u8),
+    Empty,
+}
+
+/// Renders an Excel workbook with a sheet of methods and one of deployments. Gas, deltas and
+/// percentages are numbers, with a column each per file, so that they can be sorted, filtered and
+/// formatted conditionally.
+pub fn render(comparison: &Comparison) -> Vec<u8> {
+    let methods = sheet(
+        comparison,
+        Kind::Method,
+        &["Contract", "Method", "Signature"],
+        |row| {
+            vec![
+                Value::Text(row.contract.clone()),
+                Value::Text(row.method.clone().unwrap_or_default()),
+                Value::Text(row.signature.clone().unwrap_or_default()),
+            ]
+        },
+    );
+    let deployments = sheet(comparison, Kind::Deployment, &["Contract"], |row| {
+        vec![Value::Text(row.contract.clone())]
+    });
+    zip(&[
+        ("[Content_Types].xml", CONTENT_TYPES.as_bytes()),
+        ("_rels/.rels", RELS.as_bytes()),
+        ("xl/workbook.xml", WORKBOOK.as_bytes()),
+        ("xl/_rels/workbook.xml.rels", WORKBOOK_RELS.as_bytes()),
+        ("xl/styles.xml", STYLES.as_bytes()),
+        ("xl/worksheets/sheet1.xml", methods.as_bytes()),
+        ("xl/worksheets/sheet2.xml", deployments.as_bytes()),
+    ])
+}
+
+fn sheet(
+    comparison: &Comparison,
+    kind: Kind,
+    names: &[&str],
+    name: impl Fn(&Row) -> Vec<Value>,
+) -> String {
+    let mut header: Vec<Value> = names.iter().map(|n| Value::Text(n.to_string())).collect();
+    for file in &comparison.files {
+        header.push(Value::Text(format!("{} gas", file)));
+        header.push(Value::Text(format!("{} delta", file)));
+        header.push(Value::Text(format!("{} delta %", file)));
+        header.push(Value::Text(match kind {
+            Kind::Method => format!("{} calls", file),
+            Kind::Deployment => format!("{} size", file),
+        }));
+    }
+    let columns = header.len();
+    let mut rows = vec![(header, HEADER)];
+    for row in comparison.entries.iter().filter(|row| row.kind == kind) {
+        let mut values = name(row);
+        for cell in &row.cells {
+            values.extend(match cell {
+                Some(cell) => self::cell(cell, kind),
+                None => vec![Value::Empty, Value::Empty, Value::Empty, Value::Empty],
+            });
+        }
+        rows.push((values, 0));
+    }
+
+    let last = format!("{}{}", column(columns - 1), rows.len());
+    let mut xml = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n\
+         <worksheet xmlns=\"http://schemas.openxmlformats.org/spreadsheetml/2006/main\">\n\
+         <sheetViews><sheetView workbookViewId=\"0\"><pane ySplit=\"1\" topLeftCell=\"A2\" \
+         activePane=\"bottomLeft\" state=\"frozen\"/></sheetView></sheetViews>\n<sheetData>\n",
+    );
+    for (index, (values, style)) in rows.iter().enumerate() {
+        xml.push_str(&format!("<row r=\"{}\">", index + 1));
+        for (col, value) in values.iter().enumerate() {
+            let reference = format!("{}{}", column(col), index + 1);
+            match value {
+                Value::Text(text) => xml.push_str(&format!(
+                    "<c r=\"{}\" s=\"{}\" t=\"inlineStr\"><is><t>{}</t></is></c>",
+                    reference,
+                    style,
+                    escape(text)
+                )),
+                Value::Number(number, style) => xml.push_str(&format!(
+                    "<c r=\"{}\" s=\"{}\"><v>{}</v></c>",
+                    reference, style, number
+                )),
+                Value::Empty => {}
+            }
+        }
+        xml.push_str("</row>\n");
+    }
+    xml.push_str(&format!(
+        "</sheetData>\n<autoFilter ref=\"A1:{}\"/>\n</worksheet>",
+        last
+    ));
+    xml
+}
+
+fn cell(cell: &Cell, kind: Kind) -> Vec<Value> {
+    // Percentages relative to zero gas are infinite, which a workbook can't hold.
+    let number = |value: Option<f64>, style: u8| match value {
+        Some(value) if value.is_finite() => Value::Number(value, style),
+        _ => Value::Empty,
+    };
+    vec![
+        Value::Number(cell.avg_gas as f64, 0),
+        number(cell.delta.map(|delta| delta as f64), DELTA),
+        number(cell.delta_percent.map(|percent| percent / 100.0), PERCENT),
+        match kind {
+            Kind::Method => Value::Number(cell.calls as f64, 0),
+            Kind::Deployment => number(cell.code_size.map(|size| size as f64), 0),
+        },
+    ]
+}
+
+/// The letters of a column, counting from 0: `A` to `Z`, then `AA`.
+fn column(mut index: usize) -> String {
+    let mut letters = vec![];
+    loop {
+        letters.push(b'A' + (index % 26) as u8);
+        if index < 26 {
+            break;
+        }
+        index = index / 26 - 1;
+    }
+    letters.reverse();
+    String::from_utf8(letters).unwrap()
+}
+
+/// Packs files into a ZIP archive without compressing them.
+fn zip(files: &[(&str, &[u8])]) -> Vec<u8> {
+    let mut archive = vec![];
+    let mut directory = vec![];
+    for (name, data) in files {
+        let offset = archive.len() as u32;
+        let crc = crc32(data);
+        // Version, flags, method (stored), time and date (1980-01-01), then the checksum and sizes.
+        let mut fields = vec![];
+        fields.extend(20u16.to_le_bytes());
+        fields.extend(0u16.to_le_bytes());
+        fields.extend(0u16.to_le_bytes());
+        fields.extend(0u16.to_le_bytes());
+        fields.extend(0x21u16.to_le_bytes());
+        fields.extend(crc.to_le_bytes());
+        fields.extend((data.len() as u32).to_le_bytes());
+        fields.extend((data.len() as u32).to_le_bytes());
+        fields.extend((name.len() as u16).to_le_bytes());
+        fields.extend(0u16.to_le_bytes());
+
+        archive.extend(0x04034b50u32.to_le_bytes());
+        archive.extend(&fields);
+        archive.extend(name.as_bytes());
+        archive.extend(*data);
+
+        directory.extend(0x02014b50u32.to_le_bytes());
+        directory.extend(20u16.to_le_bytes());
+        directory.extend(&fields);
+        // Comment length, disk, internal and external attributes, then the local header's offset.
+        directory.extend(0u16.to_le_bytes());
+        directory.extend(0u16.to_le_bytes());
+        directory.extend(0u16.to_le_bytes());
+        directory.extend(0u32.to_le_bytes());
+        directory.extend(offset.to_le_bytes());
+        directory.extend(name.as_bytes());
+    }
+    let start = archive.len() as u32;
+    let size = directory.len() as u32;
+    archive.extend(directory);
+    archive.extend(0x06054b50u32.to_le_bytes());
+    archive.extend(0u16.to_le_bytes());
+    archive.extend(0u16.to_le_bytes());
+    archive.extend((files.len() as u16).to_le_bytes());
+    archive.extend((files.len() as u16).to_le_bytes());
+    archive.extend(size.to_le_bytes());
+    archive.extend(start.to_le_bytes());
+    archive.extend(0u16.to_le_bytes());
+    archive
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xEDB88320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
@@ -0,0 +1,54 @@
+use std::{
+    env,
+    io::{self, IsTerminal, Write},
+    process::{Command, Stdio},
+};
+
+/// When to show the output in a pager.
+#[derive(clap::ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Paging {
+    /// When stdout is a terminal and the output is taller than it
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+/// Prints text to stdout, through `$PAGER` or `less` if paging applies.
+pub fn print(text: &str, paging: Paging) {
+    let page = match paging {
+        Paging::Always => true,
+        Paging::Never => false,
+        Paging::Auto => {
+            io::stdout().is_terminal()
+                && ratatui::crossterm::terminal::size()
+                    .is_ok_and(|(_, rows)| text.lines().count() >= rows.into())
+        }
+    };
+    if !page || !pipe(text) {
+        println!("{}", text);
+    }
+}
+
+/// Writes text to the pager, returning false if none could be started.
+fn pipe(text: &str) -> bool {
+    let pager = env::var("PAGER")
+        .ok()
+        .filter(|pager| !pager.trim().is_empty())
+        .unwrap_or_else(|| "less".to_owned());
+    let mut words = pager.split_whitespace();
+    let mut command = Command::new(words.next().unwrap());
+    command.args(words).stdin(Stdio::piped());
+    if env::var_os("LESS").is_none() {
+        // Pass colors through and quit right away if the text fits after all, like git does.
+        command.env("LESS", "FRX");
+    }
+    let Ok(mut child) = command.spawn() else {
+        return false;
+    };
+    let mut stdin = child.stdin.take().unwrap();
+    // The pager closes its input when quit early, which is not an error.
+    let _ = writeln!(stdin, "{}", text);
+    drop(stdin);
+    child.wait().is_ok()
+}
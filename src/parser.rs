@@ -0,0 +1,418 @@
+//! The formats reports are read in, recognized by their content unless one is chosen.
+
+use std::{borrow::Cow, fmt};
+
+use itertools::Itertools;
+use serde::{
+    de::{self, IgnoredAny, MapAccess},
+    Deserialize, Deserializer,
+};
+use serde_json::Value;
+
+use crate::{
+    report::{self, GasReport, ParseError},
+    tenderly,
+};
+
+/// How a report is parsed, as far as its format supports it.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ParseOptions {
+    /// Leave out malformed entries, returning why each was left out.
+    pub lenient: bool,
+    /// Fold longer gas arrays into a sketch while parsing, unless `lenient`.
+    pub max_samples: Option<usize>,
+}
+
+/// A format that reports can be read in.
+pub trait ReportParser: Send + Sync {
+    /// Name to choose the format by, e.g. `hardhat`.
+    fn name(&self) -> &'static str;
+
+    /// Whether the input looks like this format. Formats that can only be told apart by the file
+    /// name never do.
+    fn detect(&self, bytes: &[u8]) -> bool;
+
+    /// Parses the input, returning the entries left out with `options.lenient` and why.
+    fn parse(
+        &self,
+        bytes: &[u8],
+        options: ParseOptions,
+    ) -> Result<(GasReport, Vec<ParseError>), ParseError>;
+
+    /// The input converted to JSON, for formats that only change the syntax of the JSON ones.
+    /// [`Parsers::parse`] then parses it in the format it is detected as.
+    fn to_json(&self, _bytes: &[u8]) -> Option<Result<Value, ParseError>> {
+        None
+    }
+}
+
+/// The JSON output of hardhat-gas-reporter, which every JSON input is taken for that no other
+/// parser recognizes.
+pub struct HardhatParser;
+
+impl ReportParser for HardhatParser {
+    fn name(&self) -> &'static str {
+        "hardhat"
+    }
+
+    fn detect(&self, bytes: &[u8]) -> bool {
+        probe(bytes).is_some_and(|probe| probe.info.is_some())
+    }
+
+    fn parse(
+        &self,
+        bytes: &[u8],
+        options: ParseOptions,
+    ) -> Result<(GasReport, Vec<ParseError>), ParseError> {
+        report::parse_hardhat(bytes, options)
+    }
+}
+
+/// A snapshot written by `eth-gas-delta snapshot`, recognized by its `schema`.
+pub struct SnapshotParser;
+
+impl ReportParser for SnapshotParser {
+    fn name(&self) -> &'static str {
+        "snapshot"
+    }
+
+    fn detect(&self, bytes: &[u8]) -> bool {
+        probe(bytes).is_some_and(|probe| probe.schema.is_some())
+    }
+
+    fn parse(
+        &self,
+        bytes: &[u8],
+        _options: ParseOptions,
+    ) -> Result<(GasReport, Vec<ParseError>), ParseError> {
+        Ok((report::parse_snapshot(bytes)?, vec![]))
+    }
+}
+
+/// Simulations exported from Tenderly, recognized by `simulation` or `simulation_results`.
+pub struct TenderlyParser;
+
+impl ReportParser for TenderlyParser {
+    fn name(&self) -> &'static str {
+        "tenderly"
+    }
+
+    fn detect(&self, bytes: &[u8]) -> bool {
+        probe(bytes)
+            .is_some_and(|probe| probe.simulation.is_some() || probe.simulation_results.is_some())
+    }
+
+    fn parse(
+        &self,
+        bytes: &[u8],
+        options: ParseOptions,
+    ) -> Result<(GasReport, Vec<ParseError>), ParseError> {
+        tenderly::parse(bytes, options)
+    }
+}
+
+/// A report of any JSON format written as YAML, which is only chosen by name since any JSON is
+/// valid YAML too.
+pub struct YamlParser;
+
+impl ReportParser for YamlParser {
+    fn name(&self) -> &'static str {
+        "yaml"
+    }
+
+    fn detect(&self, _bytes: &[u8]) -> bool {
+        false
+    }
+
+    fn parse(
+        &self,
+        bytes: &[u8],
+        options: ParseOptions,
+    ) -> Result<(GasReport, Vec<ParseError>), ParseError> {
+        // Used on its own, it knows only the built-in JSON formats.
+        Parsers::default().parse_json(&yaml(bytes)?, options)
+    }
+
+    fn to_json(&self, bytes: &[u8]) -> Option<Result<Value, ParseError>> {
+        Some(yaml(bytes))
+    }
+}
+
+/// A report of any JSON format written as TOML, chosen by name.
+pub struct TomlParser;
+
+impl ReportParser for TomlParser {
+    fn name(&self) -> &'static str {
+        "toml"
+    }
+
+    fn detect(&self, _bytes: &[u8]) -> bool {
+        false
+    }
+
+    fn parse(
+        &self,
+        bytes: &[u8],
+        options: ParseOptions,
+    ) -> Result<(GasReport, Vec<ParseError>), ParseError> {
+        Parsers::default().parse_json(&toml(bytes)?, options)
+    }
+
+    fn to_json(&self, bytes: &[u8]) -> Option<Result<Value, ParseError>> {
+        Some(toml(bytes))
+    }
+}
+
+const YAML_FORMAT: &str = "a report or snapshot written as YAML";
+const TOML_FORMAT: &str = "a report or snapshot written as TOML";
+
+const UNKNOWN_FORMAT: &str = "in one of the known formats";
+
+fn yaml(bytes: &[u8]) -> Result<Value, ParseError> {
+    serde_yaml::from_slice(bytes).map_err(|e| ParseError {
+        path: String::new(),
+        message: e.to_string(),
+        expected: YAML_FORMAT,
+    })
+}
+
+fn toml(bytes: &[u8]) -> Result<Value, ParseError> {
+    let error = |message: String| ParseError {
+        path: String::new(),
+        message,
+        expected: TOML_FORMAT,
+    };
+    let text = std::str::from_utf8(bytes).map_err(|e| error(e.to_string()))?;
+    toml::from_str(text).map_err(|e| error(e.to_string()))
+}
+
+/// The top-level fields formats are told apart by, read without building a JSON tree.
+#[derive(Deserialize)]
+struct Probe {
+    schema: Option<IgnoredAny>,
+    info: Option<IgnoredAny>,
+    simulation: Option<IgnoredAny>,
+    simulation_results: Option<IgnoredAny>,
+}
+
+fn probe(bytes: &[u8]) -> Option<Probe> {
+    serde_json::from_slice(bytes).ok()
+}
+
+/// The reports bundled in one input, the elements of a JSON array or the lines of NDJSON, or else
+/// the whole input as a single report.
+pub fn split(bytes: &[u8]) -> Vec<Cow<'_, [u8]>> {
+    if bytes.trim_ascii_start().starts_with(b"[") {
+        if let Ok(reports) = serde_json::from_slice::<Vec<Value>>(bytes) {
+            return reports
+                .iter()
+                .map(|report| Cow::Owned(serde_json::to_vec(report).unwrap()))
+                .collect();
+        }
+    }
+    let lines: Vec<&[u8]> = bytes
+        .split(|&byte| byte == b'\n')
+        .map(<[u8]>::trim_ascii)
+        .filter(|line| !line.is_empty())
+        .collect();
+    let ndjson = lines.len() > 1
+        && lines.iter().all(|line| {
+            line.starts_with(b"{") && serde_json::from_slice::<IgnoredAny>(line).is_ok()
+        });
+    match ndjson {
+        true => lines.into_iter().map(Cow::Borrowed).collect(),
+        false => vec![Cow::Borrowed(bytes)],
+    }
+}
+
+/// The reports of an input holding one per network, like
+/// `{"networks": {"mainnet": {…}, "arbitrum": {…}}}`, by network name in the order of the input.
+/// `None` for other inputs.
+pub fn networks(bytes: &[u8]) -> Option<Vec<(String, Vec<u8>)>> {
+    #[derive(Deserialize)]
+    struct Networks {
+        networks: InOrder,
+        info: Option<IgnoredAny>,
+    }
+    let parsed: Networks = serde_json::from_slice(bytes).ok()?;
+    let networks = parsed.networks.0;
+    if parsed.info.is_some() || !networks.iter().all(|(_, report)| report.is_object()) {
+        return None;
+    }
+    let networks = networks.into_iter();
+    Some(
+        networks
+            .map(|(name, report)| (name, serde_json::to_vec(&report).unwrap()))
+            .collect(),
+    )
+}
+
+/// The entries of a JSON object, which `serde_json::Map` would sort.
+struct InOrder(Vec<(String, Value)>);
+
+impl<'de> Deserialize<'de> for InOrder {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct Visitor;
+
+        impl<'de> de::Visitor<'de> for Visitor {
+            type Value = InOrder;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("an object")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<InOrder, A::Error> {
+                let mut entries = vec![];
+                while let Some(entry) = map.next_entry()? {
+                    entries.push(entry);
+                }
+                Ok(InOrder(entries))
+            }
+        }
+
+        deserializer.deserialize_map(Visitor)
+    }
+}
+
+/// The known formats, tried in order when detecting one.
+pub struct Parsers {
+    parsers: Vec<Box<dyn ReportParser>>,
+}
+
+impl Default for Parsers {
+    fn default() -> Self {
+        Parsers {
+            parsers: vec![
+                Box::new(SnapshotParser),
+                Box::new(HardhatParser),
+                Box::new(TenderlyParser),
+                Box::new(YamlParser),
+                Box::new(TomlParser),
+            ],
+        }
+    }
+}
+
+impl fmt::Debug for Parsers {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.names()).finish()
+    }
+}
+
+impl Parsers {
+    /// Adds a format, which is tried before the ones already known and replaces one of the same
+    /// name.
+    pub fn register(&mut self, parser: Box<dyn ReportParser>) {
+        self.parsers.retain(|known| known.name() != parser.name());
+        self.parsers.insert(0, parser);
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.parsers.iter().map(|parser| parser.name())
+    }
+
+    pub fn get(&self, name: &str) -> Option<&dyn ReportParser> {
+        self.parsers
+            .iter()
+            .find(|parser| parser.name() == name)
+            .map(Box::as_ref)
+    }
+
+    /// The first format that recognizes the input, or else hardhat-gas-reporter's, so that errors
+    /// explain what the input should look like.
+    pub fn detect(&self, bytes: &[u8]) -> &dyn ReportParser {
+        self.parsers
+            .iter()
+            .map(Box::as_ref)
+            .find(|parser| parser.detect(bytes))
+            .unwrap_or(&HardhatParser)
+    }
+
+    /// Parses the input in the format of that name, or else in the detected one.
+    pub fn parse(
+        &self,
+        bytes: &[u8],
+        format: Option<&str>,
+        options: ParseOptions,
+    ) -> Result<(GasReport, Vec<ParseError>), ParseError> {
+        let parser = match format {
+            Some(name) => self.get(name).ok_or_else(|| ParseError {
+                path: String::new(),
+                message: format!(
+                    "unknown input format {} (known: {})",
+                    name,
+                    self.names().join(", ")
+                ),
+                expected: UNKNOWN_FORMAT,
+            })?,
+            None => {
+                let parser = self.detect(bytes);
+                log::debug!("detected a {} report", parser.name());
+                parser
+            }
+        };
+        match parser.to_json(bytes) {
+            Some(value) => self.parse_json(&value?, options),
+            None => parser.parse(bytes, options),
+        }
+    }
+
+    /// Parses a document converted to JSON in the format it is detected as.
+    fn parse_json(
+        &self,
+        value: &Value,
+        options: ParseOptions,
+    ) -> Result<(GasReport, Vec<ParseError>), ParseError> {
+        let json = serde_json::to_vec(value).unwrap();
+        self.parse(&json, None, options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Reads any input with a `custom` field as an empty report.
+    struct CustomParser;
+
+    impl ReportParser for CustomParser {
+        fn name(&self) -> &'static str {
+            "custom"
+        }
+
+        fn detect(&self, bytes: &[u8]) -> bool {
+            bytes.starts_with(br#"{"custom""#)
+        }
+
+        fn parse(
+            &self,
+            _bytes: &[u8],
+            _options: ParseOptions,
+        ) -> Result<(GasReport, Vec<ParseError>), ParseError> {
+            Ok((GasReport::merge([]), vec![]))
+        }
+    }
+
+    #[test]
+    fn rejects_unknown_formats() {
+        let error = Parsers::default()
+            .parse(b"{}", Some("foundry"), ParseOptions::default())
+            .unwrap_err();
+        assert!(error
+            .message
+            .starts_with("unknown input format foundry (known: snapshot,"));
+    }
+
+    #[test]
+    fn converted_inputs_are_detected_among_registered_formats() {
+        let mut parsers = Parsers::default();
+        parsers.register(Box::new(CustomParser));
+        for (format, input) in [("yaml", "custom: 1"), ("toml", "custom = 1")] {
+            let parse = |parsers: &Parsers| {
+                parsers.parse(input.as_bytes(), Some(format), ParseOptions::default())
+            };
+            assert!(parse(&parsers).is_ok(), "{}", format);
+            assert!(parse(&Parsers::default()).is_err(), "{}", format);
+        }
+    }
+}
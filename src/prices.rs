@@ -0,0 +1,131 @@
+use anyhow::{bail, Context, Result};
+use serde_json::{json, Value};
+
+use crate::{
+    cache::{self, Expiry},
+    cli::GlobalArgs,
+};
+
+const ETH_USD_URL: &str =
+    "https://api.coingecko.com/api/v3/simple/price?ids=ethereum&vs_currencies=usd";
+
+/// Blocks whose priority fees `--fee-percentile` looks at.
+const FEE_HISTORY_BLOCKS: usize = 20;
+
+/// The price per gas in gwei under EIP-1559: the base fee plus the priority fee, at most the max
+/// fee. `None` without a base fee.
+pub fn gas_price(global: &GlobalArgs) -> Option<f64> {
+    let base = match &global.rpc_url {
+        Some(url) => base_fee(url)?,
+        None => global.base_fee?,
+    };
+    let priority = match (global.priority_fee, &global.rpc_url, global.fee_percentile) {
+        (Some(priority), _, _) => priority,
+        (None, Some(url), Some(percentile)) => priority_fee(url, percentile).unwrap_or_default(),
+        _ => 0.0,
+    };
+    let price = match global.max_fee {
+        Some(max) => (base + priority).min(max),
+        None => base + priority,
+    };
+    log::info!(
+        "gas price {} gwei: base fee {} + priority fee {}",
+        price,
+        base,
+        priority
+    );
+    Some(price)
+}
+
+/// The base fee of the latest block in gwei.
+fn base_fee(rpc_url: &str) -> Option<f64> {
+    cached(&format!("base_fee {}", rpc_url), "the base fee", || {
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "eth_getBlockByNumber",
+            "params": ["latest", false],
+        });
+        let block = call(rpc_url, request)?;
+        let fee = block
+            .get("baseFeePerGas")
+            .context("the latest block has no base fee")?;
+        gwei(fee)
+    })
+}
+
+/// The median over recent blocks of the given percentile of their priority fees, in gwei.
+fn priority_fee(rpc_url: &str, percentile: f64) -> Option<f64> {
+    let key = format!("priority_fee {} {}", rpc_url, percentile);
+    cached(&key, "the priority fee", || {
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "eth_feeHistory",
+            "params": [format!("{:#x}", FEE_HISTORY_BLOCKS), "latest", [percentile]],
+        });
+        let history = call(rpc_url, request)?;
+        let mut rewards = history
+            .get("reward")
+            .and_then(Value::as_array)
+            .context("the fee history has no rewards")?
+            .iter()
+            .filter_map(|block| block.get(0))
+            .map(gwei)
+            .collect::<Result<Vec<_>>>()?;
+        if rewards.is_empty() {
+            bail!("the fee history is empty");
+        }
+        rewards.sort_by(f64::total_cmp);
+        Ok(rewards[rewards.len() / 2])
+    })
+}
+
+/// Sends a JSON-RPC request and returns its result.
+pub fn call(rpc_url: &str, request: Value) -> Result<Value> {
+    let response = ureq::post(rpc_url)
+        .set("Content-Type", "application/json")
+        .send_string(&request.to_string())?;
+    let mut response: Value = serde_json::from_reader(response.into_reader())?;
+    if let Some(error) = response.get("error") {
+        bail!("the node answered {}", error);
+    }
+    Ok(response["result"].take())
+}
+
+/// Converts a hex amount of wei to gwei.
+fn gwei(value: &Value) -> Result<f64> {
+    let hex = value.as_str().context("expected a hex quantity")?;
+    let wei = u128::from_str_radix(hex.trim_start_matches("0x"), 16)
+        .with_context(|| format!("invalid quantity {}", hex))?;
+    Ok(wei as f64 / 1e9)
+}
+
+/// The price of ETH in USD from CoinGecko.
+pub fn eth_usd() -> Option<f64> {
+    cached("eth_usd", "the ETH price", || {
+        let response = ureq::get(ETH_USD_URL)
+            .set("User-Agent", env!("CARGO_PKG_NAME"))
+            .call()?;
+        let response: Value = serde_json::from_reader(response.into_reader())?;
+        response
+            .pointer("/ethereum/usd")
+            .and_then(Value::as_f64)
+            .context("unexpected response from CoinGecko")
+    })
+}
+
+/// Returns a fresh cached price or fetches it, leaving it out when neither works.
+fn cached(key: &str, what: &str, fetch: impl FnOnce() -> Result<f64>) -> Option<f64> {
+    let value = cache::get(key, what, Expiry::Ttl, || Ok(json!(fetch()?)));
+    match value.and_then(|value| value.as_f64().context("not a number")) {
+        Ok(value) => {
+            log::info!("{} is {}", what, value);
+            Some(value)
+        }
+        Err(e) => {
+            log::warn!("cannot fetch {}, leaving out its column: {:#}", what, e);
+            None
+        }
+    }
+}
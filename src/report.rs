@@ -0,0 +1,561 @@
+use std::{
+    cell::Cell,
+    cmp::Ordering,
+    collections::{hash_map::DefaultHasher, HashMap},
+    error::Error,
+    fmt,
+    hash::{Hash, Hasher},
+    mem,
+};
+
+use serde::{
+    de::{SeqAccess, Visitor},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+use serde_json::{Map, Value};
+
+use crate::{
+    parser::{ParseOptions, Parsers},
+    snapshot::Snapshot,
+    stats::{Aggregate, Sketch, Summary},
+};
+
+/// The JSON output of hardhat-gas-reporter.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GasReport {
+    pub info: Info,
+}
+
+impl GasReport {
+    /// Combines the reports of sharded test runs into one, concatenating the gas data and summing
+    /// the calls of matching methods and deployments.
+    pub fn merge(reports: impl IntoIterator<Item = GasReport>) -> GasReport {
+        let mut merged = Info {
+            methods: HashMap::new(),
+            deployments: vec![],
+        };
+        for report in reports {
+            for (key, method) in report.info.methods {
+                match merged.methods.get_mut(&key) {
+                    Some(existing) => existing.absorb(method),
+                    None => {
+                        merged.methods.insert(key, method);
+                    }
+                }
+            }
+            for depl in report.info.deployments {
+                match merged.deployments.iter_mut().find(|d| d.name == depl.name) {
+                    Some(existing) => existing.gas_data.extend(depl.gas_data),
+                    None => merged.deployments.push(depl),
+                }
+            }
+        }
+        GasReport { info: merged }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Info {
+    pub methods: HashMap<String, RawMethod>,
+    pub deployments: Vec<RawDeployment>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RawDeployment {
+    pub name: String,
+    #[serde(rename = "gasData")]
+    pub gas_data: GasData,
+    #[serde(
+        rename = "deployedBytecode",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub deployed_bytecode: Option<String>,
+    /// Size of the deployed code where the bytecode itself is not kept, as in snapshots.
+    #[serde(
+        rename = "deployedBytecodeSize",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub code_size: Option<usize>,
+}
+
+impl RawMethod {
+    /// Adds the calls of another run or overload of the method.
+    pub fn absorb(&mut self, other: RawMethod) {
+        self.gas_data.extend(other.gas_data);
+        self.number_of_calls += other.number_of_calls;
+        for (test, gas_data) in other.tests {
+            self.tests.entry(test).or_default().extend(gas_data);
+        }
+    }
+}
+
+impl RawDeployment {
+    /// Size of the deployed code in bytes, if the report has it.
+    pub fn code_size(&self) -> Option<usize> {
+        let bytecode = self.deployed_bytecode.as_deref();
+        let from_bytecode = bytecode.map(|code| code.trim_start_matches("0x").len() / 2);
+        self.code_size.or(from_bytecode)
+    }
+
+    /// Hash of the deployed bytecode, to tell whether it changed, if the report keeps it.
+    pub fn code_hash(&self) -> Option<u64> {
+        let code = self.deployed_bytecode.as_deref()?.trim_start_matches("0x");
+        let mut hasher = DefaultHasher::new();
+        code.to_lowercase().hash(&mut hasher);
+        Some(hasher.finish())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RawMethod {
+    pub key: String,
+    #[serde(flatten)]
+    pub method: MethodIdentifier,
+    #[serde(rename = "fnSig")]
+    pub signature: String,
+    #[serde(rename = "gasData")]
+    pub gas_data: GasData,
+    #[serde(rename = "numberOfCalls")]
+    pub number_of_calls: usize,
+    /// Gas of the calls made by each test, for reporters that attribute calls to tests.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub tests: HashMap<String, GasData>,
+}
+
+/// The gas samples of an entry. [`parse_streaming`] folds long arrays into a sketch, which keeps the
+/// exact count, sum, minimum and maximum but only some of the samples.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GasData {
+    samples: Vec<u64>,
+    /// Statistics of all samples, set if some were left out.
+    summary: Option<Summary>,
+}
+
+impl GasData {
+    /// The samples, or those kept by the sketch.
+    pub fn samples(&self) -> &[u64] {
+        &self.samples
+    }
+
+    pub fn is_sketch(&self) -> bool {
+        self.summary.is_some()
+    }
+
+    /// Number of samples, including those left out.
+    pub fn len(&self) -> usize {
+        self.summary
+            .map_or(self.samples.len(), |summary| summary.count)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn summary(&self) -> Option<Summary> {
+        self.summary.or_else(|| Summary::of(&self.samples))
+    }
+
+    pub fn min(&self) -> Option<u64> {
+        Some(self.summary()?.min)
+    }
+
+    pub fn max(&self) -> Option<u64> {
+        Some(self.summary()?.max)
+    }
+
+    /// Sum of all samples, saturating at `u64::MAX`.
+    pub fn sum(&self) -> u64 {
+        let sum = self.summary().map_or(0, |summary| summary.sum);
+        u64::try_from(sum).unwrap_or(u64::MAX)
+    }
+
+    /// Aggregates non-empty gas data, exactly for the mean, minimum and maximum and estimated from
+    /// the kept samples otherwise.
+    pub fn aggregate(&self, aggregate: Aggregate) -> u64 {
+        match (aggregate, self.summary) {
+            (Aggregate::Mean, Some(summary)) => summary.mean(),
+            (Aggregate::Min, Some(summary)) => summary.min,
+            (Aggregate::Max, Some(summary)) => summary.max,
+            _ => aggregate.apply(&self.samples),
+        }
+    }
+
+    /// Subtracts `gas` from every sample, down to zero.
+    pub fn subtract(&mut self, gas: u64) {
+        for sample in &mut self.samples {
+            *sample = sample.saturating_sub(gas);
+        }
+        if let Some(summary) = &mut self.summary {
+            summary.sum = summary
+                .sum
+                .saturating_sub(gas as u128 * summary.count as u128);
+            summary.min = summary.min.saturating_sub(gas);
+            summary.max = summary.max.saturating_sub(gas);
+        }
+    }
+
+    pub fn extend(&mut self, other: GasData) {
+        if self.is_sketch() || other.is_sketch() {
+            self.summary = match (self.summary(), other.summary()) {
+                (Some(l), Some(r)) => Some(l.combine(r)),
+                (l, r) => l.or(r),
+            };
+        }
+        self.samples.extend(other.samples);
+    }
+}
+
+impl From<Vec<u64>> for GasData {
+    fn from(samples: Vec<u64>) -> Self {
+        GasData {
+            samples,
+            summary: None,
+        }
+    }
+}
+
+impl Serialize for GasData {
+    /// Writes the kept samples.
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.samples.serialize(serializer)
+    }
+}
+
+thread_local! {
+    /// Sample limit of the [`parse_streaming`] call running on this thread.
+    static MAX_SAMPLES: Cell<Option<usize>> = const { Cell::new(None) };
+}
+
+impl<'de> Deserialize<'de> for GasData {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct SketchVisitor(usize);
+
+        impl<'de> Visitor<'de> for SketchVisitor {
+            type Value = GasData;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a sequence of gas values")
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<GasData, A::Error> {
+                let mut sketch = Sketch::new(self.0);
+                while let Some(sample) = seq.next_element()? {
+                    sketch.push(sample);
+                }
+                let (samples, summary) = sketch.finish();
+                Ok(GasData { samples, summary })
+            }
+        }
+
+        match MAX_SAMPLES.get() {
+            Some(max_samples) => deserializer.deserialize_seq(SketchVisitor(max_samples)),
+            None => Vec::deserialize(deserializer).map(GasData::from),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct MethodIdentifier {
+    pub contract: String,
+    pub method: String,
+}
+
+impl PartialOrd for MethodIdentifier {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for MethodIdentifier {
+    fn cmp(&self, other: &Self) -> Ordering {
+        if self.contract == other.contract {
+            self.method.cmp(&other.method)
+        } else {
+            self.contract.cmp(&other.contract)
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Entry {
+    Deployment(RawDeployment),
+    Method(RawMethod),
+}
+
+impl Entry {
+    pub fn avg_gas(&self) -> u64 {
+        self.gas_data().aggregate(Aggregate::Mean)
+    }
+
+    /// Number of calls of a method, or the number of deployments.
+    pub fn calls(&self) -> usize {
+        match self {
+            Entry::Deployment(depl) => depl.gas_data.len(),
+            Entry::Method(m) => m.number_of_calls,
+        }
+    }
+
+    /// Size of a deployment's code in bytes, if known.
+    pub fn code_size(&self) -> Option<usize> {
+        match self {
+            Entry::Deployment(depl) => depl.code_size(),
+            Entry::Method(_) => None,
+        }
+    }
+
+    /// Hash of a deployment's bytecode, if known.
+    pub fn code_hash(&self) -> Option<u64> {
+        match self {
+            Entry::Deployment(depl) => depl.code_hash(),
+            Entry::Method(_) => None,
+        }
+    }
+
+    /// Gas of a method's calls per test, empty if the report doesn't attribute them.
+    pub fn tests(&self) -> Option<&HashMap<String, GasData>> {
+        match self {
+            Entry::Deployment(_) => None,
+            Entry::Method(m) => Some(&m.tests),
+        }
+    }
+
+    pub fn has_gas_data(&self) -> bool {
+        !self.gas_data().is_empty()
+    }
+
+    pub fn gas_data(&self) -> &GasData {
+        match self {
+            Entry::Deployment(depl) => &depl.gas_data,
+            Entry::Method(m) => &m.gas_data,
+        }
+    }
+}
+
+/// Why a report could not be parsed.
+#[derive(Debug)]
+pub struct ParseError {
+    /// Where in the JSON the error is, e.g. `info.methods.Vault_deposit.gasData[2]`, empty if the
+    /// input is no valid JSON at all.
+    pub path: String,
+    pub message: String,
+    /// The format the input was parsed as.
+    pub expected: &'static str,
+}
+
+impl ParseError {
+    /// The same error for a value nested at `path`.
+    pub(crate) fn within(mut self, path: &str) -> Self {
+        self.path = match self.path.as_str() {
+            "" => path.to_owned(),
+            inner if inner.starts_with('[') => format!("{}{}", path, inner),
+            inner => format!("{}.{}", path, inner),
+        };
+        self
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if !self.path.is_empty() {
+            write!(f, "at {}: ", self.path)?;
+        }
+        write!(f, "{}; the input should be {}", self.message, self.expected)
+    }
+}
+
+impl Error for ParseError {}
+
+pub(crate) const REPORT_FORMAT: &str =
+    "the JSON output of hardhat-gas-reporter with `outputJSON: true`";
+pub(crate) const SNAPSHOT_FORMAT: &str = "a snapshot written by `eth-gas-delta snapshot`";
+
+/// Parses a report in any of the built-in [`Parsers`], recognized by its content.
+pub fn parse(bytes: &[u8]) -> Result<GasReport, ParseError> {
+    Ok(Parsers::default()
+        .parse(bytes, None, ParseOptions::default())?
+        .0)
+}
+
+/// Like [`parse`], but leaves out malformed methods and deployments of a report, returning why
+/// each was left out.
+pub fn parse_lenient(bytes: &[u8]) -> Result<(GasReport, Vec<ParseError>), ParseError> {
+    let options = ParseOptions {
+        lenient: true,
+        ..ParseOptions::default()
+    };
+    Parsers::default().parse(bytes, None, options)
+}
+
+/// Like [`parse`], but deserializes reports without building a JSON tree first and folds gas
+/// arrays longer than `max_samples` into a sketch, which keeps memory low for huge reports.
+pub fn parse_streaming(bytes: &[u8], max_samples: usize) -> Result<GasReport, ParseError> {
+    let options = ParseOptions {
+        max_samples: Some(max_samples),
+        ..ParseOptions::default()
+    };
+    Ok(Parsers::default().parse(bytes, None, options)?.0)
+}
+
+/// Parses a hardhat-gas-reporter report.
+pub(crate) fn parse_hardhat(
+    bytes: &[u8],
+    options: ParseOptions,
+) -> Result<(GasReport, Vec<ParseError>), ParseError> {
+    if options.lenient {
+        return parse_hardhat_lenient(bytes);
+    }
+    if let Some(max_samples) = options.max_samples {
+        log::debug!("keeping at most {max_samples} samples per entry");
+        MAX_SAMPLES.set(Some(max_samples));
+        let mut deserializer = serde_json::Deserializer::from_slice(bytes);
+        let report = deserialize(&mut deserializer, REPORT_FORMAT);
+        MAX_SAMPLES.set(None);
+        return Ok((report?, vec![]));
+    }
+    Ok((deserialize(json(bytes)?, REPORT_FORMAT)?, vec![]))
+}
+
+fn parse_hardhat_lenient(bytes: &[u8]) -> Result<(GasReport, Vec<ParseError>), ParseError> {
+    let mut value = json(bytes)?;
+    // Parse the entries one by one, the rest of the report as usual.
+    let methods = match value.pointer_mut("/info/methods") {
+        Some(methods @ Value::Object(_)) => mem::replace(methods, Value::Object(Map::new())),
+        _ => Value::Null,
+    };
+    let deployments = match value.pointer_mut("/info/deployments") {
+        Some(deployments @ Value::Array(_)) => mem::replace(deployments, Value::Array(vec![])),
+        _ => Value::Null,
+    };
+    let mut report: GasReport = deserialize(value, REPORT_FORMAT)?;
+
+    let mut skipped = vec![];
+    if let Value::Object(methods) = methods {
+        for (key, method) in methods {
+            match deserialize(method, REPORT_FORMAT) {
+                Ok(method) => {
+                    report.info.methods.insert(key, method);
+                }
+                Err(e) => skipped.push(e.within(&format!("info.methods.{}", key))),
+            }
+        }
+    }
+    if let Value::Array(deployments) = deployments {
+        for (index, deployment) in deployments.into_iter().enumerate() {
+            match deserialize(deployment, REPORT_FORMAT) {
+                Ok(deployment) => report.info.deployments.push(deployment),
+                Err(e) => skipped.push(e.within(&format!("info.deployments[{}]", index))),
+            }
+        }
+    }
+    Ok((report, skipped))
+}
+
+fn json(bytes: &[u8]) -> Result<Value, ParseError> {
+    serde_json::from_slice(bytes).map_err(|e| ParseError {
+        path: String::new(),
+        message: e.to_string(),
+        expected: REPORT_FORMAT,
+    })
+}
+
+/// Parses a snapshot, which holds a single value per entry anyway.
+pub(crate) fn parse_snapshot(bytes: &[u8]) -> Result<GasReport, ParseError> {
+    let snapshot: Snapshot = deserialize(json(bytes)?, SNAPSHOT_FORMAT)?;
+    snapshot.into_report().map_err(|message| ParseError {
+        path: "schema".to_owned(),
+        message,
+        expected: SNAPSHOT_FORMAT,
+    })
+}
+
+fn deserialize<'de, D, T>(deserializer: D, expected: &'static str) -> Result<T, ParseError>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    serde_path_to_error::deserialize(deserializer).map_err(|e| ParseError {
+        path: match e.path().to_string() {
+            root if root == "." => String::new(),
+            path => path,
+        },
+        message: e.into_inner().to_string(),
+        expected,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report(gas: &str) -> String {
+        format!(
+            r#"{{"namespace": "ethGasStatsReport", "config": {{}}, "info": {{
+                "methods": {{"Vault_deposit": {{"key": "Vault_deposit", "contract": "Vault",
+                    "method": "deposit", "fnSig": "deposit(uint256)",
+                    "gasData": [{}], "numberOfCalls": 2}}}},
+                "deployments": []}}}}"#,
+            gas
+        )
+    }
+
+    #[test]
+    fn parses_gas_beyond_i64() {
+        let report = parse(report("18446744073709551615, 18446744073709551613").as_bytes())
+            .expect("values up to u64::MAX are valid gas");
+        let method = report.info.methods["Vault_deposit"].clone();
+        assert_eq!(method.gas_data.samples(), [u64::MAX, u64::MAX - 2]);
+        assert_eq!(Entry::Method(method).avg_gas(), u64::MAX - 1);
+    }
+
+    #[test]
+    fn streaming_folds_long_gas_arrays() {
+        let gas = (1..=1000).map(|gas| gas.to_string()).collect::<Vec<_>>();
+        let report = parse_streaming(report(&gas.join(", ")).as_bytes(), 10).unwrap();
+        let entry = Entry::Method(report.info.methods["Vault_deposit"].clone());
+        let gas_data = entry.gas_data();
+        assert!(gas_data.is_sketch());
+        assert!(gas_data.samples().len() <= 10);
+        assert_eq!(gas_data.len(), 1000);
+        assert_eq!((gas_data.min(), gas_data.max()), (Some(1), Some(1000)));
+        assert_eq!(gas_data.sum(), 500_500);
+        assert_eq!(entry.avg_gas(), 500);
+    }
+
+    #[test]
+    fn streaming_reports_the_path_of_errors() {
+        let error = parse_streaming(report("1, -2").as_bytes(), 10).unwrap_err();
+        assert_eq!(error.path, "info.methods.Vault_deposit.gasData[1]");
+    }
+
+    #[test]
+    fn merge_combines_gas_per_test() {
+        let with_tests = |tests: &str| {
+            let json = report("1, 2").replace(
+                r#""numberOfCalls": 2"#,
+                &format!(r#""numberOfCalls": 2, "tests": {}"#, tests),
+            );
+            parse(json.as_bytes()).unwrap()
+        };
+        let merged = GasReport::merge([
+            with_tests(r#"{"a": [1], "b": [2]}"#),
+            with_tests(r#"{"b": [3, 4]}"#),
+        ]);
+        let tests = &merged.info.methods["Vault_deposit"].tests;
+        assert_eq!(tests["a"].samples(), [1]);
+        assert_eq!(tests["b"].samples(), [2, 3, 4]);
+    }
+
+    #[test]
+    fn rejects_negative_gas() {
+        let error = parse(report("-1, 2").as_bytes()).unwrap_err();
+        assert_eq!(error.path, "info.methods.Vault_deposit.gasData[0]");
+    }
+
+    #[test]
+    fn rejects_gas_beyond_u64() {
+        let error = parse(report("18446744073709551616, 2").as_bytes()).unwrap_err();
+        assert_eq!(error.path, "info.methods.Vault_deposit.gasData[0]");
+    }
+}
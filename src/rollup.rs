@@ -0,0 +1,87 @@
+use std::collections::HashMap;
+
+use serde::Deserialize;
+
+use crate::comparison::Network;
+
+/// Bytes of signature, nonce, fee fields and RLP framing that a transaction adds to its calldata.
+pub const TX_OVERHEAD: u64 = 100;
+/// Calldata bytes assumed for entries without a size of their own.
+pub const CALLDATA: u64 = 100;
+/// Ecotone fee scalars of OP Mainnet at the time of writing.
+pub const BASE_FEE_SCALAR: f64 = 1368.0;
+pub const BLOB_BASE_FEE_SCALAR: f64 = 810_949.0;
+
+/// How a rollup charges for posting a transaction's data to L1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rollup {
+    /// OP Mainnet, Base and other OP Stack chains since the Ecotone upgrade
+    OpStack,
+    /// Arbitrum One, which charges 16 units of the L1 price per byte
+    Arbitrum,
+}
+
+impl Network {
+    pub fn rollup(self) -> Option<Rollup> {
+        match self {
+            Network::Optimism | Network::Base => Some(Rollup::OpStack),
+            Network::Arbitrum => Some(Rollup::Arbitrum),
+            Network::Mainnet | Network::Polygon | Network::Gnosis => None,
+        }
+    }
+}
+
+/// Settings of the L1 data fee in `eth-gas-delta.toml`.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct L1Config {
+    /// Calldata bytes of a call, 100 by default.
+    pub calldata: Option<u64>,
+    /// Calldata bytes of single entries, e.g. `"Vault.deposit" = 68`, and deployments.
+    pub sizes: HashMap<String, u64>,
+    pub base_fee_scalar: Option<f64>,
+    pub blob_base_fee_scalar: Option<f64>,
+}
+
+/// The fee a rollup charges per call for its data on L1, on top of the execution gas.
+#[derive(Debug, Clone, PartialEq)]
+pub struct L1Fee {
+    pub rollup: Rollup,
+    /// L1 base fee in gwei.
+    pub base_fee: f64,
+    /// L1 blob base fee in gwei, used by OP Stack chains.
+    pub blob_base_fee: f64,
+    pub base_fee_scalar: f64,
+    pub blob_base_fee_scalar: f64,
+    pub calldata: u64,
+    pub sizes: HashMap<String, u64>,
+}
+
+impl L1Fee {
+    pub fn new(rollup: Rollup, base_fee: f64, blob_base_fee: f64, config: &L1Config) -> Self {
+        L1Fee {
+            rollup,
+            base_fee,
+            blob_base_fee,
+            base_fee_scalar: config.base_fee_scalar.unwrap_or(BASE_FEE_SCALAR),
+            blob_base_fee_scalar: config.blob_base_fee_scalar.unwrap_or(BLOB_BASE_FEE_SCALAR),
+            calldata: config.calldata.unwrap_or(CALLDATA),
+            sizes: config.sizes.clone(),
+        }
+    }
+
+    /// The data fee of one call of an entry in ETH.
+    pub fn eth(&self, name: &str) -> f64 {
+        let calldata = self.sizes.get(name).copied().unwrap_or(self.calldata);
+        let size = (calldata + TX_OVERHEAD) as f64;
+        let gwei = match self.rollup {
+            Rollup::OpStack => {
+                size * (16.0 * self.base_fee_scalar * self.base_fee
+                    + self.blob_base_fee_scalar * self.blob_base_fee)
+                    / 1e6
+            }
+            Rollup::Arbitrum => size * 16.0 * self.base_fee,
+        };
+        gwei * 1e-9
+    }
+}
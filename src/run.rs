@@ -0,0 +1,150 @@
+use std::{fs, io::ErrorKind, process::Command};
+
+use anyhow::{bail, Context, Result};
+use eth_gas_delta::{report, GasReport};
+
+/// The checked out ref and stashed changes, restored by [`Worktree::restore`]. The report file
+/// is kept out of the stash, as every run writes it, and put back as it was.
+struct Worktree {
+    original: String,
+    stashed: bool,
+    report_path: String,
+    report: Option<Vec<u8>>,
+}
+
+impl Worktree {
+    fn save(report_path: &str) -> Result<Self> {
+        let branch = git(&["rev-parse", "--abbrev-ref", "HEAD"])?;
+        let original = if branch == "HEAD" {
+            git(&["rev-parse", "HEAD"])?
+        } else {
+            branch
+        };
+        let report = fs::read(report_path).ok();
+        discard(report_path)?;
+        let stashed = !git(&["status", "--porcelain"])?.is_empty();
+        if stashed {
+            git(&[
+                "stash",
+                "push",
+                "--include-untracked",
+                "-m",
+                "eth-gas-delta run",
+            ])?;
+        }
+        Ok(Worktree {
+            original,
+            stashed,
+            report_path: report_path.to_owned(),
+            report,
+        })
+    }
+
+    fn checkout(&self, git_ref: &str) -> Result<()> {
+        discard(&self.report_path)?;
+        git(&["checkout", "--quiet", git_ref]).map(drop)
+    }
+
+    /// Checks out the original ref again and applies the stashed changes.
+    fn restore(self) -> Result<()> {
+        self.checkout(&self.original)
+            .context("cannot check out the original ref")?;
+        if self.stashed {
+            git(&["stash", "pop", "--quiet"]).context(
+                "cannot apply the changes, they are kept in the stash `eth-gas-delta run`",
+            )?;
+        }
+        if let Some(report) = &self.report {
+            fs::write(&self.report_path, report)
+                .with_context(|| format!("cannot restore {}", self.report_path))?;
+        }
+        Ok(())
+    }
+}
+
+/// Removes the report a run wrote, or undoes its changes if the file is tracked, so that it
+/// neither ends up in the stash nor blocks a checkout.
+fn discard(report_path: &str) -> Result<()> {
+    if git(&["ls-files", "--error-unmatch", "--", report_path]).is_ok() {
+        return git(&["checkout", "--quiet", "--", report_path]).map(drop);
+    }
+    match fs::remove_file(report_path) {
+        Err(e) if e.kind() != ErrorKind::NotFound => {
+            Err(e).with_context(|| format!("cannot remove {}", report_path))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Runs `command` on `base` and on `head` (or the current working tree) and returns both reports.
+/// The working tree is restored afterwards, failing if it can't be.
+pub fn collect(
+    base: &str,
+    head: Option<&str>,
+    command: &[String],
+    report_path: &str,
+) -> Result<Vec<(String, GasReport)>> {
+    // Without a head ref, the current working tree is measured before anything is stashed.
+    let current = match head {
+        None => Some(produce(command, report_path).context("on the working tree")?),
+        Some(_) => None,
+    };
+
+    let worktree = Worktree::save(report_path)?;
+    let measured = (|| {
+        worktree.checkout(base)?;
+        let base_report = produce(command, report_path).with_context(|| format!("on {}", base))?;
+        let head_report = match (head, current) {
+            (Some(head), _) => {
+                worktree.checkout(head)?;
+                produce(command, report_path).with_context(|| format!("on {}", head))?
+            }
+            (None, Some(current)) => current,
+            (None, None) => unreachable!(),
+        };
+        Ok(vec![
+            (base.to_owned(), base_report),
+            (head.unwrap_or("working tree").to_owned(), head_report),
+        ])
+    })();
+    let restored = worktree
+        .restore()
+        .context("cannot restore the working tree");
+    match (measured, restored) {
+        (measured, Ok(())) => measured,
+        (Ok(_), Err(e)) => Err(e),
+        (Err(e), Err(restore)) => {
+            log::error!("{:#}", e);
+            Err(restore)
+        }
+    }
+}
+
+fn produce(command: &[String], report_path: &str) -> Result<GasReport> {
+    let (program, args) = command.split_first().context("no command given")?;
+    let status = Command::new(program)
+        .args(args)
+        .status()
+        .with_context(|| format!("cannot run {}", program))?;
+    if !status.success() {
+        bail!("{} failed with {}", program, status);
+    }
+    let bytes = fs::read(report_path)
+        .with_context(|| format!("{} wrote no report to {}", program, report_path))?;
+    report::parse(&bytes).with_context(|| format!("cannot parse {}", report_path))
+}
+
+fn git(args: &[&str]) -> Result<String> {
+    let output = Command::new("git")
+        .args(args)
+        .output()
+        .context("cannot run git")?;
+    if !output.status.success() {
+        bail!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_owned())
+}
@@ -0,0 +1,257 @@
+use std::{
+    io::{BufRead, BufReader, Write},
+    net::{TcpListener, TcpStream},
+    sync::{Mutex, PoisonError},
+    thread,
+    time::Duration,
+};
+
+use anyhow::{Context, Result};
+use eth_gas_delta::{
+    output::{self, Align, TableStyle},
+    Comparison, Format,
+};
+
+use crate::watch;
+
+/// Reloads the page once the comparison behind it changed, and opens the page of an entry when
+/// its name is clicked.
+const SCRIPT: &str = r#"
+<script>
+let version = null;
+setInterval(async () => {
+  const latest = await fetch("/version").then((r) => r.text()).catch(() => version);
+  if (version !== null && latest !== version) location.reload();
+  version = latest;
+}, 1000);
+for (const row of document.getElementById("comparison").tBodies[0].rows) {
+  row.cells[0].style.cursor = "pointer";
+  row.cells[0].addEventListener("click", () => {
+    location = "/entry?name=" + encodeURIComponent(row.dataset.name);
+  });
+}
+</script>
+"#;
+
+/// How long a client may take to send its request or read the answer before it is dropped.
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The latest comparison, or why it couldn't be made, and how often it was made.
+struct State {
+    comparison: Result<Comparison, String>,
+    version: u64,
+}
+
+/// Serves the comparison `load` returns as a web page on `address`, comparing again whenever one
+/// of the files changes. Every connection is answered on a thread of its own.
+pub fn serve(
+    address: &str,
+    files: &[&str],
+    load: impl Fn() -> Result<Comparison> + Sync,
+) -> Result<()> {
+    let listener =
+        TcpListener::bind(address).with_context(|| format!("cannot listen on {}", address))?;
+    let state = Mutex::new(State {
+        comparison: Err("not compared yet".to_owned()),
+        version: 0,
+    });
+    eprintln!("Serving on http://{}", listener.local_addr()?);
+    thread::scope(|scope| {
+        scope.spawn(|| {
+            let watched = watch::on_change(files, || {
+                let comparison = load();
+                let mut state = state.lock().unwrap_or_else(PoisonError::into_inner);
+                state.version += 1;
+                match comparison {
+                    Ok(comparison) => {
+                        state.comparison = Ok(comparison);
+                        Ok(())
+                    }
+                    Err(e) => {
+                        state.comparison = Err(format!("{:#}", e));
+                        Err(e)
+                    }
+                }
+            });
+            if let Err(e) = watched {
+                log::error!("cannot watch the files: {:#}", e);
+            }
+        });
+        for stream in listener.incoming() {
+            let state = &state;
+            scope.spawn(move || {
+                let handled = stream
+                    .map_err(anyhow::Error::from)
+                    .and_then(|stream| handle(stream, state));
+                if let Err(e) = handled {
+                    log::debug!("cannot answer a request: {:#}", e);
+                }
+            });
+        }
+        Ok(())
+    })
+}
+
+fn handle(mut stream: TcpStream, state: &Mutex<State>) -> Result<()> {
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    stream.set_write_timeout(Some(TIMEOUT))?;
+    let target = target(BufReader::new(&stream))?;
+    let (path, query) = target.split_once('?').unwrap_or((&target, ""));
+    log::debug!("GET {}", target);
+
+    let state = state.lock().unwrap_or_else(PoisonError::into_inner);
+    let (status, content_type, body) = match (path, &state.comparison) {
+        ("/version", _) => ("200 OK", "text/plain", state.version.to_string()),
+        (_, Err(e)) => ("500 Internal Server Error", "text/html", page("Error", e)),
+        ("/", Ok(comparison)) => {
+            let html = Format::Html
+                .render(comparison, &Default::default())
+                .map_err(anyhow::Error::msg)?;
+            let html = html.replacen("</body>", &format!("{}</body>", SCRIPT), 1);
+            ("200 OK", "text/html", html)
+        }
+        ("/comparison.json", Ok(comparison)) => (
+            "200 OK",
+            "application/json",
+            Format::Json
+                .render(comparison, &Default::default())
+                .map_err(anyhow::Error::msg)?,
+        ),
+        ("/entry", Ok(comparison)) => {
+            let name = query
+                .split('&')
+                .find_map(|pair| pair.strip_prefix("name="))
+                .map(decode)
+                .unwrap_or_default();
+            match comparison.entries.iter().find(|row| row.name == name) {
+                Some(row) => {
+                    let mut text = output::histogram(comparison, row);
+                    if row
+                        .cells
+                        .iter()
+                        .flatten()
+                        .any(|cell| !cell.tests.is_empty())
+                    {
+                        text += "\n\n";
+                        text += &output::by_test(comparison, row, TableStyle::Ascii);
+                    }
+                    if comparison.files.len() > 1 {
+                        text += "\n\n";
+                        text += &output::explain(comparison, row, Align::Index, TableStyle::Ascii);
+                    }
+                    ("200 OK", "text/html", page(&row.name, &text))
+                }
+                None => (
+                    "404 Not Found",
+                    "text/html",
+                    page("Not found", &format!("no entry named {}", name)),
+                ),
+            }
+        }
+        _ => ("404 Not Found", "text/html", page("Not found", &target)),
+    };
+    drop(state);
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        body
+    )?;
+    Ok(())
+}
+
+/// Reads a request up to the end of its headers, returning the requested path and query.
+fn target(mut reader: impl BufRead) -> Result<String> {
+    let mut request = String::new();
+    reader.read_line(&mut request)?;
+    // The headers are not needed, but must be read before answering.
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 2 {
+        header.clear();
+    }
+    Ok(request.split_whitespace().nth(1).unwrap_or("/").to_owned())
+}
+
+/// A page showing preformatted text, with a link back to the comparison.
+fn page(title: &str, text: &str) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{0}</title>\n</head>\n\
+         <body>\n<p><a href=\"/\">All entries</a></p>\n<h1>{0}</h1>\n<pre>{1}</pre>\n</body>\n</html>",
+        output::escape(title),
+        output::escape(text)
+    )
+}
+
+/// Decodes a percent-encoded query value.
+fn decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = vec![];
+    let mut index = 0;
+    while index < bytes.len() {
+        let hex = bytes
+            .get(index + 1..index + 3)
+            .and_then(|hex| u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok());
+        match (bytes[index], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                index += 3;
+            }
+            (b'+', _) => {
+                decoded.push(b' ');
+                index += 1;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                index += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_the_target_of_a_request() {
+        let request =
+            "GET /entry?name=Vault.deposit HTTP/1.1\r\nHost: localhost\r\nAccept: */*\r\n\r\n";
+        assert_eq!(
+            target(request.as_bytes()).unwrap(),
+            "/entry?name=Vault.deposit"
+        );
+        assert_eq!(target("".as_bytes()).unwrap(), "/");
+        assert_eq!(target("GET\r\n\r\n".as_bytes()).unwrap(), "/");
+    }
+
+    #[test]
+    fn decodes_query_values() {
+        assert_eq!(decode("Vault%2Edeposit"), "Vault.deposit");
+        assert_eq!(decode("a+b%3C%22"), "a b<\"");
+        assert_eq!(decode("100%"), "100%");
+        assert_eq!(decode("%zz%4"), "%zz%4");
+    }
+
+    #[test]
+    fn escapes_pages() {
+        let page = page("<b>Vault</b>", "a < b & \"c\"");
+        assert!(
+            page.contains("<title>&lt;b&gt;Vault&lt;/b&gt;</title>"),
+            "{}",
+            page
+        );
+        assert!(
+            page.contains("<h1>&lt;b&gt;Vault&lt;/b&gt;</h1>"),
+            "{}",
+            page
+        );
+        assert!(
+            page.contains("<pre>a &lt; b &amp; &quot;c&quot;</pre>"),
+            "{}",
+            page
+        );
+    }
+}
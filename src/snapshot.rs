@@ -0,0 +1,120 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::report::{Entry, GasReport, Info, MethodIdentifier, RawDeployment, RawMethod};
+
+pub const SCHEMA: &str = "eth-gas-delta/snapshot";
+pub const VERSION: u32 = 1;
+
+/// A compact, normalized baseline: one averaged gas value per entry, sorted by name.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Snapshot {
+    schema: String,
+    version: u32,
+    methods: Vec<SnapshotMethod>,
+    deployments: Vec<SnapshotDeployment>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct SnapshotMethod {
+    contract: String,
+    method: String,
+    signature: String,
+    gas: u64,
+    calls: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct SnapshotDeployment {
+    name: String,
+    gas: u64,
+    /// Size of the deployed code in bytes.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    size: Option<usize>,
+}
+
+impl Snapshot {
+    pub fn from_report(report: GasReport) -> Self {
+        let mut methods: Vec<_> = report
+            .info
+            .methods
+            .into_values()
+            .filter(|m| !m.gas_data.is_empty())
+            .map(|m| SnapshotMethod {
+                gas: Entry::Method(m.clone()).avg_gas(),
+                contract: m.method.contract,
+                method: m.method.method,
+                signature: m.signature,
+                calls: m.number_of_calls,
+            })
+            .collect();
+        methods.sort_by(|l, r| (&l.contract, &l.signature).cmp(&(&r.contract, &r.signature)));
+
+        let mut deployments: Vec<_> = report
+            .info
+            .deployments
+            .into_iter()
+            .filter(|d| !d.gas_data.is_empty())
+            .map(|d| SnapshotDeployment {
+                gas: Entry::Deployment(d.clone()).avg_gas(),
+                size: d.code_size(),
+                name: d.name,
+            })
+            .collect();
+        deployments.sort_by(|l, r| l.name.cmp(&r.name));
+
+        Snapshot {
+            schema: SCHEMA.to_owned(),
+            version: VERSION,
+            methods,
+            deployments,
+        }
+    }
+
+    /// Converts the snapshot back into a report with a single averaged sample per entry.
+    pub fn into_report(self) -> Result<GasReport, String> {
+        if self.schema != SCHEMA || self.version > VERSION {
+            return Err(format!(
+                "unsupported snapshot: {} version {}",
+                self.schema, self.version
+            ));
+        }
+        let methods: HashMap<String, RawMethod> = self
+            .methods
+            .into_iter()
+            .map(|m| {
+                let key = format!("{}_{}", m.contract, m.signature);
+                let method = RawMethod {
+                    key: key.clone(),
+                    method: MethodIdentifier {
+                        contract: m.contract,
+                        method: m.method,
+                    },
+                    signature: m.signature,
+                    gas_data: vec![m.gas].into(),
+                    number_of_calls: m.calls,
+                    tests: HashMap::new(),
+                };
+                (key, method)
+            })
+            .collect();
+        let deployments = self
+            .deployments
+            .into_iter()
+            .map(|d| RawDeployment {
+                name: d.name,
+                gas_data: vec![d.gas].into(),
+                deployed_bytecode: None,
+                code_size: d.size,
+            })
+            .collect();
+
+        Ok(GasReport {
+            info: Info {
+                methods,
+                deployments,
+            },
+        })
+    }
+}
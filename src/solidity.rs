@@ -0,0 +1,180 @@
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap},
+    fs,
+    hash::{Hash, Hasher},
+    path::Path,
+};
+
+use anyhow::{Context, Result};
+use eth_gas_delta::{comparison::Location, Comparison};
+use regex::Regex;
+use serde::Deserialize;
+
+/// The parts of a hardhat or foundry artifact that tell where a contract is defined and what it
+/// deploys.
+#[derive(Deserialize)]
+struct Artifact {
+    #[serde(rename = "contractName")]
+    contract_name: Option<String>,
+    /// Set by hardhat.
+    #[serde(rename = "sourceName")]
+    source_name: Option<String>,
+    /// Set by foundry, which names the file after the contract.
+    ast: Option<Ast>,
+    #[serde(rename = "deployedBytecode")]
+    deployed_bytecode: Option<Bytecode>,
+}
+
+/// Hex code, as hardhat writes it, or an object with the code, as foundry does.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Bytecode {
+    Hex(String),
+    Object { object: String },
+}
+
+#[derive(Deserialize)]
+struct Ast {
+    #[serde(rename = "absolutePath")]
+    absolute_path: String,
+}
+
+/// Sets the [`Location`] of every entry whose contract has an artifact in `dir`, hardhat's
+/// `artifacts` or foundry's `out`. Paths in the artifacts are relative to the project, the
+/// directory containing `dir`. Methods inherited from another file point at their contract.
+pub fn locate(comparison: &mut Comparison, dir: &str) -> Result<()> {
+    let root = Path::new(dir).parent().unwrap_or(Path::new(""));
+    let sources = sources(dir)?;
+    let mut texts: HashMap<&str, Option<String>> = HashMap::new();
+    for row in &mut comparison.entries {
+        let Some(source) = sources.get(&row.contract) else {
+            continue;
+        };
+        let file = root.join(source).to_string_lossy().into_owned();
+        let text = texts.entry(source).or_insert_with(|| {
+            let text = fs::read_to_string(&file);
+            text.map_err(|e| log::warn!("cannot read {}: {}", file, e))
+                .ok()
+        });
+        let Some(text) = text else {
+            continue;
+        };
+        let Some(line) = line(text, &row.contract, row.method.as_deref()) else {
+            log::debug!("{} is not defined in {}", row.contract, file);
+            continue;
+        };
+        row.location = Some(Location { file, line });
+    }
+    Ok(())
+}
+
+/// Whether the deployed bytecode of each contract with an artifact in both directories differs
+/// between them, ignoring the metadata the compiler appends.
+pub fn code_changes(before: &str, after: &str) -> Result<HashMap<String, bool>> {
+    let before = code_hashes(before)?;
+    let after = code_hashes(after)?;
+    Ok(after
+        .into_iter()
+        .filter_map(|(name, hash)| Some((name.clone(), before.get(&name)? != &hash)))
+        .collect())
+}
+
+/// Hash of the deployed bytecode of every contract with an artifact and code, the first one by
+/// path for duplicate names. Interfaces and abstract contracts have none.
+fn code_hashes(dir: &str) -> Result<HashMap<String, u64>> {
+    let mut hashes = HashMap::new();
+    for (name, artifact) in artifacts(dir)? {
+        let code = match artifact.deployed_bytecode {
+            Some(Bytecode::Hex(code) | Bytecode::Object { object: code }) => code,
+            None => continue,
+        };
+        let code = code.trim_start_matches("0x").to_lowercase();
+        if code.is_empty() {
+            continue;
+        }
+        let mut hasher = DefaultHasher::new();
+        without_metadata(&code).hash(&mut hasher);
+        hashes.entry(name).or_insert(hasher.finish());
+    }
+    Ok(hashes)
+}
+
+/// Code without the CBOR metadata at its end, whose last two bytes are its length. It holds a hash
+/// of the sources, which changes with every comment.
+fn without_metadata(code: &str) -> &str {
+    let length = code
+        .get(code.len().saturating_sub(4)..)
+        .and_then(|length| usize::from_str_radix(length, 16).ok());
+    match length.and_then(|length| code.len().checked_sub(2 * length + 4)) {
+        // A CBOR map, of at most 23 entries.
+        Some(end) if code[end..].starts_with('a') => &code[..end],
+        _ => code,
+    }
+}
+
+/// The source file of every contract with an artifact, the first one by path for duplicate names.
+fn sources(dir: &str) -> Result<HashMap<String, String>> {
+    let mut sources = HashMap::new();
+    for (name, artifact) in artifacts(dir)? {
+        let source = artifact
+            .source_name
+            .or(artifact.ast.map(|ast| ast.absolute_path));
+        if let Some(source) = source {
+            sources.entry(name).or_insert(source);
+        }
+    }
+    log::info!(
+        "found the sources of {} contracts in {}",
+        sources.len(),
+        dir
+    );
+    Ok(sources)
+}
+
+/// The artifacts in `dir` by contract name, sorted by path. Files that aren't artifacts are
+/// skipped.
+fn artifacts(dir: &str) -> Result<Vec<(String, Artifact)>> {
+    let pattern = format!("{}/**/*.json", glob::Pattern::escape(dir));
+    let mut paths: Vec<_> = glob::glob(&pattern)
+        .with_context(|| format!("invalid artifact directory {}", dir))?
+        .flatten()
+        .filter(|path| !path.to_string_lossy().ends_with(".dbg.json"))
+        .collect();
+    paths.sort();
+    let mut artifacts = vec![];
+    for path in paths {
+        let Ok(bytes) = fs::read(&path) else {
+            continue;
+        };
+        let Ok(mut artifact) = serde_json::from_slice::<Artifact>(&bytes) else {
+            continue;
+        };
+        if let Some(name) = artifact.contract_name.take().or_else(|| stem(&path)) {
+            artifacts.push((name, artifact));
+        }
+    }
+    Ok(artifacts)
+}
+
+fn stem(path: &Path) -> Option<String> {
+    Some(path.file_stem()?.to_string_lossy().into_owned())
+}
+
+/// The line declaring a method in a contract, or the contract itself, counting from 1.
+fn line(text: &str, contract: &str, method: Option<&str>) -> Option<usize> {
+    let declaration = Regex::new(&format!(
+        r"^\s*(abstract\s+)?(contract|library|interface)\s+{}\b",
+        regex::escape(contract)
+    ))
+    .ok()?;
+    let start = text.lines().position(|line| declaration.is_match(line))?;
+    let function = method.and_then(|method| {
+        let function = Regex::new(&format!(r"\bfunction\s+{}\s*\(", regex::escape(method))).ok()?;
+        let offset = text
+            .lines()
+            .skip(start)
+            .position(|line| function.is_match(line))?;
+        Some(start + offset)
+    });
+    Some(function.unwrap_or(start) + 1)
+}
@@ -0,0 +1,473 @@
+use std::{fmt, str::FromStr};
+
+use clap::ValueEnum;
+use serde::{de, Deserialize, Deserializer, Serialize};
+
+use crate::comparison::Cell;
+
+/// Statistic that summarizes the gas samples of an entry.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Aggregate {
+    /// Arithmetic mean
+    #[default]
+    Mean,
+    /// Middle sample, or the mean of the two middle samples
+    Median,
+    /// Smallest sample
+    Min,
+    /// Largest sample
+    Max,
+}
+
+impl Aggregate {
+    /// Summarizes non-empty samples.
+    pub fn apply(self, samples: &[u64]) -> u64 {
+        match self {
+            Aggregate::Mean => mean(samples),
+            Aggregate::Median => {
+                let mut sorted = samples.to_vec();
+                sorted.sort_unstable();
+                let mid = sorted.len() / 2;
+                if sorted.len().is_multiple_of(2) {
+                    ((sorted[mid - 1] as u128 + sorted[mid] as u128) / 2) as u64
+                } else {
+                    sorted[mid]
+                }
+            }
+            Aggregate::Min => *samples.iter().min().unwrap(),
+            Aggregate::Max => *samples.iter().max().unwrap(),
+        }
+    }
+}
+
+/// Samples left out before aggregating, so single pathological calls don't dominate.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Outliers {
+    /// Drop this percentage of the samples from both the top and the bottom.
+    Trim(f64),
+    /// Drop samples more than 1.5 interquartile ranges outside the quartiles.
+    Iqr,
+}
+
+impl FromStr for Outliers {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s.eq_ignore_ascii_case("iqr") {
+            return Ok(Outliers::Iqr);
+        }
+        match s.strip_suffix('%').unwrap_or(s).trim().parse() {
+            Ok(percent) if (0.0..50.0).contains(&percent) => Ok(Outliers::Trim(percent)),
+            _ => Err(format!(
+                "expected iqr or a percentage below 50% to trim: {}",
+                s
+            )),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Outliers {
+    /// Accepts `"iqr"` or a percentage to trim like `"10%"`.
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(de::Error::custom)
+    }
+}
+
+impl Outliers {
+    /// The samples that are kept, never fewer than one.
+    pub fn filter(self, samples: &[u64]) -> Vec<u64> {
+        let mut sorted = samples.to_vec();
+        sorted.sort_unstable();
+        match self {
+            Outliers::Trim(percent) => {
+                let drop = (sorted.len() as f64 * percent / 100.0) as usize;
+                let drop = drop.min((sorted.len() - 1) / 2);
+                sorted[drop..sorted.len() - drop].to_vec()
+            }
+            Outliers::Iqr => {
+                let q1 = percentile(&sorted, 25.0) as f64;
+                let q3 = percentile(&sorted, 75.0) as f64;
+                let fence = 1.5 * (q3 - q1);
+                sorted
+                    .into_iter()
+                    .filter(|&gas| (q1 - fence..=q3 + fence).contains(&(gas as f64)))
+                    .collect()
+            }
+        }
+    }
+}
+
+/// Exact statistics of a stream of samples.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Summary {
+    pub count: usize,
+    pub sum: u128,
+    pub min: u64,
+    pub max: u64,
+}
+
+impl Summary {
+    /// Statistics of the samples, `None` if there are none.
+    pub fn of(samples: &[u64]) -> Option<Summary> {
+        Some(Summary {
+            count: samples.len(),
+            sum: samples.iter().map(|&sample| sample as u128).sum(),
+            min: *samples.iter().min()?,
+            max: *samples.iter().max()?,
+        })
+    }
+
+    pub fn combine(self, other: Summary) -> Summary {
+        Summary {
+            count: self.count + other.count,
+            sum: self.sum + other.sum,
+            min: self.min.min(other.min),
+            max: self.max.max(other.max),
+        }
+    }
+
+    /// Arithmetic mean, rounded down.
+    pub fn mean(&self) -> u64 {
+        (self.sum / self.count as u128) as u64
+    }
+}
+
+/// Running statistics over a stream of samples that keeps at most `capacity` of them, spread evenly
+/// over the stream so that percentiles can still be estimated.
+#[derive(Debug)]
+pub struct Sketch {
+    capacity: usize,
+    /// Every `stride`th sample is kept.
+    stride: usize,
+    seen: usize,
+    summary: Option<Summary>,
+    samples: Vec<u64>,
+}
+
+impl Sketch {
+    pub fn new(capacity: usize) -> Self {
+        Sketch {
+            capacity: capacity.max(2),
+            stride: 1,
+            seen: 0,
+            summary: None,
+            samples: vec![],
+        }
+    }
+
+    pub fn push(&mut self, sample: u64) {
+        let single = Summary {
+            count: 1,
+            sum: sample as u128,
+            min: sample,
+            max: sample,
+        };
+        self.summary = Some(
+            self.summary
+                .map_or(single, |summary| summary.combine(single)),
+        );
+        if self.seen.is_multiple_of(self.stride) {
+            self.samples.push(sample);
+            if self.samples.len() > self.capacity {
+                // Keeping every other sample doubles the stride for those already kept.
+                let mut index = 0;
+                self.samples.retain(|_| {
+                    index += 1;
+                    index % 2 == 1
+                });
+                self.stride *= 2;
+            }
+        }
+        self.seen += 1;
+    }
+
+    /// The kept samples and, if some were left out, the statistics of all of them.
+    pub fn finish(self) -> (Vec<u64>, Option<Summary>) {
+        let summary = self
+            .summary
+            .filter(|summary| summary.count > self.samples.len());
+        (self.samples, summary)
+    }
+}
+
+/// Sum of the samples, saturating at `u64::MAX`.
+pub fn sum(samples: &[u64]) -> u64 {
+    let sum: u128 = samples.iter().map(|&sample| sample as u128).sum();
+    u64::try_from(sum).unwrap_or(u64::MAX)
+}
+
+/// Arithmetic mean of non-empty samples, rounded down. Summed in 128 bits, so it cannot overflow.
+pub fn mean(samples: &[u64]) -> u64 {
+    let sum: u128 = samples.iter().map(|&sample| sample as u128).sum();
+    (sum / samples.len() as u128) as u64
+}
+
+/// Change from one gas value to another, saturating at the bounds of `i64`.
+pub fn delta(from: u64, to: u64) -> i64 {
+    let delta = to as i128 - from as i128;
+    delta.clamp(i64::MIN as i128, i64::MAX as i128) as i64
+}
+
+/// Sample at the given percentile (0 to 100) of non-empty samples, using the nearest rank.
+pub fn percentile(samples: &[u64], percent: f64) -> u64 {
+    let mut sorted = samples.to_vec();
+    sorted.sort_unstable();
+    let rank = (percent / 100.0 * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+/// Population standard deviation of non-empty samples.
+pub fn stddev(samples: &[u64]) -> f64 {
+    let len = samples.len() as f64;
+    let mean = samples.iter().map(|&sample| sample as f64).sum::<f64>() / len;
+    let variance = samples
+        .iter()
+        .map(|&sample| (sample as f64 - mean).powi(2))
+        .sum::<f64>()
+        / len;
+    variance.sqrt()
+}
+
+/// Gaps between sorted samples of at least this share of the median separate modes.
+const MODE_GAP: f64 = 0.1;
+
+/// The mean and size of each cluster of samples separated from the others by a clear gap, e.g. of
+/// cold and warm storage accesses, sorted by gas. Clusters of fewer than two samples or a tenth of
+/// all are outliers rather than modes and left out.
+pub fn modes(samples: &[u64]) -> Vec<(u64, usize)> {
+    if samples.len() < 4 {
+        return vec![];
+    }
+    let mut sorted = samples.to_vec();
+    sorted.sort_unstable();
+    let gap = (MODE_GAP * percentile(&sorted, 50.0) as f64).max(1.0);
+    let min_size = (samples.len() / 10).max(2);
+    let mut modes = vec![];
+    let mut start = 0;
+    for end in 1..=sorted.len() {
+        if end == sorted.len() || (sorted[end] - sorted[end - 1]) as f64 >= gap {
+            if end - start >= min_size {
+                modes.push((mean(&sorted[start..end]), end - start));
+            }
+            start = end;
+        }
+    }
+    modes
+}
+
+/// Half the width of the 95% confidence interval of the difference of the means of `a` and `b`,
+/// which need at least two samples each. Uses Welch's standard error and the normal quantile.
+pub fn confidence_interval(a: &[u64], b: &[u64]) -> f64 {
+    let variance = |samples: &[u64]| {
+        let len = samples.len() as f64;
+        stddev(samples).powi(2) * len / (len - 1.0) / len
+    };
+    1.96 * (variance(a) + variance(b)).sqrt()
+}
+
+/// Two-sided p-value of a Mann-Whitney U test, using the normal approximation with tie and
+/// continuity correction.
+pub fn mann_whitney(a: &[u64], b: &[u64]) -> f64 {
+    let mut combined: Vec<(u64, bool)> = a
+        .iter()
+        .map(|&gas| (gas, true))
+        .chain(b.iter().map(|&gas| (gas, false)))
+        .collect();
+    combined.sort_unstable_by_key(|&(gas, _)| gas);
+
+    // Rank the samples, giving tied samples their average rank.
+    let total = combined.len() as f64;
+    let mut rank_sum_a = 0.0;
+    let mut ties = 0.0;
+    let mut start = 0;
+    while start < combined.len() {
+        let end = start
+            + combined[start..]
+                .iter()
+                .take_while(|(gas, _)| *gas == combined[start].0)
+                .count();
+        let rank = (start + end + 1) as f64 / 2.0;
+        let in_a = combined[start..end]
+            .iter()
+            .filter(|(_, in_a)| *in_a)
+            .count();
+        rank_sum_a += rank * in_a as f64;
+        let tied = (end - start) as f64;
+        ties += tied.powi(3) - tied;
+        start = end;
+    }
+
+    let (n_a, n_b) = (a.len() as f64, b.len() as f64);
+    let u = rank_sum_a - n_a * (n_a + 1.0) / 2.0;
+    let mean = n_a * n_b / 2.0;
+    let variance = n_a * n_b / 12.0 * ((total + 1.0) - ties / (total * (total - 1.0)));
+    if variance <= 0.0 {
+        return 1.0;
+    }
+    let z = ((u - mean).abs() - 0.5).max(0.0) / variance.sqrt();
+    erfc(z / std::f64::consts::SQRT_2).min(1.0)
+}
+
+/// Complementary error function, accurate to about 1e-7.
+fn erfc(x: f64) -> f64 {
+    // Numerical Recipes' Chebyshev fit.
+    let t = 1.0 / (1.0 + 0.5 * x.abs());
+    let y = t
+        * (-x * x - 1.26551223
+            + t * (1.00002368
+                + t * (0.37409196
+                    + t * (0.09678418
+                        + t * (-0.18628806
+                            + t * (0.27886807
+                                + t * (-1.13520398
+                                    + t * (1.48851587 + t * (-0.82215223 + t * 0.17087277)))))))))
+            .exp();
+    if x >= 0.0 {
+        y
+    } else {
+        2.0 - y
+    }
+}
+
+/// Additional statistic that can be shown next to the aggregated gas.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Stat {
+    /// Smallest observed gas
+    Min,
+    /// Largest observed gas
+    Max,
+    /// Standard deviation of the observed gas
+    Stddev,
+    /// Median observed gas
+    P50,
+    /// 90th percentile of the observed gas
+    P90,
+    /// 99th percentile of the observed gas
+    P99,
+    /// Sum of the observed gas, i.e. the gas spent by the whole test suite
+    Total,
+    /// Number of calls, highlighted when it differs from the first file
+    Calls,
+}
+
+impl Stat {
+    pub fn of(self, cell: &Cell) -> u64 {
+        match self {
+            Stat::Min => cell.min_gas,
+            Stat::Max => cell.max_gas,
+            Stat::Stddev => cell.stddev.round() as u64,
+            Stat::P50 => cell.p50,
+            Stat::P90 => cell.p90,
+            Stat::P99 => cell.p99,
+            Stat::Total => cell.total_gas,
+            Stat::Calls => cell.calls as u64,
+        }
+    }
+}
+
+impl fmt::Display for Stat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Stat::Min => "min",
+            Stat::Max => "max",
+            Stat::Stddev => "stddev",
+            Stat::P50 => "p50",
+            Stat::P90 => "p90",
+            Stat::P99 => "p99",
+            Stat::Total => "total",
+            Stat::Calls => "calls",
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mean_does_not_overflow() {
+        assert_eq!(mean(&[u64::MAX, u64::MAX, u64::MAX]), u64::MAX);
+        assert_eq!(mean(&[u64::MAX, u64::MAX - 2]), u64::MAX - 1);
+        assert_eq!(Aggregate::Mean.apply(&[u64::MAX, 1]), u64::MAX / 2 + 1);
+    }
+
+    #[test]
+    fn median_does_not_overflow() {
+        assert_eq!(Aggregate::Median.apply(&[u64::MAX, u64::MAX]), u64::MAX);
+        assert_eq!(Aggregate::Median.apply(&[1, 3, u64::MAX, 0]), 2);
+    }
+
+    #[test]
+    fn sum_saturates() {
+        assert_eq!(sum(&[u64::MAX, 1]), u64::MAX);
+        assert_eq!(sum(&[1 << 62, 1 << 62]), 1 << 63);
+        assert_eq!(sum(&[]), 0);
+    }
+
+    #[test]
+    fn delta_saturates() {
+        assert_eq!(delta(100, 150), 50);
+        assert_eq!(delta(150, 100), -50);
+        assert_eq!(delta(0, u64::MAX), i64::MAX);
+        assert_eq!(delta(u64::MAX, 0), i64::MIN);
+        assert_eq!(delta(u64::MAX, u64::MAX - 1), -1);
+    }
+
+    #[test]
+    fn modes_need_clear_gaps() {
+        let bimodal = [23000, 23100, 22900, 43000, 43100, 42900];
+        assert_eq!(modes(&bimodal), [(23000, 3), (43000, 3)]);
+        assert_eq!(modes(&[51000, 52000, 50000, 53000]), [(51500, 4)]);
+        // A single far sample is an outlier, not a mode.
+        assert_eq!(modes(&[23000, 23100, 22900, 43000]), [(23000, 3)]);
+    }
+
+    #[test]
+    fn confidence_interval_shrinks_with_samples() {
+        assert_eq!(confidence_interval(&[100, 100], &[200, 200]), 0.0);
+        let few = confidence_interval(&[90, 110], &[90, 110]);
+        assert!((few - 1.96 * 200f64.sqrt()).abs() < 1e-9);
+        let many: Vec<u64> = [90, 110].repeat(50);
+        assert!(confidence_interval(&many, &many) < few / 5.0);
+    }
+
+    #[test]
+    fn sketch_keeps_exact_summary() {
+        let mut sketch = Sketch::new(100);
+        for sample in 1..=10_000 {
+            sketch.push(sample);
+        }
+        let (samples, summary) = sketch.finish();
+        assert!(samples.len() <= 100);
+        assert_eq!(
+            summary,
+            Some(Summary {
+                count: 10_000,
+                sum: 50_005_000,
+                min: 1,
+                max: 10_000
+            })
+        );
+        let median = percentile(&samples, 50.0);
+        assert!((4_900..=5_100).contains(&median), "median {}", median);
+    }
+
+    #[test]
+    fn sketch_below_capacity_keeps_all_samples() {
+        let mut sketch = Sketch::new(100);
+        for sample in [3, 1, 2] {
+            sketch.push(sample);
+        }
+        assert_eq!(sketch.finish(), (vec![3, 1, 2], None));
+    }
+
+    #[test]
+    fn stddev_of_large_values() {
+        assert_eq!(stddev(&[u64::MAX, u64::MAX]), 0.0);
+        assert!(stddev(&[0, u64::MAX]).is_finite());
+    }
+}
@@ -0,0 +1,37 @@
+use std::fs;
+
+use anyhow::{Context, Result};
+use handlebars::{handlebars_helper, Handlebars, JsonValue};
+
+use eth_gas_delta::Comparison;
+
+handlebars_helper!(signed: |value: JsonValue| match value.as_f64() {
+    Some(value) => format!("{:+}", value),
+    None => String::new(),
+});
+handlebars_helper!(percent: |value: JsonValue| match value.as_f64() {
+    Some(value) => format!("{:+.1}%", value),
+    None => String::new(),
+});
+
+/// Renders the comparison through a Handlebars template, which sees the fields of the JSON output
+/// plus `summary` with the counts of the one-line verdict.
+///
+/// `{{signed delta}}` prints a number with its sign and `{{percent delta_percent}}` like `+1.5%`.
+/// Nothing is HTML-escaped.
+pub fn render(path: &str, comparison: &Comparison) -> Result<String> {
+    let template = fs::read_to_string(path).with_context(|| format!("cannot read {}", path))?;
+    let mut handlebars = Handlebars::new();
+    handlebars.register_escape_fn(handlebars::no_escape);
+    handlebars.register_helper("signed", Box::new(signed));
+    handlebars.register_helper("percent", Box::new(percent));
+    handlebars
+        .register_template_string(path, template)
+        .with_context(|| format!("invalid template {}", path))?;
+
+    let mut data = serde_json::to_value(comparison)?;
+    data["summary"] = serde_json::to_value(comparison.summary())?;
+    handlebars
+        .render(path, &data)
+        .with_context(|| format!("cannot render {}", path))
+}
@@ -0,0 +1,223 @@
+//! Reports from the simulations exported by Tenderly, so that production transactions can be
+//! compared with the numbers of a test suite.
+
+use std::collections::HashMap;
+
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::{
+    parser::ParseOptions,
+    report::{GasReport, Info, MethodIdentifier, ParseError, RawDeployment, RawMethod},
+};
+
+pub(crate) const TENDERLY_FORMAT: &str =
+    "a Tenderly simulation, or a bundle of them in `simulation_results`";
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Export {
+    Bundle { simulation_results: Vec<Value> },
+    Single(Value),
+}
+
+#[derive(Deserialize)]
+struct Simulation {
+    simulation: Summary,
+    transaction: Option<Transaction>,
+    #[serde(default)]
+    contracts: Vec<Contract>,
+}
+
+#[derive(Deserialize)]
+struct Summary {
+    gas_used: u64,
+    /// Empty or missing for contract creations.
+    to: Option<String>,
+    /// Whether the transaction succeeded.
+    #[serde(default = "succeeded")]
+    status: bool,
+    /// Function name Tenderly decoded, also found in the call trace.
+    method: Option<String>,
+    input: Option<String>,
+}
+
+fn succeeded() -> bool {
+    true
+}
+
+#[derive(Deserialize)]
+struct Transaction {
+    transaction_info: Option<TransactionInfo>,
+}
+
+#[derive(Deserialize)]
+struct TransactionInfo {
+    contract_address: Option<String>,
+    call_trace: Option<CallTrace>,
+}
+
+#[derive(Deserialize)]
+struct CallTrace {
+    function_name: Option<String>,
+    #[serde(default)]
+    decoded_input: Vec<Argument>,
+}
+
+#[derive(Deserialize)]
+struct Argument {
+    soltype: SolType,
+}
+
+#[derive(Deserialize)]
+struct SolType {
+    #[serde(rename = "type")]
+    kind: String,
+}
+
+#[derive(Deserialize)]
+struct Contract {
+    address: String,
+    contract_name: String,
+}
+
+impl Simulation {
+    fn info(&self) -> Option<&TransactionInfo> {
+        self.transaction.as_ref()?.transaction_info.as_ref()
+    }
+
+    /// The verified name of the contract at an address, or else the address.
+    fn contract(&self, address: &str) -> String {
+        self.contracts
+            .iter()
+            .find(|contract| contract.address.eq_ignore_ascii_case(address))
+            .map_or_else(|| address.to_lowercase(), |c| c.contract_name.clone())
+    }
+
+    /// The decoded signature of the called function, or else its selector.
+    fn signature(&self) -> String {
+        let trace = self.info().and_then(|info| info.call_trace.as_ref());
+        let name = trace
+            .and_then(|trace| trace.function_name.clone())
+            .or_else(|| self.simulation.method.clone())
+            .filter(|name| !name.is_empty());
+        match (name, trace) {
+            (Some(name), Some(trace)) => {
+                let types: Vec<_> = trace
+                    .decoded_input
+                    .iter()
+                    .map(|argument| argument.soltype.kind.as_str())
+                    .collect();
+                format!("{}({})", name, types.join(","))
+            }
+            (Some(name), None) => name,
+            (None, _) => {
+                let input = self.simulation.input.as_deref().unwrap_or_default();
+                match input.get(..10) {
+                    Some(selector) => selector.to_lowercase(),
+                    None => "fallback()".to_owned(),
+                }
+            }
+        }
+    }
+}
+
+/// Builds a report with one sample per simulated transaction, grouping calls by contract and
+/// function. Reverted simulations are left out.
+pub(crate) fn parse(
+    bytes: &[u8],
+    options: ParseOptions,
+) -> Result<(GasReport, Vec<ParseError>), ParseError> {
+    let export: Export = serde_json::from_slice(bytes).map_err(|e| ParseError {
+        path: String::new(),
+        message: e.to_string(),
+        expected: TENDERLY_FORMAT,
+    })?;
+    let (values, bundled) = match export {
+        Export::Bundle { simulation_results } => (simulation_results, true),
+        Export::Single(value) => (vec![value], false),
+    };
+
+    let mut methods: HashMap<String, RawMethod> = HashMap::new();
+    let mut deployments: HashMap<String, RawDeployment> = HashMap::new();
+    let mut skipped = vec![];
+    for (index, value) in values.into_iter().enumerate() {
+        let simulation: Simulation = match serde_path_to_error::deserialize(value) {
+            Ok(simulation) => simulation,
+            Err(e) => {
+                let mut error = ParseError {
+                    path: e.path().to_string(),
+                    message: e.into_inner().to_string(),
+                    expected: TENDERLY_FORMAT,
+                };
+                if bundled {
+                    error = error.within(&format!("simulation_results[{}]", index));
+                }
+                if !options.lenient {
+                    return Err(error);
+                }
+                skipped.push(error);
+                continue;
+            }
+        };
+        let gas_used = simulation.simulation.gas_used;
+        if !simulation.simulation.status {
+            log::warn!("leaving out simulation {}, which reverted", index + 1);
+            continue;
+        }
+        let to = simulation
+            .simulation
+            .to
+            .as_deref()
+            .filter(|to| !to.is_empty());
+        let created = simulation
+            .info()
+            .and_then(|info| info.contract_address.as_deref());
+        match (to, created) {
+            (Some(to), _) => {
+                let contract = simulation.contract(to);
+                let signature = simulation.signature();
+                let key = format!("{}_{}", contract, signature);
+                let method = methods.entry(key.clone()).or_insert_with(|| RawMethod {
+                    key,
+                    method: MethodIdentifier {
+                        contract,
+                        method: signature.split('(').next().unwrap_or_default().to_owned(),
+                    },
+                    signature,
+                    gas_data: vec![].into(),
+                    number_of_calls: 0,
+                    tests: HashMap::new(),
+                });
+                method.gas_data.extend(vec![gas_used].into());
+                method.number_of_calls += 1;
+            }
+            (None, Some(address)) => {
+                let name = simulation.contract(address);
+                deployments
+                    .entry(name.clone())
+                    .or_insert_with(|| RawDeployment {
+                        name,
+                        gas_data: vec![].into(),
+                        deployed_bytecode: None,
+                        code_size: None,
+                    })
+                    .gas_data
+                    .extend(vec![gas_used].into());
+            }
+            (None, None) => log::warn!(
+                "leaving out simulation {}, which neither calls nor creates a contract",
+                index + 1
+            ),
+        }
+    }
+    let mut deployments: Vec<_> = deployments.into_values().collect();
+    deployments.sort_by(|l, r| l.name.cmp(&r.name));
+    let report = GasReport {
+        info: Info {
+            methods,
+            deployments,
+        },
+    };
+    Ok((report, skipped))
+}
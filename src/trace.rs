@@ -0,0 +1,111 @@
+//! Gas of a single transaction broken down by opcode and call frame, from the struct logs of
+//! `debug_traceTransaction`.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::stats;
+
+/// Opcodes that run code in a new call frame.
+const CALLS: [&str; 6] = [
+    "CALL",
+    "CALLCODE",
+    "DELEGATECALL",
+    "STATICCALL",
+    "CREATE",
+    "CREATE2",
+];
+
+/// One executed instruction, as in the `structLogs` of a geth-style trace.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Step {
+    pub op: String,
+    /// Gas left before the instruction.
+    pub gas: u64,
+    /// Cost of the instruction, for calls including the gas passed on to the callee.
+    pub gas_cost: u64,
+    pub depth: usize,
+    /// Stack before the instruction, top last, if the trace has it.
+    #[serde(default)]
+    pub stack: Vec<String>,
+}
+
+/// Gas spent on one opcode or in one contract.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct Usage {
+    pub gas: u64,
+    /// Number of executed instructions, or of entered call frames.
+    pub count: usize,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Breakdown {
+    /// Gas used by the transaction, from its receipt.
+    pub gas_used: u64,
+    pub opcodes: BTreeMap<String, Usage>,
+    /// Gas spent in the code of each called contract, without its callees, by lowercase address
+    /// or `CREATE` for code run while deploying.
+    pub frames: BTreeMap<String, Usage>,
+}
+
+impl Breakdown {
+    /// Attributes the gas of every step to its opcode and call frame. Calls are charged their own
+    /// cost only, the callee's steps are counted on their own.
+    pub fn new(steps: &[Step], to: Option<&str>, gas_used: u64) -> Self {
+        let mut breakdown = Breakdown {
+            gas_used,
+            ..Breakdown::default()
+        };
+        let root = to.map_or("CREATE".to_owned(), str::to_lowercase);
+        let mut frames = vec![root.clone()];
+        breakdown.frames.entry(root).or_default().count += 1;
+        for (index, step) in steps.iter().enumerate() {
+            frames.truncate(step.depth.max(1));
+            let next = steps.get(index + 1);
+            let cost = match next {
+                // Entering a callee, which got the rest of the cost.
+                Some(next) if next.depth > step.depth => step.gas_cost.saturating_sub(next.gas),
+                // Exact for calls that return right away, like those to precompiles.
+                Some(next) if next.depth == step.depth => step.gas.saturating_sub(next.gas),
+                _ => step.gas_cost,
+            };
+            let opcode = breakdown.opcodes.entry(step.op.clone()).or_default();
+            opcode.gas += cost;
+            opcode.count += 1;
+            let frame = frames.last().cloned().unwrap_or_default();
+            breakdown.frames.entry(frame).or_default().gas += cost;
+
+            if next.is_some_and(|next| next.depth > step.depth) && CALLS.contains(&&*step.op) {
+                let callee = match &*step.op {
+                    "CREATE" | "CREATE2" => "CREATE".to_owned(),
+                    // The address is the second argument of all other calls.
+                    _ => step
+                        .stack
+                        .iter()
+                        .rev()
+                        .nth(1)
+                        .map_or("?".to_owned(), |word| address(word)),
+                };
+                breakdown.frames.entry(callee.clone()).or_default().count += 1;
+                frames.push(callee);
+            }
+        }
+        breakdown
+    }
+
+    /// Gas not spent on any instruction: the intrinsic gas of the transaction minus refunds.
+    pub fn intrinsic(&self) -> i64 {
+        let executed = self.opcodes.values().map(|usage| usage.gas);
+        let executed = executed.fold(0u64, u64::saturating_add);
+        stats::delta(executed, self.gas_used)
+    }
+}
+
+/// The address in the low 20 bytes of a stack word.
+fn address(word: &str) -> String {
+    let hex = word.trim_start_matches("0x").trim_start_matches('0');
+    let hex = &hex[hex.len().saturating_sub(40)..];
+    format!("0x{:0>40}", hex.to_lowercase())
+}
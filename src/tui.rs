@@ -0,0 +1,256 @@
+use ratatui::{
+    crossterm::event::{self, Event, KeyCode, KeyEventKind},
+    layout::{Constraint, Layout},
+    style::{Color, Modifier, Style},
+    text::Line,
+    widgets::{Block, Cell as TableCell, Paragraph, Row as TableRow, Table, TableState, Wrap},
+    DefaultTerminal, Frame,
+};
+
+use eth_gas_delta::{
+    comparison::{Cell, Comparison, Row, SortKey, Status},
+    output,
+};
+
+fn next(key: SortKey) -> SortKey {
+    match key {
+        SortKey::Name => SortKey::Gas,
+        SortKey::Gas => SortKey::Delta,
+        SortKey::Delta => SortKey::DeltaPercent,
+        SortKey::DeltaPercent => SortKey::Name,
+    }
+}
+
+fn label(key: SortKey) -> &'static str {
+    match key {
+        SortKey::Name => "name",
+        SortKey::Gas => "gas",
+        SortKey::Delta => "delta",
+        SortKey::DeltaPercent => "delta %",
+    }
+}
+
+struct App<'a> {
+    comparison: &'a Comparison,
+    sort: SortKey,
+    reverse: bool,
+    filter: String,
+    filtering: bool,
+    /// Indices into `comparison.entries` matching the filter, in display order.
+    visible: Vec<usize>,
+    state: TableState,
+}
+
+/// Shows the comparison in an interactive terminal UI until the user quits.
+pub fn run(comparison: &Comparison, sort: SortKey, reverse: bool) -> std::io::Result<()> {
+    let mut app = App {
+        comparison,
+        sort,
+        reverse,
+        filter: String::new(),
+        filtering: false,
+        visible: vec![],
+        state: TableState::default().with_selected(0),
+    };
+    app.refresh();
+    ratatui::run(|terminal| app.event_loop(terminal))
+}
+
+impl App<'_> {
+    fn event_loop(&mut self, terminal: &mut DefaultTerminal) -> std::io::Result<()> {
+        loop {
+            terminal.draw(|frame| self.draw(frame))?;
+            let Event::Key(key) = event::read()? else {
+                continue;
+            };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            if self.filtering {
+                match key.code {
+                    KeyCode::Enter | KeyCode::Esc => self.filtering = false,
+                    KeyCode::Backspace => {
+                        self.filter.pop();
+                    }
+                    KeyCode::Char(c) => self.filter.push(c),
+                    _ => {}
+                }
+                self.refresh();
+                continue;
+            }
+            match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                KeyCode::Down | KeyCode::Char('j') => self.state.select_next(),
+                KeyCode::Up | KeyCode::Char('k') => self.state.select_previous(),
+                KeyCode::PageDown => self.state.scroll_down_by(20),
+                KeyCode::PageUp => self.state.scroll_up_by(20),
+                KeyCode::Home | KeyCode::Char('g') => self.state.select_first(),
+                KeyCode::End | KeyCode::Char('G') => self.state.select_last(),
+                KeyCode::Char('/') => self.filtering = true,
+                KeyCode::Char('s') => {
+                    self.sort = next(self.sort);
+                    self.refresh();
+                }
+                KeyCode::Char('r') => {
+                    self.reverse = !self.reverse;
+                    self.refresh();
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn refresh(&mut self) {
+        let entries = &self.comparison.entries;
+        let mut visible: Vec<usize> = (0..entries.len())
+            .filter(|&i| fuzzy_match(&self.filter, &entries[i].name))
+            .collect();
+        visible.sort_by(|&l, &r| {
+            let order = self.sort.compare(&entries[l], &entries[r]);
+            if self.reverse {
+                order.reverse()
+            } else {
+                order
+            }
+        });
+        self.visible = visible;
+        if self.visible.is_empty() {
+            self.state.select(None);
+        } else if self
+            .state
+            .selected()
+            .is_none_or(|i| i >= self.visible.len())
+        {
+            self.state.select(Some(0));
+        }
+    }
+
+    fn selected(&self) -> Option<&Row> {
+        let index = *self.visible.get(self.state.selected()?)?;
+        Some(&self.comparison.entries[index])
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [filter, body, help] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Min(0),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+        let [table, detail] =
+            Layout::horizontal([Constraint::Percentage(65), Constraint::Percentage(35)])
+                .areas(body);
+
+        let cursor = if self.filtering { "█" } else { "" };
+        frame.render_widget(
+            Paragraph::new(format!("Filter: {}{}", self.filter, cursor)),
+            filter,
+        );
+
+        let header = TableRow::new(
+            std::iter::once("Entry".to_owned())
+                .chain(self.comparison.files.iter().cloned())
+                .collect::<Vec<_>>(),
+        )
+        .style(Style::new().add_modifier(Modifier::BOLD));
+        let rows = self.visible.iter().map(|&i| {
+            let row = &self.comparison.entries[i];
+            let mut cells = vec![TableCell::from(row.name.clone())];
+            cells.extend(row.cells.iter().map(|cell| match cell {
+                Some(cell) => format_cell(cell),
+                None => TableCell::from(""),
+            }));
+            TableRow::new(cells)
+        });
+        let mut widths = vec![Constraint::Fill(2)];
+        widths.extend(self.comparison.files.iter().map(|_| Constraint::Fill(1)));
+        let title = format!(
+            " {} of {} entries, sorted by {}{} ",
+            self.visible.len(),
+            self.comparison.entries.len(),
+            label(self.sort),
+            if self.reverse { " (reversed)" } else { "" }
+        );
+        frame.render_stateful_widget(
+            Table::new(rows, widths)
+                .header(header)
+                .block(Block::bordered().title(title))
+                .row_highlight_style(Style::new().add_modifier(Modifier::REVERSED)),
+            table,
+            &mut self.state,
+        );
+
+        frame.render_widget(
+            Paragraph::new(self.detail())
+                .wrap(Wrap { trim: false })
+                .block(Block::bordered().title(" Calls ")),
+            detail,
+        );
+
+        frame.render_widget(
+            Paragraph::new("↑/↓ move  / filter  s sort  r reverse  q quit")
+                .style(Style::new().fg(Color::DarkGray)),
+            help,
+        );
+    }
+
+    fn detail(&self) -> Vec<Line<'static>> {
+        let Some(row) = self.selected() else {
+            return vec![];
+        };
+        let mut lines = vec![
+            Line::styled(row.name.clone(), Style::new().add_modifier(Modifier::BOLD)),
+            Line::from(""),
+        ];
+        for (cell, file) in row.cells.iter().zip(&self.comparison.files) {
+            lines.push(Line::styled(
+                file.clone(),
+                Style::new().add_modifier(Modifier::UNDERLINED),
+            ));
+            match cell {
+                Some(cell) => {
+                    lines.push(Line::from(format!(
+                        "avg {} gas over {} calls",
+                        cell.avg_gas,
+                        cell.samples.len()
+                    )));
+                    let samples: Vec<String> = cell.samples.iter().map(u64::to_string).collect();
+                    lines.push(Line::from(samples.join(", ")));
+                }
+                None => lines.push(Line::from("not present")),
+            }
+            lines.push(Line::from(""));
+        }
+        let histogram = output::histogram(self.comparison, row);
+        lines.extend(
+            histogram
+                .lines()
+                .skip(1)
+                .map(|line| Line::from(line.to_owned())),
+        );
+        lines
+    }
+}
+
+fn format_cell(cell: &Cell) -> TableCell<'static> {
+    match (cell.delta, cell.delta_percent) {
+        (Some(delta), Some(percent)) => {
+            let style = match cell.status {
+                Status::Increased => Style::new().fg(Color::Red),
+                Status::Decreased => Style::new().fg(Color::Green),
+                _ => Style::new(),
+            };
+            TableCell::from(format!("{:+} ({:+.1}%)", delta, percent)).style(style)
+        }
+        _ => TableCell::from(cell.avg_gas.to_string()),
+    }
+}
+
+/// Whether all characters of `pattern` appear in `text` in order, ignoring case.
+fn fuzzy_match(pattern: &str, text: &str) -> bool {
+    let mut text = text.chars().flat_map(char::to_lowercase);
+    pattern
+        .chars()
+        .flat_map(char::to_lowercase)
+        .all(|p| text.any(|t| t == p))
+}
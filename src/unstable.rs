@@ -0,0 +1,35 @@
+use std::{collections::HashSet, fs, io::ErrorKind};
+
+use anyhow::{Context, Result};
+
+/// Reads a list of unstable entries, one name per line. Empty lines and lines starting with `#`
+/// are skipped.
+pub fn load(path: &str) -> Result<HashSet<String>> {
+    let text = fs::read_to_string(path).with_context(|| format!("cannot read {}", path))?;
+    Ok(text
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::to_owned)
+        .collect())
+}
+
+/// Adds names to the list in `path`, created if missing, so that entries found in earlier runs
+/// stay listed. Returns how many were new.
+pub fn save(path: &str, names: impl IntoIterator<Item = String>) -> Result<usize> {
+    let mut listed = match fs::metadata(path) {
+        Err(e) if e.kind() == ErrorKind::NotFound => HashSet::new(),
+        _ => load(path)?,
+    };
+    let before = listed.len();
+    listed.extend(names);
+    let added = listed.len() - before;
+    let mut sorted: Vec<String> = listed.into_iter().collect();
+    sorted.sort();
+    let text = format!(
+        "# Entries whose gas varies between identical runs, written by `eth-gas-delta flaky`\n{}\n",
+        sorted.join("\n")
+    );
+    fs::write(path, text).with_context(|| format!("cannot write {}", path))?;
+    Ok(added)
+}
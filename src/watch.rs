@@ -0,0 +1,59 @@
+use std::{
+    collections::HashSet,
+    fs,
+    path::{Path, PathBuf},
+    sync::mpsc,
+    time::Duration,
+};
+
+use anyhow::{Context, Result};
+use notify::{RecursiveMode, Watcher};
+
+/// Changes arriving within this window after the first one are handled by a single render.
+const DEBOUNCE: Duration = Duration::from_millis(200);
+
+/// Calls `render` on a cleared screen now and whenever one of the files changes.
+pub fn watch(files: &[&str], mut render: impl FnMut() -> Result<()>) -> Result<()> {
+    on_change(files, || {
+        print!("\x1b[2J\x1b[H");
+        render()
+    })
+}
+
+/// Calls `handle` now and whenever one of the files changes, logging its errors.
+pub fn on_change(files: &[&str], mut handle: impl FnMut() -> Result<()>) -> Result<()> {
+    let paths: HashSet<PathBuf> = files.iter().map(|file| absolute(Path::new(file))).collect();
+    // Watch the directories, as many tools replace files instead of writing to them.
+    let dirs: HashSet<PathBuf> = paths
+        .iter()
+        .filter_map(|path| path.parent().map(Path::to_path_buf))
+        .collect();
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(tx)?;
+    for dir in &dirs {
+        watcher
+            .watch(dir, RecursiveMode::NonRecursive)
+            .with_context(|| format!("cannot watch {}", dir.display()))?;
+    }
+
+    loop {
+        // A report that is still being written fails to parse; keep watching for the next change.
+        if let Err(e) = handle() {
+            eprintln!("error: {:#}", e);
+        }
+
+        loop {
+            let event = rx.recv()??;
+            if event.kind.is_access() || !event.paths.iter().any(|p| paths.contains(p)) {
+                continue;
+            }
+            while rx.recv_timeout(DEBOUNCE).is_ok() {}
+            break;
+        }
+    }
+}
+
+fn absolute(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
@@ -0,0 +1,58 @@
+//! WebAssembly bindings for the eth-gas-delta comparison engine.
+//!
+//! Build the npm package with `wasm-pack build wasm --target nodejs` (or `--target web`).
+
+use eth_gas_delta::{output, report, Comparison, Config, Format};
+use wasm_bindgen::prelude::*;
+
+/// Compares reports given as JSON strings, relative to the first one.
+///
+/// `names` label the reports in the same order. `config` optionally holds the contents of an
+/// `eth-gas-delta.toml`. Returns the comparison as JSON, as with `--format json`.
+#[wasm_bindgen]
+pub fn compare(
+    names: Vec<String>,
+    reports: Vec<String>,
+    config: Option<String>,
+) -> Result<String, JsError> {
+    render(names, reports, "json".to_owned(), config)
+}
+
+/// Like [`compare`], but renders the comparison in any text format supported by `--format`.
+#[wasm_bindgen]
+pub fn render(
+    names: Vec<String>,
+    reports: Vec<String>,
+    format: String,
+    config: Option<String>,
+) -> Result<String, JsError> {
+    let comparison = comparison(names, reports, config)?;
+    let format: Format = format.parse().map_err(|e: String| JsError::new(&e))?;
+    let options = output::Options {
+        color: false,
+        ..Default::default()
+    };
+    format
+        .render(&comparison, &options)
+        .map_err(|e| JsError::new(&e))
+}
+
+fn comparison(
+    names: Vec<String>,
+    reports: Vec<String>,
+    config: Option<String>,
+) -> Result<Comparison, JsError> {
+    if names.len() != reports.len() {
+        return Err(JsError::new("expected one name per report"));
+    }
+    let config = match config {
+        Some(config) => Config::parse(&config)?,
+        None => Config::default(),
+    };
+    let reports = names
+        .into_iter()
+        .zip(reports)
+        .map(|(name, report)| Ok((name, report::parse(report.as_bytes())?)))
+        .collect::<Result<_, JsError>>()?;
+    Ok(Comparison::from_reports(reports, &config))
+}